@@ -0,0 +1,37 @@
+//! Checks that `SUPPORTED_OPS` in `src/protocol.rs` lists exactly the `ButlerRequest`
+//! variants, so `hello` never advertises an op the butler cannot parse.
+//!
+//! The committed protocol artifacts (`schema/butler-protocol.json` and
+//! `../src/butlerProtocol.generated.ts`) are written by `tests/protocol_schema.rs`
+//! with `UPDATE_SCHEMA=1`; a build never touches files outside `OUT_DIR`.
+
+#[allow(dead_code)]
+#[path = "src/protocol.rs"]
+mod protocol;
+
+fn schema_ops(schema: &serde_json::Value) -> Vec<String> {
+    schema["request"]["oneOf"]
+        .as_array()
+        .expect("ButlerRequest schema is a oneOf")
+        .iter()
+        .filter_map(|variant| variant["properties"]["op"]["enum"][0].as_str())
+        .map(str::to_owned)
+        .collect()
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=src/protocol.rs");
+
+    let schema = protocol::protocol_schema();
+    let mut ops = schema_ops(&schema);
+    let mut supported = protocol::SUPPORTED_OPS
+        .iter()
+        .map(|op| (*op).to_owned())
+        .collect::<Vec<_>>();
+    ops.sort();
+    supported.sort();
+    assert_eq!(
+        ops, supported,
+        "SUPPORTED_OPS is out of sync with the ButlerRequest variants"
+    );
+}
@@ -0,0 +1,183 @@
+//! Per-pane and per-tab activity, derived from successive `PaneUpdate`/`TabUpdate`s.
+//!
+//! Zellij reports no timestamps, so the butler stamps what it sees: when a pane or tab
+//! gained or lost focus, and when its visible state (title, command, exit) last
+//! differed from the previous snapshot. Anything that happened before the plugin
+//! loaded is unknown; `first_seen` marks where the record starts.
+//!
+//! Tabs have no stable id, only a position that shifts when an earlier tab closes or
+//! tabs move, so a tab record follows the terminal panes it holds: each snapshot
+//! hands a record to the tab that now has any of its panes.
+
+use crate::protocol::ButlerActivity;
+use std::collections::{BTreeMap, BTreeSet};
+use zellij_tile::prelude::{PaneInfo, PaneManifest, TabInfo};
+
+#[derive(Debug, Default)]
+pub(crate) struct ActivityTracker {
+    panes: BTreeMap<u32, Record>,
+    /// Keyed by current position.
+    tabs: BTreeMap<usize, TabRecord>,
+}
+
+#[derive(Debug)]
+struct TabRecord {
+    record: Record,
+    /// Terminal panes the tab held in the last snapshot.
+    pane_ids: BTreeSet<u32>,
+}
+
+#[derive(Debug)]
+struct Record {
+    first_seen_ms: u128,
+    last_focused_ms: Option<u128>,
+    last_changed_ms: u128,
+    /// Focus time from finished focus spells.
+    focus_ms: u128,
+    focused_since_ms: Option<u128>,
+    fingerprint: String,
+}
+
+impl Record {
+    fn new(now_ms: u128, fingerprint: String) -> Self {
+        Record {
+            first_seen_ms: now_ms,
+            last_focused_ms: None,
+            last_changed_ms: now_ms,
+            focus_ms: 0,
+            focused_since_ms: None,
+            fingerprint,
+        }
+    }
+
+    fn observe(&mut self, now_ms: u128, fingerprint: String, focused: bool) {
+        if fingerprint != self.fingerprint {
+            self.fingerprint = fingerprint;
+            self.last_changed_ms = now_ms;
+        }
+        match (self.focused_since_ms, focused) {
+            (None, true) => self.focused_since_ms = Some(now_ms),
+            (Some(since_ms), false) => {
+                self.focus_ms += now_ms.saturating_sub(since_ms);
+                self.focused_since_ms = None;
+                self.last_focused_ms = Some(now_ms);
+            }
+            _ => {}
+        }
+    }
+
+    fn snapshot(&self, now_ms: u128) -> ButlerActivity {
+        let focused_now_ms = self
+            .focused_since_ms
+            .map_or(0, |since_ms| now_ms.saturating_sub(since_ms));
+        // A focused record is in use right now, however long ago it last changed.
+        let last_active_ms = if self.focused_since_ms.is_some() {
+            now_ms
+        } else {
+            self.last_focused_ms
+                .unwrap_or(self.first_seen_ms)
+                .max(self.last_changed_ms)
+        };
+        ButlerActivity {
+            first_seen_epoch_ms: to_u64(self.first_seen_ms),
+            last_focused_epoch_ms: if self.focused_since_ms.is_some() {
+                Some(to_u64(now_ms))
+            } else {
+                self.last_focused_ms.map(to_u64)
+            },
+            last_changed_epoch_ms: to_u64(self.last_changed_ms),
+            focus_ms: to_u64(self.focus_ms + focused_now_ms),
+            idle_ms: to_u64(now_ms.saturating_sub(last_active_ms)),
+        }
+    }
+}
+
+impl ActivityTracker {
+    /// Folds one snapshot in; panes and tabs that are gone are forgotten. A TabUpdate
+    /// whose PaneUpdate hasn't arrived yet (the manifest has a tab position that no
+    /// longer exists) is skipped, since its panes are still filed under old positions.
+    pub(crate) fn observe(&mut self, now_ms: u128, tabs: &[TabInfo], manifest: &PaneManifest) {
+        if manifest
+            .panes
+            .keys()
+            .any(|position| !tabs.iter().any(|tab| tab.position == *position))
+        {
+            return;
+        }
+        let mut previous_tabs = std::mem::take(&mut self.tabs);
+        let mut seen_panes = Vec::new();
+        for tab in tabs {
+            let panes = manifest
+                .panes
+                .get(&tab.position)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            let mut tab_fingerprint = tab.name.clone();
+            let mut pane_ids = BTreeSet::new();
+            for pane in panes.iter().filter(|pane| !pane.is_plugin) {
+                let fingerprint = pane_fingerprint(pane);
+                tab_fingerprint.push_str(&format!("\n{}:{}", pane.id, fingerprint));
+                let focused = tab.active
+                    && pane.is_focused
+                    && !pane.is_suppressed
+                    && (!pane.is_floating || tab.are_floating_panes_visible);
+                self.panes
+                    .entry(pane.id)
+                    .or_insert_with(|| Record::new(now_ms, fingerprint.clone()))
+                    .observe(now_ms, fingerprint, focused);
+                seen_panes.push(pane.id);
+                pane_ids.insert(pane.id);
+            }
+            // A tab without terminal panes can only be recognized by its position.
+            let previous_position = previous_tabs
+                .iter()
+                .find(|(_, previous)| !previous.pane_ids.is_disjoint(&pane_ids))
+                .or_else(|| {
+                    previous_tabs
+                        .get_key_value(&tab.position)
+                        .filter(|(_, previous)| previous.pane_ids.is_empty())
+                })
+                .map(|(position, _)| *position);
+            let mut record = previous_position
+                .and_then(|position| previous_tabs.remove(&position))
+                .map_or_else(
+                    || Record::new(now_ms, tab_fingerprint.clone()),
+                    |previous| previous.record,
+                );
+            record.observe(
+                now_ms,
+                tab_fingerprint,
+                tab.active || !tab.other_focused_clients.is_empty(),
+            );
+            self.tabs
+                .insert(tab.position, TabRecord { record, pane_ids });
+        }
+        self.panes.retain(|pane_id, _| seen_panes.contains(pane_id));
+    }
+
+    pub(crate) fn pane(&self, pane_id: u32, now_ms: u128) -> Option<ButlerActivity> {
+        self.panes
+            .get(&pane_id)
+            .map(|record| record.snapshot(now_ms))
+    }
+
+    pub(crate) fn tab(&self, position: usize, now_ms: u128) -> Option<ButlerActivity> {
+        self.tabs
+            .get(&position)
+            .map(|tab| tab.record.snapshot(now_ms))
+    }
+}
+
+fn pane_fingerprint(pane: &PaneInfo) -> String {
+    format!(
+        "{}\u{1f}{}\u{1f}{}\u{1f}{:?}",
+        pane.title,
+        pane.terminal_command.as_deref().unwrap_or(""),
+        pane.exited,
+        pane.exit_status
+    )
+}
+
+fn to_u64(ms: u128) -> u64 {
+    u64::try_from(ms).unwrap_or(u64::MAX)
+}
@@ -0,0 +1,606 @@
+//! In-memory [`ZellijHost`] for native tests.
+//!
+//! `FakeHost` keeps a small model of tabs and panes, applies host calls to it the way
+//! Zellij would (hide suppresses, break moves and docks, embed/eject flips floating,
+//! ...), records every call, and can emit the resulting `PaneUpdate`/`TabUpdate`
+//! events so tests can feed them back into [`crate::State`].
+
+use crate::host::ZellijHost;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::path::PathBuf;
+use zellij_tile::prelude::*;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HostCall {
+    Subscribe(usize),
+    RequestPermission(usize),
+    RequestPluginStateSnapshot,
+    ListClients,
+    /// Timer length in milliseconds.
+    SetTimeout(u64),
+    WatchFilesystem,
+    HidePane(PaneId),
+    ShowPane {
+        pane_id: PaneId,
+        should_float_if_hidden: bool,
+        should_focus_pane: bool,
+    },
+    BreakPanesToTab {
+        pane_ids: Vec<PaneId>,
+        tab_index: usize,
+    },
+    TogglePaneEmbedOrEject(PaneId),
+    OpenCommandPaneFloating {
+        path: String,
+        args: Vec<String>,
+        cwd: Option<String>,
+    },
+    OpenFile {
+        path: String,
+        line_number: Option<usize>,
+        cwd: Option<String>,
+        floating: bool,
+        in_place_of: Option<PaneId>,
+    },
+    FocusTerminalPane(u32),
+    CloseTerminalPane(u32),
+    RerunCommandPane(u32),
+    RenameTab {
+        tab_position: u32,
+        name: String,
+    },
+    RenamePane {
+        pane_id: PaneId,
+        name: String,
+    },
+    CliPipeOutput {
+        pipe_id: String,
+        output: String,
+    },
+    UnblockCliPipeInput(String),
+}
+
+#[derive(Debug, Default)]
+pub struct FakeHost {
+    pub now_ms: u128,
+    pub tabs: Vec<TabInfo>,
+    /// Attached clients; only reported when a test delivers `clients_update()`.
+    pub clients: Vec<ClientInfo>,
+    /// Panes keyed by tab position.
+    pub panes: BTreeMap<usize, Vec<PaneInfo>>,
+    pub calls: Vec<HostCall>,
+    /// When set, `open_command_pane_floating` fails with this message.
+    pub launch_error: Option<String>,
+    /// Outcomes for upcoming launches (pane id or error), consumed in order before
+    /// falling back to allocating ids. Used by replays.
+    pub scripted_launches: VecDeque<Result<u32, String>>,
+    /// Sandbox files by path. File access is not recorded in `calls`.
+    pub files: BTreeMap<String, String>,
+    /// When set, writes and appends fail with this message.
+    pub file_error: Option<String>,
+    next_pane_id: u32,
+    /// Bytes handed out by `fill_random` so far; the stream is fixed, so replays
+    /// reproduce launch tokens.
+    random_bytes_drawn: u64,
+}
+
+impl FakeHost {
+    /// A session with `tab_count` tabs, each holding one focused shell pane. Tab 0 is
+    /// active and the clock starts at an arbitrary non-zero epoch.
+    pub fn with_tabs(tab_count: usize) -> Self {
+        let mut host = FakeHost {
+            now_ms: 1_700_000_000_000,
+            ..Default::default()
+        };
+        for position in 0..tab_count {
+            host.tabs.push(TabInfo {
+                position,
+                name: format!("Tab #{}", position + 1),
+                active: position == 0,
+                ..Default::default()
+            });
+            let pane_id = host.add_terminal_pane(position, "shell", Some("zsh"));
+            host.pane_mut(pane_id).expect("pane just added").is_focused = true;
+        }
+        host
+    }
+
+    pub fn advance(&mut self, ms: u128) {
+        self.now_ms = self.now_ms.saturating_add(ms);
+    }
+
+    pub fn add_terminal_pane(
+        &mut self,
+        tab_index: usize,
+        title: &str,
+        command: Option<&str>,
+    ) -> u32 {
+        let pane_id = self.allocate_pane_id();
+        self.insert_terminal_pane(tab_index, pane_id, title, command);
+        pane_id
+    }
+
+    fn insert_terminal_pane(
+        &mut self,
+        tab_index: usize,
+        pane_id: u32,
+        title: &str,
+        command: Option<&str>,
+    ) {
+        self.next_pane_id = self.next_pane_id.max(pane_id + 1);
+        self.panes.entry(tab_index).or_default().push(PaneInfo {
+            id: pane_id,
+            title: title.to_owned(),
+            terminal_command: command.map(str::to_owned),
+            is_selectable: true,
+            ..Default::default()
+        });
+        self.refresh_tab_counts();
+    }
+
+    pub fn remove_pane(&mut self, pane_id: u32) -> Option<(usize, PaneInfo)> {
+        let (tab_index, pane) = self.take_pane(pane_id)?;
+        self.refresh_tab_counts();
+        Some((tab_index, pane))
+    }
+
+    /// Closes the tab at `position` with its panes; later tabs shift down one
+    /// position, as Zellij renumbers them.
+    pub fn close_tab(&mut self, position: usize) {
+        self.tabs.retain(|tab| tab.position != position);
+        for tab in &mut self.tabs {
+            if tab.position > position {
+                tab.position -= 1;
+            }
+        }
+        if !self.tabs.is_empty() && !self.tabs.iter().any(|tab| tab.active) {
+            let last = self.tabs.len() - 1;
+            self.tabs[position.min(last)].active = true;
+        }
+        self.panes.remove(&position);
+        self.panes = std::mem::take(&mut self.panes)
+            .into_iter()
+            .map(|(tab_index, panes)| {
+                let tab_index = if tab_index > position {
+                    tab_index - 1
+                } else {
+                    tab_index
+                };
+                (tab_index, panes)
+            })
+            .collect();
+    }
+
+    /// Marks a pane's command as exited, held open the way Zellij keeps command panes.
+    pub fn exit_pane(&mut self, pane_id: u32, exit_status: Option<i32>) {
+        if let Some(pane) = self.pane_mut(pane_id) {
+            pane.exited = true;
+            pane.is_held = true;
+            pane.exit_status = exit_status;
+        }
+    }
+
+    /// The `CwdChanged` event Zellij sends when a terminal pane's shell changes
+    /// directory.
+    pub fn cwd_changed(&self, pane_id: u32, cwd: &str) -> Event {
+        Event::CwdChanged(PaneId::Terminal(pane_id), PathBuf::from(cwd), Vec::new())
+    }
+
+    pub fn focus_tab(&mut self, position: usize) {
+        for tab in &mut self.tabs {
+            tab.active = tab.position == position;
+        }
+    }
+
+    pub fn pane(&self, pane_id: u32) -> Option<(usize, &PaneInfo)> {
+        self.panes.iter().find_map(|(tab_index, panes)| {
+            panes
+                .iter()
+                .find(|pane| !pane.is_plugin && pane.id == pane_id)
+                .map(|pane| (*tab_index, pane))
+        })
+    }
+
+    pub fn pane_mut(&mut self, pane_id: u32) -> Option<&mut PaneInfo> {
+        self.panes
+            .values_mut()
+            .flat_map(|panes| panes.iter_mut())
+            .find(|pane| !pane.is_plugin && pane.id == pane_id)
+    }
+
+    pub fn manifest(&self) -> PaneManifest {
+        PaneManifest {
+            panes: self
+                .panes
+                .iter()
+                .map(|(tab_index, panes)| (*tab_index, panes.clone()))
+                .collect::<HashMap<_, _>>(),
+        }
+    }
+
+    pub fn pane_update(&self) -> Event {
+        Event::PaneUpdate(self.manifest())
+    }
+
+    pub fn tab_update(&self) -> Event {
+        Event::TabUpdate(self.tabs.clone())
+    }
+
+    pub fn clients_update(&self) -> Event {
+        Event::ListClients(self.clients.clone())
+    }
+
+    /// Attaches another client focused on `pane_id`.
+    pub fn add_client(&mut self, client_id: ClientId, pane_id: u32) {
+        self.clients.push(ClientInfo {
+            client_id,
+            pane_id: PaneId::Terminal(pane_id),
+            running_command: String::new(),
+            is_current_client: self.clients.is_empty(),
+        });
+    }
+
+    /// Drains recorded calls, so a test can assert on one step at a time.
+    pub fn take_calls(&mut self) -> Vec<HostCall> {
+        std::mem::take(&mut self.calls)
+    }
+
+    /// The last `cli_pipe_output` written for `pipe_id`, parsed as JSON.
+    pub fn last_cli_output(&self, pipe_id: &str) -> Option<serde_json::Value> {
+        self.calls.iter().rev().find_map(|call| match call {
+            HostCall::CliPipeOutput {
+                pipe_id: id,
+                output,
+            } if id == pipe_id => serde_json::from_str(output).ok(),
+            _ => None,
+        })
+    }
+
+    fn active_tab(&self) -> usize {
+        self.tabs
+            .iter()
+            .find(|tab| tab.active)
+            .map(|tab| tab.position)
+            .unwrap_or(0)
+    }
+
+    fn allocate_pane_id(&mut self) -> u32 {
+        let pane_id = self.next_pane_id;
+        self.next_pane_id += 1;
+        pane_id
+    }
+
+    fn take_pane(&mut self, pane_id: u32) -> Option<(usize, PaneInfo)> {
+        for (tab_index, panes) in self.panes.iter_mut() {
+            if let Some(index) = panes
+                .iter()
+                .position(|pane| !pane.is_plugin && pane.id == pane_id)
+            {
+                return Some((*tab_index, panes.remove(index)));
+            }
+        }
+        None
+    }
+
+    fn focus_pane_in_its_tab(&mut self, pane_id: u32) {
+        let Some((tab_index, _)) = self.pane(pane_id) else {
+            return;
+        };
+        if let Some(panes) = self.panes.get_mut(&tab_index) {
+            for pane in panes.iter_mut() {
+                pane.is_focused = !pane.is_plugin && pane.id == pane_id;
+            }
+        }
+    }
+
+    fn refresh_tab_counts(&mut self) {
+        for tab in &mut self.tabs {
+            let panes = self.panes.get(&tab.position);
+            let count = |floating: bool| {
+                panes.map_or(0, |panes| {
+                    panes
+                        .iter()
+                        .filter(|pane| {
+                            pane.is_selectable
+                                && !pane.is_suppressed
+                                && pane.is_floating == floating
+                        })
+                        .count()
+                })
+            };
+            tab.selectable_tiled_panes_count = count(false);
+            tab.selectable_floating_panes_count = count(true);
+        }
+    }
+}
+
+impl ZellijHost for FakeHost {
+    fn now_epoch_millis(&self) -> u128 {
+        self.now_ms
+    }
+
+    fn fill_random(&mut self, bytes: &mut [u8]) -> Result<(), String> {
+        for byte in bytes {
+            self.random_bytes_drawn += 1;
+            *byte = (self.random_bytes_drawn.wrapping_mul(0x9e37_79b9_7f4a_7c15) >> 56) as u8;
+        }
+        Ok(())
+    }
+
+    fn subscribe(&mut self, event_types: &[EventType]) {
+        self.calls.push(HostCall::Subscribe(event_types.len()));
+    }
+
+    fn request_permission(&mut self, permissions: &[PermissionType]) {
+        self.calls
+            .push(HostCall::RequestPermission(permissions.len()));
+    }
+
+    fn request_plugin_state_snapshot(&mut self) {
+        self.calls.push(HostCall::RequestPluginStateSnapshot);
+    }
+
+    fn list_clients(&mut self) {
+        self.calls.push(HostCall::ListClients);
+    }
+
+    fn set_timeout(&mut self, secs: f64) {
+        self.calls
+            .push(HostCall::SetTimeout((secs * 1000.0).round() as u64));
+    }
+
+    fn watch_filesystem(&mut self) {
+        self.calls.push(HostCall::WatchFilesystem);
+    }
+
+    fn hide_pane(&mut self, pane_id: PaneId) {
+        self.calls.push(HostCall::HidePane(pane_id));
+        if let PaneId::Terminal(id) = pane_id {
+            if let Some(pane) = self.pane_mut(id) {
+                pane.is_suppressed = true;
+                pane.is_focused = false;
+            }
+            self.refresh_tab_counts();
+        }
+    }
+
+    fn show_pane(
+        &mut self,
+        pane_id: PaneId,
+        should_float_if_hidden: bool,
+        should_focus_pane: bool,
+    ) {
+        self.calls.push(HostCall::ShowPane {
+            pane_id,
+            should_float_if_hidden,
+            should_focus_pane,
+        });
+        if let PaneId::Terminal(id) = pane_id {
+            if let Some(pane) = self.pane_mut(id) {
+                if pane.is_suppressed {
+                    pane.is_suppressed = false;
+                    pane.is_floating = should_float_if_hidden;
+                }
+            }
+            if should_focus_pane {
+                self.focus_pane_in_its_tab(id);
+            }
+            self.refresh_tab_counts();
+        }
+    }
+
+    fn break_panes_to_tab(
+        &mut self,
+        pane_ids: &[PaneId],
+        tab_index: usize,
+        _should_change_focus_to_target_tab: bool,
+    ) {
+        self.calls.push(HostCall::BreakPanesToTab {
+            pane_ids: pane_ids.to_vec(),
+            tab_index,
+        });
+        for pane_id in pane_ids {
+            if let PaneId::Terminal(id) = pane_id {
+                if let Some((_, mut pane)) = self.take_pane(*id) {
+                    // Breaking into a tab docks the pane as a visible tiled pane.
+                    pane.is_floating = false;
+                    pane.is_suppressed = false;
+                    self.panes.entry(tab_index).or_default().push(pane);
+                }
+            }
+        }
+        self.refresh_tab_counts();
+    }
+
+    fn toggle_pane_embed_or_eject(&mut self, pane_id: PaneId) {
+        self.calls.push(HostCall::TogglePaneEmbedOrEject(pane_id));
+        if let PaneId::Terminal(id) = pane_id {
+            if let Some(pane) = self.pane_mut(id) {
+                pane.is_floating = !pane.is_floating;
+            }
+            self.refresh_tab_counts();
+        }
+    }
+
+    fn open_command_pane_floating(&mut self, command: CommandToRun) -> Result<PaneId, String> {
+        let path = command.path.to_string_lossy().into_owned();
+        self.calls.push(HostCall::OpenCommandPaneFloating {
+            path: path.clone(),
+            args: command.args.clone(),
+            cwd: command
+                .cwd
+                .as_ref()
+                .map(|cwd| cwd.to_string_lossy().into_owned()),
+        });
+        if let Some(error) = self.launch_error.clone() {
+            return Err(error);
+        }
+        let tab_index = self.active_tab();
+        let pane_id = match self.scripted_launches.pop_front() {
+            Some(Ok(pane_id)) => pane_id,
+            Some(Err(error)) => return Err(error),
+            None => self.allocate_pane_id(),
+        };
+        // Zellij titles command panes with their command line.
+        let command_line = std::iter::once(path)
+            .chain(command.args)
+            .collect::<Vec<_>>()
+            .join(" ");
+        self.insert_terminal_pane(tab_index, pane_id, &command_line, Some(&command_line));
+        if let Some(pane) = self.pane_mut(pane_id) {
+            pane.is_floating = true;
+        }
+        self.focus_pane_in_its_tab(pane_id);
+        self.refresh_tab_counts();
+        Ok(PaneId::Terminal(pane_id))
+    }
+
+    fn open_file(
+        &mut self,
+        file: FileToOpen,
+        floating: bool,
+        in_place_of: Option<PaneId>,
+    ) -> Result<PaneId, String> {
+        let path = file.path.to_string_lossy().into_owned();
+        self.calls.push(HostCall::OpenFile {
+            path: path.clone(),
+            line_number: file.line_number,
+            cwd: file
+                .cwd
+                .as_ref()
+                .map(|cwd| cwd.to_string_lossy().into_owned()),
+            floating,
+            in_place_of,
+        });
+        if let Some(error) = self.launch_error.clone() {
+            return Err(error);
+        }
+        // An in-place editor takes the replaced pane's tab and suspends it.
+        let tab_index = match in_place_of {
+            Some(PaneId::Terminal(id)) => {
+                let (tab_index, _) = self
+                    .pane(id)
+                    .ok_or_else(|| format!("pane {} does not exist", id))?;
+                if let Some(pane) = self.pane_mut(id) {
+                    pane.is_suppressed = true;
+                    pane.is_focused = false;
+                }
+                tab_index
+            }
+            _ => self.active_tab(),
+        };
+        let pane_id = self.allocate_pane_id();
+        self.insert_terminal_pane(tab_index, pane_id, &path, None);
+        if let Some(pane) = self.pane_mut(pane_id) {
+            pane.is_floating = floating;
+        }
+        self.focus_pane_in_its_tab(pane_id);
+        self.refresh_tab_counts();
+        Ok(PaneId::Terminal(pane_id))
+    }
+
+    fn focus_terminal_pane(
+        &mut self,
+        terminal_pane_id: u32,
+        _should_float_if_hidden: bool,
+        _should_be_in_place_if_hidden: bool,
+    ) {
+        self.calls
+            .push(HostCall::FocusTerminalPane(terminal_pane_id));
+        // Zellij switches to the pane's tab too.
+        if let Some((tab_index, _)) = self.pane(terminal_pane_id) {
+            self.focus_tab(tab_index);
+        }
+        self.focus_pane_in_its_tab(terminal_pane_id);
+    }
+
+    fn close_terminal_pane(&mut self, terminal_pane_id: u32) {
+        self.calls
+            .push(HostCall::CloseTerminalPane(terminal_pane_id));
+        self.remove_pane(terminal_pane_id);
+    }
+
+    fn rerun_command_pane(&mut self, terminal_pane_id: u32) {
+        self.calls
+            .push(HostCall::RerunCommandPane(terminal_pane_id));
+        if let Some(pane) = self.pane_mut(terminal_pane_id) {
+            pane.exited = false;
+            pane.is_held = false;
+            pane.exit_status = None;
+        }
+    }
+
+    fn rename_tab(&mut self, tab_position: u32, name: &str) {
+        self.calls.push(HostCall::RenameTab {
+            tab_position,
+            name: name.to_owned(),
+        });
+        let position = (tab_position as usize).saturating_sub(1);
+        if let Some(tab) = self.tabs.iter_mut().find(|tab| tab.position == position) {
+            tab.name = name.to_owned();
+        }
+    }
+
+    fn rename_pane(&mut self, pane_id: PaneId, name: &str) {
+        self.calls.push(HostCall::RenamePane {
+            pane_id,
+            name: name.to_owned(),
+        });
+        if let PaneId::Terminal(id) = pane_id {
+            if let Some(pane) = self.pane_mut(id) {
+                pane.title = name.to_owned();
+            }
+        }
+    }
+
+    fn cli_pipe_output(&mut self, pipe_id: &str, output: &str) {
+        self.calls.push(HostCall::CliPipeOutput {
+            pipe_id: pipe_id.to_owned(),
+            output: output.to_owned(),
+        });
+    }
+
+    fn unblock_cli_pipe_input(&mut self, pipe_id: &str) {
+        self.calls
+            .push(HostCall::UnblockCliPipeInput(pipe_id.to_owned()));
+    }
+
+    fn file_len(&self, path: &str) -> Option<u64> {
+        self.files.get(path).map(|contents| contents.len() as u64)
+    }
+
+    fn read_file(&self, path: &str) -> Result<String, String> {
+        self.files
+            .get(path)
+            .cloned()
+            .ok_or_else(|| format!("{}: no such file", path))
+    }
+
+    fn write_file(&mut self, path: &str, contents: &str) -> Result<(), String> {
+        if let Some(error) = self.file_error.clone() {
+            return Err(error);
+        }
+        self.files.insert(path.to_owned(), contents.to_owned());
+        Ok(())
+    }
+
+    fn append_file(&mut self, path: &str, contents: &str) -> Result<(), String> {
+        if let Some(error) = self.file_error.clone() {
+            return Err(error);
+        }
+        self.files
+            .entry(path.to_owned())
+            .or_default()
+            .push_str(contents);
+        Ok(())
+    }
+
+    fn rename_file(&mut self, from: &str, to: &str) -> Result<(), String> {
+        let contents = self
+            .files
+            .remove(from)
+            .ok_or_else(|| format!("{}: no such file", from))?;
+        self.files.insert(to.to_owned(), contents);
+        Ok(())
+    }
+}
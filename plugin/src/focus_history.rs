@@ -0,0 +1,86 @@
+//! Most-recently-used focus history behind `focus_back` and the `jump_back` pipe.
+//!
+//! Each `PaneUpdate`/`TabUpdate` reports which pane the active tab has focused; when
+//! that differs from the head of the list it moves to the front. Jelly panes are never
+//! recorded, so "back" from the assistant means back to where the user was working.
+
+use crate::protocol::{PaneFocusEntry, TabFocusEntry};
+use std::collections::VecDeque;
+
+/// Entries kept per list; older ones fall off.
+pub(crate) const FOCUS_HISTORY_LIMIT: usize = 50;
+
+#[derive(Debug, Default)]
+pub(crate) struct FocusHistory {
+    /// `(pane_id, tab_index, focused_at_ms)`, most recent first.
+    panes: VecDeque<(u32, usize, u128)>,
+    /// `(tab_position, focused_at_ms)`, most recent first.
+    tabs: VecDeque<(usize, u128)>,
+}
+
+impl FocusHistory {
+    pub(crate) fn observe(
+        &mut self,
+        now_ms: u128,
+        active_tab: Option<usize>,
+        focused_pane: Option<u32>,
+    ) {
+        if let Some(position) = active_tab {
+            if self.tabs.front().map(|(head, _)| *head) != Some(position) {
+                self.tabs.retain(|(tab, _)| *tab != position);
+                self.tabs.push_front((position, now_ms));
+                self.tabs.truncate(FOCUS_HISTORY_LIMIT);
+            }
+        }
+        if let (Some(pane_id), Some(tab_index)) = (focused_pane, active_tab) {
+            if self.panes.front().map(|(head, _, _)| *head) != Some(pane_id) {
+                self.panes.retain(|(pane, _, _)| *pane != pane_id);
+                self.panes.push_front((pane_id, tab_index, now_ms));
+                self.panes.truncate(FOCUS_HISTORY_LIMIT);
+            }
+        }
+    }
+
+    /// Drops panes that closed and tabs past the end; keeps moved panes' tabs current.
+    pub(crate) fn retain(&mut self, pane_tab: impl Fn(u32) -> Option<usize>, tab_count: usize) {
+        self.panes
+            .retain_mut(|(pane_id, tab_index, _)| match pane_tab(*pane_id) {
+                Some(current) => {
+                    *tab_index = current;
+                    true
+                }
+                None => false,
+            });
+        self.tabs.retain(|(position, _)| *position < tab_count);
+    }
+
+    /// The pane `steps` back, not counting `current` (the pane focused right now).
+    pub(crate) fn pane_back(&self, steps: usize, current: Option<u32>) -> Option<(u32, usize)> {
+        self.panes
+            .iter()
+            .filter(|(pane_id, _, _)| Some(*pane_id) != current)
+            .nth(steps.checked_sub(1)?)
+            .map(|(pane_id, tab_index, _)| (*pane_id, *tab_index))
+    }
+
+    pub(crate) fn pane_entries(&self) -> Vec<PaneFocusEntry> {
+        self.panes
+            .iter()
+            .map(|(pane_id, tab_index, at_ms)| PaneFocusEntry {
+                pane_id: *pane_id,
+                tab_position: *tab_index,
+                focused_epoch_ms: u64::try_from(*at_ms).unwrap_or(u64::MAX),
+            })
+            .collect()
+    }
+
+    pub(crate) fn tab_entries(&self) -> Vec<TabFocusEntry> {
+        self.tabs
+            .iter()
+            .map(|(position, at_ms)| TabFocusEntry {
+                position: *position,
+                focused_epoch_ms: u64::try_from(*at_ms).unwrap_or(u64::MAX),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,364 @@
+//! Filesystem change summaries behind `get_fs_changes`.
+//!
+//! With `fs_watch` on, the butler asks Zellij to watch the session's cwd (mounted at
+//! `/host`) and folds `FileSystemCreate`/`Update`/`Delete` events into one summary per
+//! burst. A burst ends once no change has arrived for the debounce window; the butler
+//! sets a timer when a burst starts and cuts it when that fires, or earlier when the
+//! next change arrives or someone reads the summaries.
+//!
+//! Within a burst a file created then deleted (editor swap files) is dropped, and one
+//! deleted then created again (atomic saves) counts as updated.
+
+use crate::host::ZellijHost;
+use crate::protocol::{FsChangeSummary, TraceCategory};
+use crate::state::State;
+use regex::Regex;
+use std::collections::{BTreeMap, VecDeque};
+use std::path::{Path, PathBuf};
+use zellij_tile::prelude::FileMetadata;
+
+pub(crate) const DEFAULT_DEBOUNCE_MS: u128 = 500;
+pub(crate) const DEFAULT_EXCLUDE: &str = ".git/**,target/**,node_modules/**";
+/// Summaries kept; older ones fall off.
+const SUMMARY_LIMIT: usize = 100;
+/// Paths listed per summary; the rest are only counted.
+const PATHS_PER_SUMMARY: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FsChangeKind {
+    Created,
+    Updated,
+    Deleted,
+}
+
+#[derive(Debug)]
+struct Glob {
+    pattern: String,
+    regex: Regex,
+}
+
+#[derive(Debug)]
+pub(crate) struct FsWatch {
+    include: Vec<Glob>,
+    exclude: Vec<Glob>,
+    debounce_ms: u128,
+    /// The burst being collected, by path.
+    pending: BTreeMap<String, FsChangeKind>,
+    /// `(first, last)` change times of the pending burst.
+    pending_ms: Option<(u128, u128)>,
+    summaries: VecDeque<FsChangeSummary>,
+    next_seq: u64,
+}
+
+impl FsWatch {
+    /// Globs are comma-separated and matched against paths relative to the watched
+    /// folder; one without a `/` matches a file name at any depth. An empty include
+    /// list takes everything.
+    pub(crate) fn new(include: &str, exclude: &str, debounce_ms: u128) -> Self {
+        FsWatch {
+            include: parse_globs(include),
+            exclude: parse_globs(exclude),
+            debounce_ms,
+            pending: BTreeMap::new(),
+            pending_ms: None,
+            summaries: VecDeque::new(),
+            next_seq: 1,
+        }
+    }
+
+    pub(crate) fn include(&self) -> Vec<String> {
+        self.include
+            .iter()
+            .map(|glob| glob.pattern.clone())
+            .collect()
+    }
+
+    pub(crate) fn exclude(&self) -> Vec<String> {
+        self.exclude
+            .iter()
+            .map(|glob| glob.pattern.clone())
+            .collect()
+    }
+
+    /// Adds a batch of changes. Returns `(seq, changes)` of the summary cut first if the
+    /// previous burst had already gone quiet.
+    pub(crate) fn observe<'a>(
+        &mut self,
+        now_ms: u128,
+        kind: FsChangeKind,
+        paths: impl IntoIterator<Item = &'a Path>,
+    ) -> Option<(u64, usize)> {
+        let cut = self.cut_if_quiet(now_ms);
+        let mut observed = false;
+        for path in paths {
+            let Some(path) = self.watched_path(path) else {
+                continue;
+            };
+            observed = true;
+            let merged = match (self.pending.get(&path), kind) {
+                (Some(FsChangeKind::Created), FsChangeKind::Deleted) => None,
+                (Some(FsChangeKind::Created), _) => Some(FsChangeKind::Created),
+                (Some(FsChangeKind::Deleted), FsChangeKind::Created) => Some(FsChangeKind::Updated),
+                (_, kind) => Some(kind),
+            };
+            match merged {
+                Some(kind) => self.pending.insert(path, kind),
+                None => self.pending.remove(&path),
+            };
+        }
+        if observed {
+            let first_ms = self.pending_ms.map_or(now_ms, |(first_ms, _)| first_ms);
+            self.pending_ms = Some((first_ms, now_ms));
+        }
+        cut
+    }
+
+    /// When the pending burst will have been quiet for the debounce window.
+    pub(crate) fn quiet_at_ms(&self) -> Option<u128> {
+        self.pending_ms
+            .map(|(_, last_ms)| last_ms.saturating_add(self.debounce_ms))
+    }
+
+    /// Cuts the pending burst into a summary once it has been quiet for the debounce
+    /// window. Returns the new summary's `(seq, changes)`.
+    pub(crate) fn cut_if_quiet(&mut self, now_ms: u128) -> Option<(u64, usize)> {
+        let (first_ms, last_ms) = self.pending_ms?;
+        if now_ms.saturating_sub(last_ms) < self.debounce_ms {
+            return None;
+        }
+        self.pending_ms = None;
+        let pending = std::mem::take(&mut self.pending);
+        if pending.is_empty() {
+            return None;
+        }
+        let changes = pending.len();
+        let mut summary = FsChangeSummary {
+            seq: self.next_seq,
+            first_epoch_ms: first_ms as u64,
+            last_epoch_ms: last_ms as u64,
+            created: Vec::new(),
+            updated: Vec::new(),
+            deleted: Vec::new(),
+            omitted: pending.len().saturating_sub(PATHS_PER_SUMMARY),
+        };
+        for (path, kind) in pending.into_iter().take(PATHS_PER_SUMMARY) {
+            match kind {
+                FsChangeKind::Created => summary.created.push(path),
+                FsChangeKind::Updated => summary.updated.push(path),
+                FsChangeKind::Deleted => summary.deleted.push(path),
+            }
+        }
+        let seq = summary.seq;
+        self.next_seq += 1;
+        self.summaries.push_back(summary);
+        while self.summaries.len() > SUMMARY_LIMIT {
+            self.summaries.pop_front();
+        }
+        Some((seq, changes))
+    }
+
+    /// Summaries with a `seq` above `since`, oldest first.
+    pub(crate) fn summaries_since(&self, since: Option<u64>) -> Vec<FsChangeSummary> {
+        self.summaries
+            .iter()
+            .filter(|summary| since.is_none_or(|since| summary.seq > since))
+            .cloned()
+            .collect()
+    }
+
+    /// The path relative to the watched folder, if the globs let it through.
+    fn watched_path(&self, path: &Path) -> Option<String> {
+        let path = path.strip_prefix("/host").unwrap_or(path);
+        let path = path.to_string_lossy();
+        let path = path.trim_start_matches('/');
+        let included =
+            self.include.is_empty() || self.include.iter().any(|glob| glob.regex.is_match(path));
+        let excluded = self.exclude.iter().any(|glob| glob.regex.is_match(path));
+        (!path.is_empty() && included && !excluded).then(|| path.to_owned())
+    }
+}
+
+fn parse_globs(globs: &str) -> Vec<Glob> {
+    globs
+        .split(',')
+        .map(str::trim)
+        .filter(|pattern| !pattern.is_empty())
+        .map(|pattern| Glob {
+            pattern: pattern.to_owned(),
+            regex: glob_regex(pattern),
+        })
+        .collect()
+}
+
+/// `**` crosses directories, `*` and `?` do not; a trailing `/**` also matches the
+/// directory itself.
+fn glob_regex(pattern: &str) -> Regex {
+    let pattern = pattern.trim_start_matches('/');
+    let mut regex = String::from("^");
+    if !pattern.contains('/') {
+        regex.push_str("(?:.*/)?");
+    }
+    let mut rest = pattern;
+    while let Some(c) = rest.chars().next() {
+        if let Some(after) = rest.strip_prefix("**/") {
+            regex.push_str("(?:.*/)?");
+            rest = after;
+        } else if rest == "/**" {
+            regex.push_str("(?:/.*)?");
+            rest = "";
+        } else if let Some(after) = rest.strip_prefix("**") {
+            regex.push_str(".*");
+            rest = after;
+        } else {
+            match c {
+                '*' => regex.push_str("[^/]*"),
+                '?' => regex.push_str("[^/]"),
+                _ => regex.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+            }
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    regex.push('$');
+    Regex::new(&regex).expect("escaped glob is a valid regex")
+}
+
+impl<H: ZellijHost> State<H> {
+    pub(crate) fn observe_fs_changes(
+        &mut self,
+        kind: FsChangeKind,
+        paths: &[(PathBuf, Option<FileMetadata>)],
+    ) {
+        self.metrics.count_event("fs_change");
+        let now_ms = self.host.now_epoch_millis();
+        let Some(fs_watch) = self.fs_watch.as_mut() else {
+            return;
+        };
+        let cut = fs_watch.observe(now_ms, kind, paths.iter().map(|(path, _)| path.as_path()));
+        self.trace_fs_summary(cut);
+        self.arm_fs_cut_timer(now_ms);
+    }
+
+    /// Cuts the filesystem burst once its timer has fired and it has gone quiet, and
+    /// re-arms the timer when later changes kept it going.
+    pub(crate) fn cut_quiet_fs_burst(&mut self) {
+        let now_ms = self.host.now_epoch_millis();
+        if self.fs_cut_timer_ms.is_some_and(|due_ms| due_ms <= now_ms) {
+            self.fs_cut_timer_ms = None;
+        }
+        let Some(fs_watch) = self.fs_watch.as_mut() else {
+            return;
+        };
+        let cut = fs_watch.cut_if_quiet(now_ms);
+        self.trace_fs_summary(cut);
+        self.arm_fs_cut_timer(now_ms);
+    }
+
+    /// Sets a timer for the moment the pending burst goes quiet, unless one is armed.
+    fn arm_fs_cut_timer(&mut self, now_ms: u128) {
+        if self.fs_cut_timer_ms.is_some() {
+            return;
+        }
+        let Some(quiet_at_ms) = self.fs_watch.as_ref().and_then(FsWatch::quiet_at_ms) else {
+            return;
+        };
+        self.fs_cut_timer_ms = Some(quiet_at_ms);
+        self.host
+            .set_timeout(quiet_at_ms.saturating_sub(now_ms) as f64 / 1000.0);
+    }
+
+    pub(crate) fn trace_fs_summary(&mut self, cut: Option<(u64, usize)>) {
+        if let Some((seq, changes)) = cut {
+            self.push_trace(TraceCategory::Fs, "fs_changes")
+                .field("seq", seq)
+                .field("changes", changes);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, path: &str) -> bool {
+        glob_regex(pattern).is_match(path)
+    }
+
+    #[test]
+    fn single_star_stays_within_a_directory() {
+        assert!(matches("src/*.rs", "src/lib.rs"));
+        assert!(!matches("src/*.rs", "src/bin/main.rs"));
+        assert!(matches("src/?.rs", "src/a.rs"));
+        assert!(!matches("src/?.rs", "src/ab.rs"));
+    }
+
+    #[test]
+    fn double_star_crosses_directories_and_matches_zero_of_them() {
+        assert!(matches("src/**/*.rs", "src/lib.rs"));
+        assert!(matches("src/**/*.rs", "src/a/b/c.rs"));
+        assert!(matches("target/**", "target"));
+        assert!(matches("target/**", "target/debug/x"));
+        assert!(!matches("target/**", "targets/x"));
+        assert!(matches("**.toml", "crates/api/Cargo.toml"));
+    }
+
+    #[test]
+    fn slashless_globs_match_names_at_any_depth_and_are_anchored() {
+        assert!(matches("*.toml", "Cargo.toml"));
+        assert!(matches("*.toml", "crates/api/Cargo.toml"));
+        assert!(!matches("*.toml", "Cargo.toml.bak"));
+        assert!(
+            matches("/src/*.rs", "src/lib.rs"),
+            "a leading slash is ignored"
+        );
+        assert!(!matches("src/*.rs", "crates/src/lib.rs"));
+    }
+
+    #[test]
+    fn regex_characters_in_globs_are_literal() {
+        assert!(matches("a+b.(txt)", "a+b.(txt)"));
+        assert!(!matches("a+b.(txt)", "aab.(txt)"));
+        assert!(!matches("*.rs", "librs"));
+    }
+
+    #[test]
+    fn bursts_merge_create_delete_pairs() {
+        let mut watch = FsWatch::new("", DEFAULT_EXCLUDE, 100);
+        let observe = |watch: &mut FsWatch, now_ms, kind, path: &str| {
+            watch.observe(now_ms, kind, [Path::new(path)])
+        };
+        observe(&mut watch, 0, FsChangeKind::Created, "/host/.swp");
+        observe(&mut watch, 10, FsChangeKind::Deleted, "/host/.swp");
+        observe(&mut watch, 20, FsChangeKind::Deleted, "/host/main.rs");
+        observe(&mut watch, 30, FsChangeKind::Created, "/host/main.rs");
+        observe(&mut watch, 40, FsChangeKind::Created, "/host/new.rs");
+        observe(&mut watch, 50, FsChangeKind::Updated, "/host/new.rs");
+        observe(&mut watch, 60, FsChangeKind::Updated, "/host/.git/index");
+
+        assert_eq!(
+            watch.cut_if_quiet(149),
+            None,
+            "still inside the debounce window"
+        );
+        assert_eq!(watch.cut_if_quiet(150), Some((1, 2)));
+        let summary = &watch.summaries_since(None)[0];
+        assert_eq!(
+            (summary.first_epoch_ms, summary.last_epoch_ms),
+            (0, 50),
+            "excluded paths do not extend the burst"
+        );
+        assert_eq!(summary.created, ["new.rs"]);
+        assert_eq!(summary.updated, ["main.rs"]);
+        assert!(summary.deleted.is_empty());
+        assert!(watch.summaries_since(Some(1)).is_empty());
+    }
+
+    #[test]
+    fn a_burst_that_cancels_out_leaves_no_summary() {
+        let mut watch = FsWatch::new("", "", 100);
+        watch.observe(0, FsChangeKind::Created, [Path::new("/host/tmp")]);
+        watch.observe(10, FsChangeKind::Deleted, [Path::new("/host/tmp")]);
+
+        assert_eq!(watch.cut_if_quiet(1_000), None);
+        assert!(watch.summaries_since(None).is_empty());
+    }
+}
@@ -0,0 +1,69 @@
+//! The narrow slice of the Zellij plugin API the butler depends on.
+//!
+//! The wasm binary implements this with `zellij_tile` shim calls; native tests use
+//! [`crate::fake_host::FakeHost`]. Method names follow the shim functions they wrap.
+
+use zellij_tile::prelude::{CommandToRun, EventType, FileToOpen, PaneId, PermissionType};
+
+pub trait ZellijHost {
+    /// Wall-clock time in epoch milliseconds. Injected so dedup windows and grace
+    /// periods can be driven deterministically in tests.
+    fn now_epoch_millis(&self) -> u128;
+    /// Fills `bytes` from the OS entropy source. Injected so tests and replays see
+    /// repeatable launch tokens.
+    fn fill_random(&mut self, bytes: &mut [u8]) -> Result<(), String>;
+
+    fn subscribe(&mut self, event_types: &[EventType]);
+    fn request_permission(&mut self, permissions: &[PermissionType]);
+    fn request_plugin_state_snapshot(&mut self);
+    /// Answered asynchronously with `Event::ListClients`.
+    fn list_clients(&mut self);
+    /// Answered with `Event::Timer` after roughly `secs` seconds.
+    fn set_timeout(&mut self, secs: f64);
+    /// Starts `FileSystemCreate`/`Update`/`Delete` events for the `/host` folder.
+    fn watch_filesystem(&mut self);
+
+    fn hide_pane(&mut self, pane_id: PaneId);
+    fn show_pane(&mut self, pane_id: PaneId, should_float_if_hidden: bool, should_focus_pane: bool);
+    fn break_panes_to_tab(
+        &mut self,
+        pane_ids: &[PaneId],
+        tab_index: usize,
+        should_change_focus_to_target_tab: bool,
+    );
+    fn toggle_pane_embed_or_eject(&mut self, pane_id: PaneId);
+    /// Runs `command` directly (no shell) in a new floating command pane.
+    fn open_command_pane_floating(&mut self, command: CommandToRun) -> Result<PaneId, String>;
+    /// Opens `file` in the user's `$EDITOR`: in place of `in_place_of` (which comes
+    /// back when the editor exits), floating, or as a new tiled pane.
+    fn open_file(
+        &mut self,
+        file: FileToOpen,
+        floating: bool,
+        in_place_of: Option<PaneId>,
+    ) -> Result<PaneId, String>;
+    fn focus_terminal_pane(
+        &mut self,
+        terminal_pane_id: u32,
+        should_float_if_hidden: bool,
+        should_be_in_place_if_hidden: bool,
+    );
+    fn close_terminal_pane(&mut self, terminal_pane_id: u32);
+    /// Restarts the command of a held (exited) command pane in place.
+    fn rerun_command_pane(&mut self, terminal_pane_id: u32);
+    /// `tab_position` is 1-based, matching the shim.
+    fn rename_tab(&mut self, tab_position: u32, name: &str);
+    fn rename_pane(&mut self, pane_id: PaneId, name: &str);
+
+    fn cli_pipe_output(&mut self, pipe_id: &str, output: &str);
+    fn unblock_cli_pipe_input(&mut self, pipe_id: &str);
+
+    /// Files under the plugin's sandboxed folders (`/data`, `/tmp`, `/host`). The
+    /// length of a missing file is `None`.
+    fn file_len(&self, path: &str) -> Option<u64>;
+    fn read_file(&self, path: &str) -> Result<String, String>;
+    fn write_file(&mut self, path: &str, contents: &str) -> Result<(), String>;
+    fn append_file(&mut self, path: &str, contents: &str) -> Result<(), String>;
+    /// Replaces `to` if it exists.
+    fn rename_file(&mut self, from: &str, to: &str) -> Result<(), String>;
+}
@@ -0,0 +1,357 @@
+//! Launching Jelly panes: the `sh -c` command line, launch tokens and their
+//! registration, and keeping `launch_env` values and tokens out of everything the
+//! butler reports.
+
+use crate::host::ZellijHost;
+use crate::protocol::{TraceCategory, TraceLevel};
+use crate::recording::RecordedEvent;
+use crate::state::{State, PANE_NAME};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use zellij_tile::prelude::*;
+
+/// Environment variable carrying a launch token into `jelly-j ui`, which echoes it
+/// back through `register_ui_pane`.
+pub const UI_TOKEN_ENV: &str = "JELLY_J_BUTLER_TOKEN";
+/// Launch tokens kept while waiting for the UI to register.
+const UI_TOKEN_LIMIT: usize = 16;
+/// Stands in for `launch_env` values and launch tokens wherever the butler would
+/// otherwise echo them.
+pub(crate) const REDACTED: &str = "<redacted>";
+
+/// Where new Jelly panes start, from the `launch_cwd` config.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) enum LaunchCwd {
+    /// The directory of the pane focused when Jelly was toggled, once Zellij has
+    /// reported it; otherwise Zellij's default.
+    #[default]
+    Focused,
+    /// `$HOME` as the Zellij server sees it.
+    Home,
+    Fixed(PathBuf),
+}
+
+impl LaunchCwd {
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "focused" => Some(LaunchCwd::Focused),
+            "home" => Some(LaunchCwd::Home),
+            value => value
+                .strip_prefix("fixed:")
+                .map(str::trim)
+                .filter(|path| !path.is_empty())
+                .map(|path| LaunchCwd::Fixed(PathBuf::from(path))),
+        }
+    }
+
+    pub(crate) fn describe(&self) -> String {
+        match self {
+            LaunchCwd::Focused => "focused".to_owned(),
+            LaunchCwd::Home => "home".to_owned(),
+            LaunchCwd::Fixed(path) => format!("fixed:{}", path.display()),
+        }
+    }
+}
+
+/// The configuration as recorded: `launch_env.<NAME>` values are secrets as often
+/// as not, so only their names are kept.
+pub(crate) fn redacted_configuration(
+    configuration: &BTreeMap<String, String>,
+) -> BTreeMap<String, String> {
+    configuration
+        .iter()
+        .map(|(key, value)| {
+            let value = if key.starts_with("launch_env.") {
+                REDACTED.to_owned()
+            } else {
+                value.clone()
+            };
+            (key.clone(), value)
+        })
+        .collect()
+}
+
+impl<H: ZellijHost> State<H> {
+    /// Tokens are single-use: the one on the pane's command line is spent once its UI
+    /// registers, so anything reading it there later (`ps`) learns nothing useful.
+    pub(crate) fn register_ui_pane(&mut self, pane_id: u32, token: &str) -> Value {
+        match self.ui_tokens.get(token).copied() {
+            Some(launched_pane_id) if launched_pane_id == pane_id => {
+                self.ui_tokens.remove(token);
+                self.registered_jelly_panes.insert(pane_id);
+                self.push_trace(TraceCategory::Request, "registered_ui_pane")
+                    .field("pane_id", pane_id);
+                Self::ack_response()
+            }
+            Some(launched_pane_id) => {
+                self.push_trace_at(
+                    TraceLevel::Warn,
+                    TraceCategory::Request,
+                    "register_ui_pane token_mismatch",
+                )
+                .field("pane_id", pane_id)
+                .field("token_pane_id", launched_pane_id);
+                Self::error_response(
+                    "token_mismatch",
+                    format!(
+                        "token was issued to pane {}, not pane {}",
+                        launched_pane_id, pane_id
+                    ),
+                )
+            }
+            None => Self::error_response(
+                "unknown_token",
+                "token was not issued by this butler instance",
+            ),
+        }
+    }
+
+    pub(crate) fn launch_new_jelly_terminal(&mut self, origin_pane: Option<u32>) -> &'static str {
+        let command = self.launch_command().to_owned();
+        self.push_trace(
+            TraceCategory::Toggle,
+            "launching_new_jelly_terminal atomically",
+        )
+        .field("command", command);
+        if let Some(pane_id) = self.jelly_pane_id {
+            self.push_trace(
+                TraceCategory::Toggle,
+                "launch_skipped_tracked_jelly_pending",
+            )
+            .field("id", pane_id);
+            return "launch_skipped";
+        }
+        match self.launch_jelly_pane(origin_pane) {
+            Ok(pane_id) => {
+                self.jelly_pane_id = Some(pane_id);
+                "launch"
+            }
+            Err(action) => action,
+        }
+    }
+
+    /// The pane Jelly is being opened from: the client's focused pane, else the
+    /// focused non-Jelly terminal pane in `tab`.
+    pub(crate) fn origin_pane(&self, client_id: Option<ClientId>, tab: usize) -> Option<u32> {
+        let client_pane = client_id.and_then(|client_id| {
+            self.clients
+                .iter()
+                .find(|client| client.client_id == client_id)
+                .and_then(|client| match client.pane_id {
+                    PaneId::Terminal(pane_id) => Some(pane_id),
+                    PaneId::Plugin(_) => None,
+                })
+        });
+        if let Some(pane_id) = client_pane {
+            if self
+                .find_terminal_pane_by_id(pane_id)
+                .is_some_and(|(_, pane)| !self.is_jelly_pane(&pane))
+            {
+                return Some(pane_id);
+            }
+        }
+        self.panes
+            .as_ref()?
+            .panes
+            .get(&tab)?
+            .iter()
+            .find(|pane| {
+                pane.is_focused && !pane.is_plugin && !pane.exited && !self.is_jelly_pane(pane)
+            })
+            .map(|pane| pane.id)
+    }
+
+    /// `sh -c 'export "$@" && exec <launch_command>' jelly-j NAME=value...
+    /// JELLY_J_BUTLER_TOKEN=<token>`. The non-interactive shell reads no init files and
+    /// parses `launch_command` (quotes, `~`); the assignments are positional arguments,
+    /// so their values are never parsed. `launch_cwd = home` adds `cd &&`, since only
+    /// the server knows `$HOME`.
+    ///
+    /// Zellij cannot set a command pane's environment, so the values are on its command
+    /// line; [`Self::redact_launch_env`] keeps them out of everything the butler reports.
+    /// Without a token (no entropy) the pane is left to the title/command heuristic.
+    fn launch_argv(&self, token: Option<&str>) -> Vec<String> {
+        let cd = if self.launch_cwd == LaunchCwd::Home {
+            "cd && "
+        } else {
+            ""
+        };
+        let mut argv = vec![
+            "sh".to_owned(),
+            "-c".to_owned(),
+            format!("export \"$@\" && {}exec {}", cd, self.launch_command()),
+            "jelly-j".to_owned(),
+        ];
+        argv.extend(
+            self.launch_env
+                .iter()
+                .map(|(name, value)| format!("{}={}", name, value)),
+        );
+        if let Some(token) = token {
+            argv.push(format!("{}={}", UI_TOKEN_ENV, token));
+        }
+        argv
+    }
+
+    /// Replaces `launch_env` values and launch tokens in a command line or title with
+    /// `<redacted>`.
+    fn redact_launch_env(&self, text: &mut String) {
+        let token_prefix = format!("{}=", UI_TOKEN_ENV);
+        let mut from = 0;
+        while let Some(found) = text[from..].find(&token_prefix) {
+            let start = from + found + token_prefix.len();
+            let end = text[start..]
+                .find(|c: char| c.is_whitespace() || c == '\'' || c == '"')
+                .map_or(text.len(), |len| start + len);
+            text.replace_range(start..end, REDACTED);
+            from = start + REDACTED.len();
+        }
+        for (name, value) in &self.launch_env {
+            if value.is_empty() {
+                continue;
+            }
+            let assignment = format!("{}={}", name, value);
+            if text.contains(&assignment) {
+                *text = text.replace(&assignment, &format!("{}={}", name, REDACTED));
+            }
+        }
+    }
+
+    /// Strips `launch_env` values and launch tokens from the pane commands, titles and
+    /// client commands Zellij reports back, before they are cached, recorded or served.
+    pub(crate) fn redact_event(&self, event: &mut Event) {
+        match event {
+            Event::PaneUpdate(manifest) => {
+                for pane in manifest.panes.values_mut().flatten() {
+                    self.redact_launch_env(&mut pane.title);
+                    if let Some(command) = pane.terminal_command.as_mut() {
+                        self.redact_launch_env(command);
+                    }
+                }
+            }
+            Event::ListClients(clients) => {
+                for client in clients {
+                    self.redact_launch_env(&mut client.running_command);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub(crate) fn launch_cwd_for(&self, origin_pane: Option<u32>) -> Option<PathBuf> {
+        match &self.launch_cwd {
+            LaunchCwd::Focused => {
+                origin_pane.and_then(|pane_id| self.pane_cwds.get(&pane_id).cloned())
+            }
+            LaunchCwd::Home => None,
+            LaunchCwd::Fixed(path) => Some(path.clone()),
+        }
+    }
+
+    /// Replays script `open_command_pane_floating` from these, so every launch records
+    /// one.
+    pub(crate) fn record_launch_result(&mut self, launched: &Result<PaneId, String>) {
+        self.record(|| match launched {
+            Ok(PaneId::Terminal(pane_id)) => RecordedEvent::LaunchResult {
+                pane_id: Some(*pane_id),
+                error: None,
+            },
+            Ok(pane_id) => RecordedEvent::LaunchResult {
+                pane_id: None,
+                error: Some(format!("unexpected pane kind {:?}", pane_id)),
+            },
+            Err(error) => RecordedEvent::LaunchResult {
+                pane_id: None,
+                error: Some(error.clone()),
+            },
+        });
+    }
+
+    /// 128 random bits as hex, so a launch token cannot be guessed from the clock or
+    /// the launch count.
+    fn new_launch_token(&mut self) -> Result<String, String> {
+        let mut bytes = [0u8; 16];
+        self.host.fill_random(&mut bytes)?;
+        Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+    }
+
+    /// Launches a floating Jelly pane; on failure returns the metrics action.
+    pub(crate) fn launch_jelly_pane(
+        &mut self,
+        origin_pane: Option<u32>,
+    ) -> Result<u32, &'static str> {
+        let token = match self.new_launch_token() {
+            Ok(token) => Some(token),
+            Err(error) => {
+                self.push_trace_at(
+                    TraceLevel::Warn,
+                    TraceCategory::Toggle,
+                    "launch_token_failed",
+                )
+                .field("error", error);
+                None
+            }
+        };
+        let mut argv = self.launch_argv(token.as_deref());
+        let cwd = self.launch_cwd_for(origin_pane);
+        let entry = self.push_trace_at(TraceLevel::Debug, TraceCategory::Toggle, "launch_argv");
+        entry.field("program", &argv[0]);
+        if let Some(cwd) = cwd.as_ref() {
+            entry.field("cwd", cwd.display());
+        }
+        let path = PathBuf::from(argv.remove(0));
+        let launched = self.host.open_command_pane_floating(CommandToRun {
+            path,
+            args: argv,
+            cwd,
+        });
+        self.record_launch_result(&launched);
+        match launched {
+            Ok(PaneId::Terminal(pane_id)) => {
+                self.push_trace(TraceCategory::Toggle, "launched_new_jelly_terminal")
+                    .field("pane_id", pane_id);
+                self.lifetime.launches += 1;
+                if let Some(token) = token {
+                    self.ui_tokens.insert(token, pane_id);
+                }
+                // Pane ids only grow, so the lowest belongs to the oldest launch.
+                while self.ui_tokens.len() > UI_TOKEN_LIMIT {
+                    let oldest = self
+                        .ui_tokens
+                        .iter()
+                        .min_by_key(|(_, launched)| **launched)
+                        .map(|(token, _)| token.clone());
+                    if let Some(oldest) = oldest {
+                        self.ui_tokens.remove(&oldest);
+                    }
+                }
+                // Command panes are titled with their command line.
+                self.host.rename_pane(PaneId::Terminal(pane_id), PANE_NAME);
+                self.host.request_plugin_state_snapshot();
+                self.host.show_pane(PaneId::Terminal(pane_id), true, true);
+                Ok(pane_id)
+            }
+            Ok(pane_id) => {
+                self.push_trace_at(
+                    TraceLevel::Error,
+                    TraceCategory::Toggle,
+                    "launched_unexpected_pane_kind",
+                )
+                .field("pane_id", format!("{:?}", pane_id));
+                self.metrics.toggles_mut().launch_failures += 1;
+                Err("launch_failed")
+            }
+            Err(error) => {
+                self.push_trace_at(
+                    TraceLevel::Error,
+                    TraceCategory::Toggle,
+                    "open_command_pane_failed",
+                )
+                .field("error", error);
+                self.metrics.toggles_mut().launch_failures += 1;
+                Err("launch_failed")
+            }
+        }
+    }
+}
@@ -0,0 +1,34 @@
+//! Jelly J butler logic, independent of the wasm host.
+//!
+//! `src/main.rs` is a thin `zellij_tile` binary that forwards plugin callbacks into
+//! [`State`]; everything else lives here so it can be exercised with `cargo test`.
+
+mod activity;
+pub mod fake_host;
+mod focus_history;
+mod fs_watch;
+pub mod host;
+mod launch;
+mod liveness;
+mod metrics;
+mod persist;
+mod protocol;
+pub mod recording;
+pub mod replay;
+mod restarts;
+mod rules;
+mod scratch;
+mod selector;
+mod state;
+mod tab_names;
+mod tags;
+mod toggle;
+mod trace;
+
+pub use host::ZellijHost;
+pub use launch::UI_TOKEN_ENV;
+pub use liveness::{UI_HEARTBEAT_TIMEOUT_MS, UI_STALE_GRACE_MS};
+pub use protocol::protocol_schema;
+pub use restarts::CRASH_LOOP_FREE_RESTARTS;
+pub use state::{State, PANE_NAME, TRACKED_PANE_MISSING_GRACE_MS};
+pub use toggle::{KEYBIND_CLIENT_WAIT_MS, TOGGLE_DEDUP_WINDOW_MS};
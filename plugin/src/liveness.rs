@@ -0,0 +1,107 @@
+//! Liveness of registered `jelly-j ui` processes.
+//!
+//! A UI announces itself with `register_ui` and then sends `ui_heartbeat`. Silence is
+//! measured on the plugin's own event activity rather than the wall clock, so a
+//! suspended session does not make its UIs look dead, and a toggle marks a silent UI
+//! stale before it ever closes its pane.
+
+use crate::host::ZellijHost;
+use crate::protocol::{TraceCategory, TraceLevel};
+use crate::state::State;
+
+/// A registered UI that hasn't sent `ui_heartbeat` for this long of plugin activity is
+/// stale: the next toggle still shows its pane, but marks it.
+pub const UI_HEARTBEAT_TIMEOUT_MS: u128 = 30_000;
+/// How long of plugin activity a stale UI has to heartbeat or register again before a
+/// toggle closes its pane and launches a fresh one.
+pub const UI_STALE_GRACE_MS: u128 = 5 * 60_000;
+/// A gap between two plugin events counts at most this much toward UI silence; a longer
+/// one means the session was suspended, and the UI could not heartbeat either.
+const ACTIVITY_GAP_CAP_MS: u128 = 10_000;
+
+/// A `jelly-j ui` process that announced itself with `register_ui`.
+#[derive(Debug, Clone)]
+pub(crate) struct UiClient {
+    pub(crate) client_id: String,
+    pub(crate) pid: u32,
+    pub(crate) last_heartbeat_ms: u128,
+    /// [`State::activity_ms`] at the last heartbeat or registration.
+    pub(crate) heartbeat_activity_ms: u128,
+    /// [`State::activity_ms`] when a toggle found the UI silent.
+    pub(crate) stale_since_activity_ms: Option<u128>,
+}
+
+impl<H: ZellijHost> State<H> {
+    pub(crate) fn ui_client_is_alive(&self, ui_client: &UiClient) -> bool {
+        self.activity_ms
+            .saturating_sub(ui_client.heartbeat_activity_ms)
+            <= UI_HEARTBEAT_TIMEOUT_MS
+    }
+
+    /// Advances [`Self::activity_ms`] by the time since the previous event.
+    pub(crate) fn note_activity(&mut self) {
+        let now_ms = self.host.now_epoch_millis();
+        if let Some(last_event_ms) = self.last_event_ms {
+            self.activity_ms += now_ms
+                .saturating_sub(last_event_ms)
+                .min(ACTIVITY_GAP_CAP_MS);
+        }
+        self.last_event_ms = Some(now_ms);
+    }
+
+    /// Marks Jelly panes whose UI stopped heartbeating as stale; the toggle still shows
+    /// and focuses them, which gives a UI that was merely slow its chance to heartbeat
+    /// or register again. One still silent [`UI_STALE_GRACE_MS`] later is closed: the
+    /// terminal is alive but the prompt is gone, and the toggle that follows launches a
+    /// working pane.
+    pub(crate) fn close_zombie_panes(&mut self) {
+        let activity_ms = self.activity_ms;
+        let mut zombies = Vec::new();
+        let mut newly_stale = Vec::new();
+        for (pane_id, ui_client) in &self.ui_clients {
+            if self.ui_client_is_alive(ui_client) {
+                continue;
+            }
+            match ui_client.stale_since_activity_ms {
+                None => newly_stale.push(*pane_id),
+                Some(since_ms) if activity_ms.saturating_sub(since_ms) >= UI_STALE_GRACE_MS => {
+                    zombies.push((*pane_id, ui_client.last_heartbeat_ms))
+                }
+                Some(_) => {}
+            }
+        }
+        for pane_id in newly_stale {
+            if let Some(ui_client) = self.ui_clients.get_mut(&pane_id) {
+                ui_client.stale_since_activity_ms = Some(activity_ms);
+            }
+            self.push_trace_at(TraceLevel::Warn, TraceCategory::Toggle, "ui_stale")
+                .field("id", pane_id);
+        }
+        let now_ms = self.host.now_epoch_millis();
+        for (pane_id, last_heartbeat_ms) in zombies {
+            self.ui_clients.remove(&pane_id);
+            let Some((_, pane)) = self.find_terminal_pane_by_id(pane_id) else {
+                continue;
+            };
+            if !self.may_close_jelly_pane(&pane) {
+                self.push_trace_at(
+                    TraceLevel::Warn,
+                    TraceCategory::Toggle,
+                    "not_closing_unconfirmed_zombie_pane",
+                )
+                .field("id", pane_id);
+                continue;
+            }
+            self.push_trace_at(
+                TraceLevel::Warn,
+                TraceCategory::Toggle,
+                "closing_zombie_jelly_pane",
+            )
+            .field("id", pane_id)
+            .field("silent_ms", now_ms.saturating_sub(last_heartbeat_ms));
+            self.host.close_terminal_pane(pane_id);
+            self.metrics.toggles_mut().zombies_replaced += 1;
+            self.forget_jelly_pane(pane_id);
+        }
+    }
+}
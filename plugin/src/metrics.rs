@@ -0,0 +1,129 @@
+//! Counters and latency histograms served by `get_metrics`.
+//!
+//! Everything here is a fixed-size counter update on the hot path; building the
+//! response is the only allocation, so the daemon can poll it from its health check.
+
+use crate::protocol::{EventRate, LatencyHistogram, MetricsResult, PaneExitMetrics, ToggleMetrics};
+use std::collections::BTreeMap;
+
+/// Upper bounds (inclusive) of the toggle latency buckets; a final bucket catches
+/// everything slower.
+pub(crate) const LATENCY_BUCKETS_MS: &[u64] = &[5, 10, 25, 50, 100, 250, 500, 1_000, 2_500];
+
+#[derive(Debug, Default)]
+pub(crate) struct Metrics {
+    started_epoch_ms: Option<u128>,
+    requests_by_op: BTreeMap<String, u64>,
+    errors_by_op: BTreeMap<String, u64>,
+    errors_by_code: BTreeMap<String, u64>,
+    toggles: ToggleMetrics,
+    pane_exits: PaneExitMetrics,
+    toggle_latency: BTreeMap<&'static str, Histogram>,
+    events: BTreeMap<&'static str, u64>,
+}
+
+#[derive(Debug, Default)]
+struct Histogram {
+    counts: Vec<u64>,
+    count: u64,
+    sum_ms: u64,
+    max_ms: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, latency_ms: u64) {
+        if self.counts.is_empty() {
+            self.counts = vec![0; LATENCY_BUCKETS_MS.len() + 1];
+        }
+        let bucket = LATENCY_BUCKETS_MS
+            .iter()
+            .position(|bound| latency_ms <= *bound)
+            .unwrap_or(LATENCY_BUCKETS_MS.len());
+        self.counts[bucket] += 1;
+        self.count += 1;
+        self.sum_ms = self.sum_ms.saturating_add(latency_ms);
+        self.max_ms = self.max_ms.max(latency_ms);
+    }
+}
+
+impl Metrics {
+    pub(crate) fn start(&mut self, now_ms: u128) {
+        self.started_epoch_ms.get_or_insert(now_ms);
+    }
+
+    pub(crate) fn toggles_mut(&mut self) -> &mut ToggleMetrics {
+        &mut self.toggles
+    }
+
+    pub(crate) fn pane_exits_mut(&mut self) -> &mut PaneExitMetrics {
+        &mut self.pane_exits
+    }
+
+    pub(crate) fn count_event(&mut self, kind: &'static str) {
+        *self.events.entry(kind).or_default() += 1;
+    }
+
+    /// `error_code` is `None` for successful responses.
+    pub(crate) fn count_request(&mut self, op: &str, error_code: Option<&str>) {
+        *self.requests_by_op.entry(op.to_owned()).or_default() += 1;
+        if let Some(code) = error_code {
+            *self.errors_by_op.entry(op.to_owned()).or_default() += 1;
+            *self.errors_by_code.entry(code.to_owned()).or_default() += 1;
+        }
+    }
+
+    /// Time from receiving a toggle pipe message to the host call that acted on it.
+    pub(crate) fn observe_toggle_latency(&mut self, action: &'static str, latency_ms: u128) {
+        self.toggle_latency
+            .entry(action)
+            .or_default()
+            .observe(u64::try_from(latency_ms).unwrap_or(u64::MAX));
+    }
+
+    pub(crate) fn snapshot(&self, now_ms: u128) -> MetricsResult {
+        let uptime_ms = now_ms.saturating_sub(self.started_epoch_ms.unwrap_or(now_ms));
+        let uptime_minutes = uptime_ms as f64 / 60_000.0;
+        MetricsResult {
+            uptime_ms: u64::try_from(uptime_ms).unwrap_or(u64::MAX),
+            requests_by_op: self.requests_by_op.clone(),
+            errors_by_op: self.errors_by_op.clone(),
+            errors_by_code: self.errors_by_code.clone(),
+            toggles: self.toggles.clone(),
+            pane_exits: self.pane_exits.clone(),
+            latency_bucket_bounds_ms: LATENCY_BUCKETS_MS.to_vec(),
+            toggle_latency_ms: self
+                .toggle_latency
+                .iter()
+                .map(|(action, histogram)| {
+                    (
+                        (*action).to_owned(),
+                        LatencyHistogram {
+                            counts: histogram.counts.clone(),
+                            count: histogram.count,
+                            sum_ms: histogram.sum_ms,
+                            max_ms: histogram.max_ms,
+                        },
+                    )
+                })
+                .collect(),
+            events: self
+                .events
+                .iter()
+                .map(|(kind, count)| {
+                    let per_minute = if uptime_minutes > 0.0 {
+                        *count as f64 / uptime_minutes
+                    } else {
+                        0.0
+                    };
+                    (
+                        (*kind).to_owned(),
+                        EventRate {
+                            count: *count,
+                            per_minute,
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,235 @@
+//! Toggle state that survives plugin reloads and upgrades.
+//!
+//! A fresh `State` knows nothing about the Jelly pane the previous instance was
+//! tracking, so the first toggle after a reload used to launch a second pane and
+//! then close one of the duplicates. The butler now writes a small JSON file to the
+//! plugin's `/data` folder and, on load, holds on to what it read until the first
+//! `PaneUpdate` confirms the pane still exists and still looks like Jelly J.
+
+use crate::host::ZellijHost;
+use crate::protocol::{LifetimeCounters, PaneGeometry, TraceCategory, TraceLevel};
+use crate::state::{OwnedPane, State};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+pub(crate) const DEFAULT_STATE_PATH: &str = "/data/butler-state.json";
+/// Bumped when a field changes meaning; files with another version are ignored.
+pub(crate) const STATE_FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct PersistedState {
+    pub(crate) version: u32,
+    #[serde(default)]
+    pub(crate) jelly_pane_id: Option<u32>,
+    /// Panes whose UI registered with a launch token.
+    #[serde(default)]
+    pub(crate) registered_jelly_panes: Vec<u32>,
+    #[serde(default)]
+    pub(crate) jelly_geometry: Option<PaneGeometry>,
+    #[serde(default)]
+    pub(crate) counters: LifetimeCounters,
+    /// Tags by pane id; restored for panes that still exist.
+    #[serde(default)]
+    pub(crate) pane_tags: BTreeMap<u32, BTreeSet<String>>,
+    /// Scratch pane ids by name; restored for panes that still exist.
+    #[serde(default)]
+    pub(crate) scratch_panes: BTreeMap<String, u32>,
+}
+
+#[derive(Debug)]
+pub(crate) struct StateStore {
+    path: String,
+    /// Last contents written, so unchanged state costs no I/O.
+    last_written: Option<String>,
+}
+
+impl StateStore {
+    /// Persistence is on unless `persist_state` is `false`/`off`; `state_path`
+    /// overrides the file location.
+    pub(crate) fn from_configuration(configuration: &BTreeMap<String, String>) -> Option<Self> {
+        if matches!(
+            configuration.get("persist_state").map(|value| value.trim()),
+            Some("false") | Some("off")
+        ) {
+            return None;
+        }
+        let path = configuration
+            .get("state_path")
+            .map(|value| value.trim())
+            .filter(|value| !value.is_empty())
+            .unwrap_or(DEFAULT_STATE_PATH);
+        Some(StateStore {
+            path: path.to_owned(),
+            last_written: None,
+        })
+    }
+
+    pub(crate) fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// `Ok(None)` when nothing was saved yet.
+    pub(crate) fn read<H: ZellijHost>(
+        &mut self,
+        host: &H,
+    ) -> Result<Option<PersistedState>, String> {
+        if host.file_len(&self.path).is_none() {
+            return Ok(None);
+        }
+        let contents = host.read_file(&self.path)?;
+        let persisted =
+            serde_json::from_str::<PersistedState>(&contents).map_err(|error| error.to_string())?;
+        if persisted.version != STATE_FORMAT_VERSION {
+            return Err(format!(
+                "unsupported state version {} (expected {})",
+                persisted.version, STATE_FORMAT_VERSION
+            ));
+        }
+        self.last_written = Some(contents);
+        Ok(Some(persisted))
+    }
+
+    pub(crate) fn write<H: ZellijHost>(
+        &mut self,
+        host: &mut H,
+        state: &PersistedState,
+    ) -> Result<(), String> {
+        let contents = serde_json::to_string(state).map_err(|error| error.to_string())?;
+        if self.last_written.as_deref() == Some(contents.as_str()) {
+            return Ok(());
+        }
+        host.write_file(&self.path, &contents)?;
+        self.last_written = Some(contents);
+        Ok(())
+    }
+}
+
+impl<H: ZellijHost> State<H> {
+    pub(crate) fn read_persisted_state(&mut self, configuration: &BTreeMap<String, String>) {
+        self.state_store = StateStore::from_configuration(configuration);
+        if let Some(store) = self.state_store.as_mut() {
+            match store.read(&self.host) {
+                Ok(Some(persisted)) => {
+                    self.lifetime = persisted.counters.clone();
+                    self.jelly_geometry = persisted.jelly_geometry;
+                    self.restored = Some(persisted);
+                }
+                Ok(None) => {}
+                Err(error) => {
+                    let path = store.path().to_owned();
+                    self.push_trace_at(
+                        TraceLevel::Warn,
+                        TraceCategory::Lifecycle,
+                        "persisted_state_ignored",
+                    )
+                    .field("path", path)
+                    .field("error", error);
+                }
+            }
+        }
+        self.lifetime.loads += 1;
+    }
+
+    /// Pane ids are reused across sessions, so a persisted id is only trusted if the
+    /// pane is still there and still looks like Jelly J.
+    pub(crate) fn adopt_persisted_state(&mut self, persisted: PersistedState) {
+        for (pane_id, tags) in persisted.pane_tags {
+            if self.terminal_pane_present(pane_id) {
+                self.pane_tags.insert(pane_id, tags);
+            }
+        }
+        for (name, pane_id) in persisted.scratch_panes {
+            if self.scratch_commands.contains_key(&name) && self.terminal_pane_present(pane_id) {
+                self.scratch_panes.insert(
+                    name,
+                    OwnedPane {
+                        pane_id,
+                        launched_ms: None,
+                    },
+                );
+            }
+        }
+        // A registration is only as good as the pane it names, so restored ones must
+        // also pass the heuristic.
+        for pane_id in persisted.registered_jelly_panes {
+            if self
+                .find_terminal_pane_by_id(pane_id)
+                .is_some_and(|(_, pane)| self.looks_like_jelly_pane(&pane))
+            {
+                self.registered_jelly_panes.insert(pane_id);
+            }
+        }
+        let Some(pane_id) = persisted.jelly_pane_id else {
+            return;
+        };
+        if self.jelly_pane_id.is_some() {
+            return;
+        }
+        let verdict = match self.find_terminal_pane_by_id(pane_id) {
+            Some((_, pane)) if self.is_jelly_pane(&pane) => None,
+            Some(_) => Some("not_a_jelly_pane"),
+            None => Some("missing"),
+        };
+        match verdict {
+            None => {
+                self.jelly_pane_id = Some(pane_id);
+                self.restored_jelly_pane = true;
+                self.push_trace(TraceCategory::Lifecycle, "restored_tracked_jelly_pane")
+                    .field("id", pane_id);
+            }
+            Some(reason) => {
+                self.push_trace(TraceCategory::Lifecycle, "discarded_persisted_jelly_pane")
+                    .field("id", pane_id)
+                    .field("reason", reason);
+            }
+        }
+    }
+
+    pub(crate) fn capture_jelly_geometry(&mut self, pane_id: u32) {
+        if let Some((_, pane)) = self.find_terminal_pane_by_id(pane_id) {
+            if pane.is_floating && !pane.is_suppressed && pane.pane_columns > 0 {
+                self.jelly_geometry = Some(PaneGeometry {
+                    x: pane.pane_x,
+                    y: pane.pane_y,
+                    columns: pane.pane_columns,
+                    rows: pane.pane_rows,
+                });
+            }
+        }
+    }
+
+    /// Writes toggle state once the first PaneUpdate has validated what was restored;
+    /// before that we would overwrite the previous instance's state with nothing.
+    pub(crate) fn save_persisted_state(&mut self) {
+        if !self.seen_pane_update {
+            return;
+        }
+        let snapshot = PersistedState {
+            version: STATE_FORMAT_VERSION,
+            jelly_pane_id: self.jelly_pane_id,
+            registered_jelly_panes: self.registered_jelly_panes.iter().copied().collect(),
+            jelly_geometry: self.jelly_geometry,
+            counters: self.lifetime.clone(),
+            pane_tags: self.pane_tags.clone(),
+            scratch_panes: self
+                .scratch_panes
+                .iter()
+                .map(|(name, scratch_pane)| (name.clone(), scratch_pane.pane_id))
+                .collect(),
+        };
+        let Some(store) = self.state_store.as_mut() else {
+            return;
+        };
+        if let Err(error) = store.write(&mut self.host, &snapshot) {
+            let path = store.path().to_owned();
+            self.state_store = None;
+            self.push_trace_at(
+                TraceLevel::Warn,
+                TraceCategory::Lifecycle,
+                "persist_state_disabled",
+            )
+            .field("path", path)
+            .field("error", error);
+        }
+    }
+}
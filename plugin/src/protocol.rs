@@ -0,0 +1,803 @@
+//! Wire types for the butler `request` pipe.
+//!
+//! This module is the source of truth for the protocol. It is compiled both into the
+//! plugin and into `build.rs`, which checks `SUPPORTED_OPS` against the request variants,
+//! so it must not depend on `zellij_tile`. `tests/protocol_schema.rs` writes the JSON
+//! Schema artifacts that the TypeScript client typechecks against.
+
+use schemars::{schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+/// Bumped whenever a request or response shape changes incompatibly.
+pub(crate) const PROTOCOL_VERSION: u32 = 1;
+pub(crate) const SUPPORTED_OPS: &[&str] = &[
+    "hello",
+    "get_schema",
+    "ping",
+    "get_state",
+    "get_metrics",
+    "get_trace",
+    "clear_trace",
+    "export_trace",
+    "rename_tab",
+    "rename_pane",
+    "hide_pane",
+    "show_pane",
+    "batch",
+    "start_recording",
+    "stop_recording",
+    "get_recording",
+    "register_ui_pane",
+    "register_ui",
+    "ui_heartbeat",
+    "get_pane_info",
+    "get_tab_name_suggestions",
+    "list_rules",
+    "add_rule",
+    "remove_rule",
+    "get_activity",
+    "focus_back",
+    "get_focus_history",
+    "tag_pane",
+    "untag_pane",
+    "list_tags",
+    "hide_panes",
+    "show_panes",
+    "float_panes",
+    "close_panes",
+    "toggle_scratch",
+    "open_file",
+    "get_fs_changes",
+];
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub(crate) enum ButlerRequest {
+    Hello,
+    GetSchema,
+    Ping,
+    GetState,
+    GetMetrics,
+    GetTrace {
+        limit: Option<usize>,
+        /// Minimum level to include.
+        level: Option<TraceLevel>,
+        category: Option<TraceCategory>,
+        /// Only entries with a greater `seq`.
+        since_seq: Option<u64>,
+        /// `text` (default) keeps the legacy `"0001 +12ms message k=v"` lines.
+        format: Option<TraceFormat>,
+    },
+    ClearTrace,
+    /// Writes the trace as JSON lines to a file under the plugin's `/data` or `/tmp`
+    /// folder; any other path fails with `invalid_path`. Includes the persisted log
+    /// files when `trace_log` is configured.
+    ExportTrace {
+        path: String,
+    },
+    RenameTab {
+        position: usize,
+        name: String,
+    },
+    RenamePane {
+        pane_id: PaneTarget,
+        name: String,
+    },
+    HidePane {
+        pane_id: PaneTarget,
+    },
+    ShowPane {
+        pane_id: PaneTarget,
+        should_float_if_hidden: Option<bool>,
+        should_focus_pane: Option<bool>,
+    },
+    Batch {
+        requests: Vec<ButlerRequest>,
+        #[serde(default)]
+        stop_on_error: bool,
+        #[serde(default)]
+        atomic: bool,
+    },
+    /// Starts (or restarts) recording raw inbound events for replay.
+    StartRecording {
+        limit: Option<usize>,
+    },
+    /// Stops recording and returns what was captured.
+    StopRecording,
+    GetRecording {
+        since_seq: Option<u64>,
+    },
+    /// Sent by `jelly-j ui` at startup with the token the butler put in its
+    /// environment (`JELLY_J_BUTLER_TOKEN`), proving the pane runs Jelly J.
+    RegisterUiPane {
+        pane_id: u32,
+        token: String,
+    },
+    /// Sent by `jelly-j ui` once it is connected to the daemon; starts liveness
+    /// tracking for its pane.
+    RegisterUi {
+        pane_id: u32,
+        /// The id the UI registered with at the daemon.
+        client_id: String,
+        pid: u32,
+    },
+    /// Periodic liveness ping from a registered UI. Fails with `ui_not_registered`
+    /// after a plugin reload, telling the UI to send `register_ui` again.
+    UiHeartbeat {
+        pane_id: u32,
+    },
+    /// Everything the butler knows about one terminal pane, exited panes included.
+    /// A selector must match exactly one pane (`ambiguous_selector` otherwise).
+    GetPaneInfo {
+        pane_id: PaneTarget,
+    },
+    /// Names the `auto_name_tabs` rules would give default-named (`Tab #N`) tabs.
+    /// Fails with `auto_name_tabs_off` unless the config is `suggest` or `apply`.
+    GetTabNameSuggestions,
+    ListRules,
+    /// Adds a rule to the rules file. Fails with `rule_exists` if the id is taken.
+    AddRule {
+        rule: WorkspaceRule,
+    },
+    /// Removes a rule from the rules file; rules from the plugin config are read-only.
+    RemoveRule {
+        id: String,
+    },
+    /// Tabs and terminal panes with their activity, stalest first.
+    GetActivity,
+    /// Focuses the pane `steps` (default 1) back in the focus history, skipping the
+    /// pane focused now. Fails with `no_focus_history` if the history is shorter.
+    FocusBack {
+        steps: Option<usize>,
+    },
+    GetFocusHistory,
+    /// Adds labels such as `project:api` to panes; they survive renames and reloads.
+    TagPane {
+        pane_id: PaneTarget,
+        tags: Vec<String>,
+    },
+    /// Removes `tags`, or every tag when omitted.
+    UntagPane {
+        pane_id: PaneTarget,
+        tags: Option<Vec<String>>,
+    },
+    ListTags,
+    /// Hides every matching pane that is not hidden yet. Jelly panes are never matched
+    /// by the bulk ops; toggling them is the butler's job.
+    HidePanes {
+        selector: String,
+    },
+    /// Shows every matching hidden pane, without focusing them unless asked.
+    ShowPanes {
+        selector: String,
+        should_float_if_hidden: Option<bool>,
+        should_focus_pane: Option<bool>,
+    },
+    /// Floats every matching visible tiled pane.
+    FloatPanes {
+        selector: String,
+    },
+    /// Closes every matching pane; `dry_run` only reports which ones would close.
+    ClosePanes {
+        selector: String,
+        #[serde(default)]
+        dry_run: bool,
+    },
+    /// Hides, shows, moves or launches the `scratch.<name>.command` pane, like the
+    /// Jelly toggle. `client_id` picks whose tab it lands in (default: the active tab).
+    ToggleScratch {
+        name: String,
+        client_id: Option<u16>,
+    },
+    /// Opens `path` in the user's `$EDITOR` at `line` (1-based): as a new tiled pane,
+    /// floating, or in place of `in_place_of_pane_id` (an id, or a selector matching one
+    /// pane), which comes back when the editor exits. Relative paths resolve against the
+    /// focused pane's cwd.
+    OpenFile {
+        path: String,
+        line: Option<usize>,
+        #[serde(default)]
+        floating: bool,
+        #[serde(alias = "in_place_of")]
+        in_place_of_pane_id: Option<PaneTarget>,
+    },
+    /// Filesystem change summaries with a `seq` above `since`, oldest first. Empty
+    /// unless the `fs_watch` config is on.
+    GetFsChanges {
+        since: Option<u64>,
+    },
+}
+
+/// A terminal pane id, or a selector such as `"tag:role:logs"` that may match several
+/// panes (see `plugin/src/tags.rs`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub(crate) enum PaneTarget {
+    Id(u32),
+    Selector(String),
+}
+
+/// "When `when` happens, run `actions`": see `plugin/src/rules.rs`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct WorkspaceRule {
+    pub(crate) id: String,
+    pub(crate) when: RuleTrigger,
+    /// Request objects as sent to the `request` pipe. `$pane_id` and `$tab_position`
+    /// in any string are replaced with the pane and tab that fired the rule (a string
+    /// that is only the placeholder becomes a number), e.g. `"tab:$tab_position"`;
+    /// `tab_appeared` rules have no pane, so they may not use `$pane_id`.
+    #[serde(default)]
+    pub(crate) actions: Vec<Value>,
+    /// Logged as a `rule` trace entry at `warn` when the rule fires; nothing is shown in
+    /// the UI. Read it back with `get_trace { category: "rule" }`. Formerly `notify`.
+    #[serde(default, alias = "notify", skip_serializing_if = "Option::is_none")]
+    pub(crate) trace_message: Option<String>,
+    /// Minimum time between two firings, default 1000. Matches from the same update
+    /// all run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) cooldown_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub(crate) enum RuleTrigger {
+    /// A terminal pane's command exited.
+    PaneExited {
+        /// Substring of the pane's command.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        command: Option<String>,
+        /// Only fire for a non-zero exit status.
+        #[serde(default)]
+        nonzero_only: bool,
+    },
+    /// A terminal pane appeared.
+    PaneOpened {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        command: Option<String>,
+        /// Substring of the pane title.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        title: Option<String>,
+    },
+    /// A tab with this exact name appeared (created or renamed).
+    TabAppeared { name: String },
+}
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum TraceLevel {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum TraceCategory {
+    /// Plugin load, subscriptions and state events.
+    Lifecycle,
+    Permission,
+    Pipe,
+    Toggle,
+    Request,
+    /// Workspace rules firing.
+    Rule,
+    /// Filesystem change summaries.
+    Fs,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum TraceFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct TraceEntry {
+    pub(crate) seq: u64,
+    pub(crate) epoch_ms: u64,
+    /// Milliseconds since the first trace entry of this plugin instance.
+    pub(crate) delta_ms: u64,
+    pub(crate) level: TraceLevel,
+    pub(crate) category: TraceCategory,
+    pub(crate) message: String,
+    pub(crate) fields: BTreeMap<String, String>,
+}
+
+impl TraceEntry {
+    /// Legacy single-line rendering: `"0001 +12ms message k=v k=v"`.
+    pub(crate) fn to_text(&self) -> String {
+        let mut line = format!("{:04} +{}ms {}", self.seq, self.delta_ms, self.message);
+        for (key, value) in &self.fields {
+            line.push_str(&format!(" {}={}", key, value));
+        }
+        line
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
+pub(crate) struct ErrorResponse {
+    pub(crate) ok: bool,
+    pub(crate) code: String,
+    pub(crate) error: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub(crate) struct AckResult {
+    pub(crate) ok: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub(crate) struct HelloResult {
+    pub(crate) protocol_version: u32,
+    pub(crate) crate_version: String,
+    pub(crate) supported_ops: Vec<String>,
+    pub(crate) permission_status: String,
+    pub(crate) granted_permissions: Vec<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(untagged)]
+pub(crate) enum TraceEntries {
+    Text(Vec<String>),
+    Json(Vec<TraceEntry>),
+}
+
+#[derive(Serialize, JsonSchema)]
+pub(crate) struct TraceResult {
+    pub(crate) entries: TraceEntries,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub(crate) struct ExportTraceResult {
+    pub(crate) path: String,
+    pub(crate) entries: usize,
+    pub(crate) bytes: u64,
+    /// Whether the export came from the persisted log rather than the in-memory ring.
+    pub(crate) persisted: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub(crate) struct BatchResult {
+    /// One response envelope per executed step.
+    pub(crate) steps: Vec<Value>,
+    pub(crate) completed: usize,
+    pub(crate) stopped_early: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub(crate) struct RecordingResult {
+    pub(crate) recording: bool,
+    /// Entries evicted because the recording hit its limit.
+    pub(crate) dropped: u64,
+    /// Recorded entries (`seq`, `epoch_ms`, `event`); see `plugin/src/recording.rs`.
+    pub(crate) entries: Vec<Value>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
+pub(crate) struct ToggleMetrics {
+    /// Toggle pipe messages, including ignored ones.
+    pub(crate) received: u64,
+    pub(crate) dedup_window_ignored: u64,
+    pub(crate) duplicate_ignored: u64,
+    /// Toggles that reached a show/hide/move/launch decision.
+    pub(crate) executed: u64,
+    pub(crate) launch_failures: u64,
+    /// Tracked Jelly panes forgotten after the missing-pane grace period.
+    pub(crate) grace_period_timeouts: u64,
+    /// Jelly panes closed and relaunched because their UI stopped heartbeating.
+    pub(crate) zombies_replaced: u64,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub(crate) struct LatencyHistogram {
+    /// One count per `latency_bucket_bounds_ms` entry, plus a final overflow bucket.
+    pub(crate) counts: Vec<u64>,
+    pub(crate) count: u64,
+    pub(crate) sum_ms: u64,
+    pub(crate) max_ms: u64,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub(crate) struct EventRate {
+    pub(crate) count: u64,
+    /// Average over the plugin's uptime.
+    pub(crate) per_minute: f64,
+}
+
+/// Exits of tracked Jelly panes' commands and what `on_exit` did about them.
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
+pub(crate) struct PaneExitMetrics {
+    pub(crate) detected: u64,
+    /// Commands restarted in place (`on_exit "rerun"`).
+    pub(crate) reruns: u64,
+    /// Exited panes closed (`on_exit "close"`), relaunched if they were on screen.
+    pub(crate) closed: u64,
+    /// Restarts delayed by the crash-loop backoff.
+    pub(crate) backoffs: u64,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub(crate) struct MetricsResult {
+    pub(crate) uptime_ms: u64,
+    /// Top-level requests; batch steps count towards `batch`.
+    pub(crate) requests_by_op: BTreeMap<String, u64>,
+    pub(crate) errors_by_op: BTreeMap<String, u64>,
+    pub(crate) errors_by_code: BTreeMap<String, u64>,
+    pub(crate) toggles: ToggleMetrics,
+    pub(crate) pane_exits: PaneExitMetrics,
+    pub(crate) latency_bucket_bounds_ms: Vec<u64>,
+    /// Pipe receipt to the acting host call, keyed by action (`hide`, `show`, `move`,
+    /// `launch`, ...).
+    pub(crate) toggle_latency_ms: BTreeMap<String, LatencyHistogram>,
+    /// Inbound events by kind (`pane_update`, `tab_update`, `pipe_toggle`, ...).
+    pub(crate) events: BTreeMap<String, EventRate>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub(crate) struct ButlerTabState {
+    pub(crate) position: usize,
+    pub(crate) name: String,
+    pub(crate) active: bool,
+    pub(crate) selectable_tiled_panes_count: usize,
+    pub(crate) selectable_floating_panes_count: usize,
+    pub(crate) activity: Option<ButlerActivity>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub(crate) struct ButlerPaneState {
+    pub(crate) id: u32,
+    pub(crate) tab_index: usize,
+    pub(crate) title: String,
+    pub(crate) terminal_command: Option<String>,
+    pub(crate) is_plugin: bool,
+    pub(crate) is_focused: bool,
+    pub(crate) is_floating: bool,
+    pub(crate) is_suppressed: bool,
+    pub(crate) exited: bool,
+    /// Last directory reported by `CwdChanged`; terminal panes only.
+    pub(crate) cwd: Option<String>,
+    /// What the pane is running in the foreground, as last reported by `ListClients`
+    /// for a client focused on it, else the command a command pane was started with.
+    pub(crate) foreground_command: Option<String>,
+    /// Labels from `tag_pane`, sorted.
+    pub(crate) tags: Vec<String>,
+    /// Terminal panes only; see `plugin/src/activity.rs`.
+    pub(crate) activity: Option<ButlerActivity>,
+}
+
+/// Focus and change times the butler observed since it loaded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub(crate) struct ButlerActivity {
+    /// When the butler first saw it; earlier history is unknown.
+    pub(crate) first_seen_epoch_ms: u64,
+    /// Now while focused; `None` if never focused since `first_seen_epoch_ms`.
+    pub(crate) last_focused_epoch_ms: Option<u64>,
+    /// Last title, command or exit change (for tabs: name or any of its panes).
+    pub(crate) last_changed_epoch_ms: u64,
+    /// Total time focused.
+    pub(crate) focus_ms: u64,
+    /// Time since it was last focused or changed; 0 while focused.
+    pub(crate) idle_ms: u64,
+}
+
+/// Result of `get_activity`: both lists sorted by `idle_ms`, stalest first.
+#[derive(Serialize, JsonSchema)]
+pub(crate) struct ActivityResult {
+    pub(crate) now_epoch_ms: u64,
+    pub(crate) tabs: Vec<TabActivity>,
+    pub(crate) panes: Vec<PaneActivity>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub(crate) struct TabActivity {
+    pub(crate) position: usize,
+    pub(crate) name: String,
+    #[serde(flatten)]
+    pub(crate) activity: ButlerActivity,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub(crate) struct PaneActivity {
+    pub(crate) id: u32,
+    pub(crate) tab_index: usize,
+    pub(crate) title: String,
+    pub(crate) is_jelly: bool,
+    #[serde(flatten)]
+    pub(crate) activity: ButlerActivity,
+}
+
+/// Result of `get_pane_info`.
+#[derive(Serialize, JsonSchema)]
+pub(crate) struct ButlerPaneInfo {
+    #[serde(flatten)]
+    pub(crate) pane: ButlerPaneState,
+    pub(crate) geometry: PaneGeometry,
+    pub(crate) is_fullscreen: bool,
+    pub(crate) is_held: bool,
+    pub(crate) exit_status: Option<i32>,
+    /// Whether the butler treats this pane as a Jelly pane.
+    pub(crate) is_jelly: bool,
+    /// Whether a `jelly-j ui` in this pane proved itself with its launch token.
+    pub(crate) registered_ui: bool,
+}
+
+/// Result of `get_focus_history`: most recent first, the current focus included.
+/// Jelly panes are not recorded.
+#[derive(Serialize, JsonSchema)]
+pub(crate) struct FocusHistoryResult {
+    pub(crate) panes: Vec<PaneFocusEntry>,
+    pub(crate) tabs: Vec<TabFocusEntry>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub(crate) struct PaneFocusEntry {
+    pub(crate) pane_id: u32,
+    pub(crate) tab_position: usize,
+    pub(crate) focused_epoch_ms: u64,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub(crate) struct TabFocusEntry {
+    pub(crate) position: usize,
+    pub(crate) focused_epoch_ms: u64,
+}
+
+/// Result of `focus_back`: the pane that was focused.
+#[derive(Serialize, JsonSchema)]
+pub(crate) struct FocusBackResult {
+    pub(crate) pane_id: u32,
+    pub(crate) tab_position: usize,
+}
+
+/// One burst of filesystem changes, paths relative to the watched folder.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct FsChangeSummary {
+    pub(crate) seq: u64,
+    pub(crate) first_epoch_ms: u64,
+    pub(crate) last_epoch_ms: u64,
+    pub(crate) created: Vec<String>,
+    pub(crate) updated: Vec<String>,
+    pub(crate) deleted: Vec<String>,
+    /// Changed paths left out of the lists above to keep the summary small.
+    pub(crate) omitted: usize,
+}
+
+/// Result of `get_fs_changes`.
+#[derive(Serialize, JsonSchema)]
+pub(crate) struct FsChangesResult {
+    /// Whether the `fs_watch` config is on.
+    pub(crate) watching: bool,
+    pub(crate) include: Vec<String>,
+    pub(crate) exclude: Vec<String>,
+    pub(crate) summaries: Vec<FsChangeSummary>,
+}
+
+/// Result of `open_file`: the editor pane.
+#[derive(Serialize, JsonSchema)]
+pub(crate) struct OpenFileResult {
+    pub(crate) pane_id: u32,
+}
+
+/// Result of `toggle_scratch`.
+#[derive(Serialize, JsonSchema)]
+pub(crate) struct ScratchResult {
+    pub(crate) name: String,
+    /// The scratch pane, once one exists.
+    pub(crate) pane_id: Option<u32>,
+    /// `launch`, `show`, `hide`, `move` or `launch_skipped` (still starting).
+    pub(crate) action: String,
+}
+
+/// Result of `hide_panes`, `show_panes`, `float_panes` and `close_panes`.
+#[derive(Serialize, JsonSchema)]
+pub(crate) struct PanesResult {
+    /// The panes acted on (or, with `dry_run`, that would be).
+    pub(crate) pane_ids: Vec<u32>,
+    pub(crate) dry_run: bool,
+}
+
+/// Result of `list_tags`: every tag in use, sorted.
+#[derive(Serialize, JsonSchema)]
+pub(crate) struct ListTagsResult {
+    pub(crate) tags: Vec<TagSummary>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub(crate) struct TagSummary {
+    pub(crate) tag: String,
+    pub(crate) pane_ids: Vec<u32>,
+}
+
+/// Result of `list_rules`.
+#[derive(Serialize, JsonSchema)]
+pub(crate) struct ListRulesResult {
+    /// The editable rules file.
+    pub(crate) path: String,
+    pub(crate) rules: Vec<ListedRule>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub(crate) struct ListedRule {
+    #[serde(flatten)]
+    pub(crate) rule: WorkspaceRule,
+    /// `config` (read-only) or `file`.
+    pub(crate) source: String,
+    /// Times fired since plugin load.
+    pub(crate) fired: u64,
+    pub(crate) last_fired_epoch_ms: Option<u64>,
+}
+
+/// Result of `get_tab_name_suggestions`.
+#[derive(Serialize, JsonSchema)]
+pub(crate) struct TabNameSuggestionsResult {
+    /// The `auto_name_tabs` config: `suggest` or `apply`.
+    pub(crate) mode: String,
+    pub(crate) suggestions: Vec<TabNameSuggestion>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub(crate) struct TabNameSuggestion {
+    pub(crate) position: usize,
+    pub(crate) current_name: String,
+    pub(crate) name: String,
+    /// Which rule produced the name: `repo`, `cwd` or `command`.
+    pub(crate) reason: String,
+}
+
+/// Floating position and size of a pane, in cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct PaneGeometry {
+    pub(crate) x: usize,
+    pub(crate) y: usize,
+    pub(crate) columns: usize,
+    pub(crate) rows: usize,
+}
+
+/// Counters that survive plugin reloads (see `plugin/src/persist.rs`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct LifetimeCounters {
+    #[serde(default)]
+    pub(crate) loads: u64,
+    #[serde(default)]
+    pub(crate) toggles: u64,
+    #[serde(default)]
+    pub(crate) launches: u64,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub(crate) struct ButlerClientPane {
+    pub(crate) client_id: u16,
+    pub(crate) pane_id: u32,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub(crate) struct ButlerUiClient {
+    pub(crate) pane_id: u32,
+    pub(crate) client_id: String,
+    pub(crate) pid: u32,
+    pub(crate) last_heartbeat_epoch_ms: u64,
+    /// Heard from within the heartbeat timeout.
+    pub(crate) alive: bool,
+    /// A toggle found it silent; its pane is replaced if it stays silent through the
+    /// grace period.
+    pub(crate) stale: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub(crate) struct ButlerTabPane {
+    pub(crate) tab_index: usize,
+    pub(crate) pane_id: u32,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub(crate) struct ButlerScratchPane {
+    pub(crate) name: String,
+    pub(crate) command: String,
+    pub(crate) pane_id: Option<u32>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub(crate) struct ButlerWorkspaceState {
+    pub(crate) tabs: Vec<ButlerTabState>,
+    pub(crate) panes: Vec<ButlerPaneState>,
+    pub(crate) butler: ButlerRuntimeState,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub(crate) struct ButlerRuntimeState {
+    pub(crate) ready: bool,
+    pub(crate) permission_result_seen: bool,
+    pub(crate) permission_denied: bool,
+    pub(crate) pending_toggle: bool,
+    pub(crate) pane_update_count: u64,
+    pub(crate) tab_update_count: u64,
+    pub(crate) trace_len: usize,
+    pub(crate) trace_log_path: Option<String>,
+    pub(crate) jelly_pane_id: Option<u32>,
+    /// Panes whose UI registered with its launch token; only these (or panes that
+    /// look unmistakably like Jelly) are ever closed.
+    pub(crate) registered_jelly_panes: Vec<u32>,
+    /// UIs that sent `register_ui`, with their liveness.
+    pub(crate) ui_clients: Vec<ButlerUiClient>,
+    /// `rerun`, `close` or `keep`.
+    pub(crate) on_exit: String,
+    /// Exited panes waiting out the crash-loop backoff.
+    pub(crate) deferred_restarts: Vec<u32>,
+    /// `single`, `per_client` or `per_tab`.
+    pub(crate) pane_policy: String,
+    pub(crate) client_panes: Vec<ButlerClientPane>,
+    pub(crate) tab_panes: Vec<ButlerTabPane>,
+    /// Last floating geometry seen for the tracked Jelly pane.
+    pub(crate) jelly_geometry: Option<PaneGeometry>,
+    /// Where toggle state is persisted, when enabled.
+    pub(crate) state_path: Option<String>,
+    /// Whether the tracked pane id was restored from a previous plugin instance.
+    pub(crate) restored_jelly_pane: bool,
+    pub(crate) lifetime: LifetimeCounters,
+    pub(crate) last_cli_toggle_pipe_id: Option<String>,
+    pub(crate) launch_command: String,
+    /// `focused`, `home` or `fixed:<path>`.
+    pub(crate) launch_cwd: String,
+    /// Names of the extra `launch_env` variables; values are not echoed.
+    pub(crate) launch_env: Vec<String>,
+    /// `off`, `suggest` or `apply`.
+    pub(crate) auto_name_tabs: String,
+    /// Configured scratch panes and the pane each is using.
+    pub(crate) scratch_panes: Vec<ButlerScratchPane>,
+}
+
+/// Full protocol description served by `get_schema` and committed as
+/// `plugin/schema/butler-protocol.json` (see `tests/protocol_schema.rs`).
+pub fn protocol_schema() -> Value {
+    json!({
+        "protocol_version": PROTOCOL_VERSION,
+        "request": schema_for!(ButlerRequest),
+        "error": schema_for!(ErrorResponse),
+        "results": {
+            "hello": schema_for!(HelloResult),
+            "get_schema": { "type": "object" },
+            "ping": schema_for!(AckResult),
+            "get_state": schema_for!(ButlerWorkspaceState),
+            "get_metrics": schema_for!(MetricsResult),
+            "get_trace": schema_for!(TraceResult),
+            "clear_trace": schema_for!(AckResult),
+            "export_trace": schema_for!(ExportTraceResult),
+            "rename_tab": schema_for!(AckResult),
+            "rename_pane": schema_for!(AckResult),
+            "hide_pane": schema_for!(AckResult),
+            "show_pane": schema_for!(AckResult),
+            "batch": schema_for!(BatchResult),
+            "start_recording": schema_for!(AckResult),
+            "stop_recording": schema_for!(RecordingResult),
+            "get_recording": schema_for!(RecordingResult),
+            "register_ui_pane": schema_for!(AckResult),
+            "register_ui": schema_for!(AckResult),
+            "ui_heartbeat": schema_for!(AckResult),
+            "get_pane_info": schema_for!(ButlerPaneInfo),
+            "get_tab_name_suggestions": schema_for!(TabNameSuggestionsResult),
+            "list_rules": schema_for!(ListRulesResult),
+            "add_rule": schema_for!(AckResult),
+            "remove_rule": schema_for!(AckResult),
+            "get_activity": schema_for!(ActivityResult),
+            "focus_back": schema_for!(FocusBackResult),
+            "get_focus_history": schema_for!(FocusHistoryResult),
+            "tag_pane": schema_for!(AckResult),
+            "untag_pane": schema_for!(AckResult),
+            "list_tags": schema_for!(ListTagsResult),
+            "hide_panes": schema_for!(PanesResult),
+            "show_panes": schema_for!(PanesResult),
+            "float_panes": schema_for!(PanesResult),
+            "close_panes": schema_for!(PanesResult),
+            "toggle_scratch": schema_for!(ScratchResult),
+            "open_file": schema_for!(OpenFileResult),
+            "get_fs_changes": schema_for!(FsChangesResult),
+        },
+    })
+}
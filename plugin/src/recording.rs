@@ -0,0 +1,541 @@
+//! Raw inbound event recording for deterministic replay.
+//!
+//! When enabled, the butler records every event it reacts to (load, PaneUpdate,
+//! TabUpdate, permission results, timers, cwd changes, filesystem changes, pipe
+//! messages) plus the outcome of pane launches, with host timestamps. [`crate::replay`]
+//! feeds such a recording back into a [`crate::State`] on a
+//! [`crate::fake_host::FakeHost`].
+//!
+//! Panes and tabs are stored in a compact form of our own rather than serializing
+//! `PaneInfo`/`TabInfo` directly, so recordings stay readable across zellij-tile bumps.
+
+use crate::host::ZellijHost;
+use crate::launch::{redacted_configuration, REDACTED};
+use crate::protocol::{RecordingResult, TraceCategory};
+use crate::state::State;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, VecDeque};
+use std::path::PathBuf;
+use zellij_tile::prelude::*;
+
+pub const DEFAULT_RECORDING_LIMIT: usize = 500;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedPane {
+    pub id: u32,
+    pub tab_index: usize,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub terminal_command: Option<String>,
+    #[serde(default)]
+    pub is_plugin: bool,
+    #[serde(default)]
+    pub is_focused: bool,
+    #[serde(default)]
+    pub is_floating: bool,
+    #[serde(default)]
+    pub is_suppressed: bool,
+    #[serde(default)]
+    pub exited: bool,
+    #[serde(default)]
+    pub exit_status: Option<i32>,
+    #[serde(default)]
+    pub is_selectable: bool,
+    #[serde(default)]
+    pub is_held: bool,
+    #[serde(default)]
+    pub is_fullscreen: bool,
+    #[serde(default)]
+    pub plugin_url: Option<String>,
+    #[serde(default)]
+    pub pane_x: usize,
+    #[serde(default)]
+    pub pane_y: usize,
+    #[serde(default)]
+    pub pane_columns: usize,
+    #[serde(default)]
+    pub pane_rows: usize,
+    #[serde(default)]
+    pub pane_content_x: usize,
+    #[serde(default)]
+    pub pane_content_y: usize,
+    #[serde(default)]
+    pub pane_content_columns: usize,
+    #[serde(default)]
+    pub pane_content_rows: usize,
+    /// `(x, y)` within the pane.
+    #[serde(default)]
+    pub cursor_coordinates_in_pane: Option<(usize, usize)>,
+}
+
+impl RecordedPane {
+    fn from_pane(tab_index: usize, pane: &PaneInfo) -> Self {
+        RecordedPane {
+            id: pane.id,
+            tab_index,
+            title: pane.title.clone(),
+            terminal_command: pane.terminal_command.clone(),
+            is_plugin: pane.is_plugin,
+            is_focused: pane.is_focused,
+            is_floating: pane.is_floating,
+            is_suppressed: pane.is_suppressed,
+            exited: pane.exited,
+            exit_status: pane.exit_status,
+            is_selectable: pane.is_selectable,
+            is_held: pane.is_held,
+            is_fullscreen: pane.is_fullscreen,
+            plugin_url: pane.plugin_url.clone(),
+            pane_x: pane.pane_x,
+            pane_y: pane.pane_y,
+            pane_columns: pane.pane_columns,
+            pane_rows: pane.pane_rows,
+            pane_content_x: pane.pane_content_x,
+            pane_content_y: pane.pane_content_y,
+            pane_content_columns: pane.pane_content_columns,
+            pane_content_rows: pane.pane_content_rows,
+            cursor_coordinates_in_pane: pane.cursor_coordinates_in_pane,
+        }
+    }
+
+    fn to_pane(&self) -> PaneInfo {
+        PaneInfo {
+            id: self.id,
+            title: self.title.clone(),
+            terminal_command: self.terminal_command.clone(),
+            is_plugin: self.is_plugin,
+            is_focused: self.is_focused,
+            is_floating: self.is_floating,
+            is_suppressed: self.is_suppressed,
+            exited: self.exited,
+            exit_status: self.exit_status,
+            is_selectable: self.is_selectable,
+            is_held: self.is_held,
+            is_fullscreen: self.is_fullscreen,
+            plugin_url: self.plugin_url.clone(),
+            pane_x: self.pane_x,
+            pane_y: self.pane_y,
+            pane_columns: self.pane_columns,
+            pane_rows: self.pane_rows,
+            pane_content_x: self.pane_content_x,
+            pane_content_y: self.pane_content_y,
+            pane_content_columns: self.pane_content_columns,
+            pane_content_rows: self.pane_content_rows,
+            cursor_coordinates_in_pane: self.cursor_coordinates_in_pane,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedTab {
+    pub position: usize,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub active: bool,
+    #[serde(default)]
+    pub selectable_tiled_panes_count: usize,
+    #[serde(default)]
+    pub selectable_floating_panes_count: usize,
+    /// Clients other than the current one with this tab focused.
+    #[serde(default)]
+    pub other_focused_clients: Vec<u16>,
+    #[serde(default)]
+    pub are_floating_panes_visible: bool,
+}
+
+impl RecordedTab {
+    fn from_tab(tab: &TabInfo) -> Self {
+        RecordedTab {
+            position: tab.position,
+            name: tab.name.clone(),
+            active: tab.active,
+            selectable_tiled_panes_count: tab.selectable_tiled_panes_count,
+            selectable_floating_panes_count: tab.selectable_floating_panes_count,
+            other_focused_clients: tab.other_focused_clients.clone(),
+            are_floating_panes_visible: tab.are_floating_panes_visible,
+        }
+    }
+
+    fn to_tab(&self) -> TabInfo {
+        TabInfo {
+            position: self.position,
+            name: self.name.clone(),
+            active: self.active,
+            selectable_tiled_panes_count: self.selectable_tiled_panes_count,
+            selectable_floating_panes_count: self.selectable_floating_panes_count,
+            other_focused_clients: self.other_focused_clients.clone(),
+            are_floating_panes_visible: self.are_floating_panes_visible,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedClient {
+    pub client_id: u16,
+    /// The client's focused pane.
+    pub pane_id: u32,
+    #[serde(default)]
+    pub is_plugin: bool,
+    #[serde(default)]
+    pub is_current_client: bool,
+    /// What the client's focused pane is running, as Zellij reports it.
+    #[serde(default)]
+    pub running_command: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RecordedPipeSource {
+    Cli { pipe_id: String },
+    Plugin { plugin_id: u32 },
+    Keybind,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RecordedEvent {
+    Load {
+        configuration: BTreeMap<String, String>,
+    },
+    PermissionResult {
+        granted: bool,
+    },
+    PaneUpdate {
+        panes: Vec<RecordedPane>,
+    },
+    TabUpdate {
+        tabs: Vec<RecordedTab>,
+    },
+    ListClients {
+        clients: Vec<RecordedClient>,
+    },
+    Timer {
+        elapsed_secs: f64,
+    },
+    CwdChanged {
+        pane_id: u32,
+        cwd: String,
+    },
+    /// `FileSystemCreate`/`Update`/`Delete`; `change` is `create`, `update` or `delete`.
+    /// Only the paths are kept: the butler never reads the file metadata, so it replays
+    /// as `None`.
+    FileSystem {
+        change: String,
+        paths: Vec<String>,
+    },
+    Pipe {
+        name: String,
+        source: RecordedPipeSource,
+        #[serde(default)]
+        payload: Option<String>,
+        #[serde(default)]
+        args: BTreeMap<String, String>,
+    },
+    /// Result of an `open_command_pane_floating` host call. Replays script the fake host's
+    /// launches with these so pane ids line up with the recorded PaneUpdates.
+    LaunchResult {
+        #[serde(default)]
+        pane_id: Option<u32>,
+        #[serde(default)]
+        error: Option<String>,
+    },
+}
+
+impl RecordedEvent {
+    pub(crate) fn from_event(event: &Event) -> Option<Self> {
+        match event {
+            Event::PermissionRequestResult(status) => Some(RecordedEvent::PermissionResult {
+                granted: matches!(status, PermissionStatus::Granted),
+            }),
+            Event::PaneUpdate(manifest) => Some(Self::pane_update(manifest)),
+            Event::TabUpdate(tabs) => Some(Self::tab_update(tabs)),
+            Event::ListClients(clients) => Some(RecordedEvent::ListClients {
+                clients: clients
+                    .iter()
+                    .map(|client| {
+                        let (pane_id, is_plugin) = match client.pane_id {
+                            PaneId::Terminal(pane_id) => (pane_id, false),
+                            PaneId::Plugin(pane_id) => (pane_id, true),
+                        };
+                        RecordedClient {
+                            client_id: client.client_id,
+                            pane_id,
+                            is_plugin,
+                            is_current_client: client.is_current_client,
+                            running_command: client.running_command.clone(),
+                        }
+                    })
+                    .collect(),
+            }),
+            Event::Timer(elapsed_secs) => Some(RecordedEvent::Timer {
+                elapsed_secs: *elapsed_secs,
+            }),
+            Event::CwdChanged(PaneId::Terminal(pane_id), cwd, _) => {
+                Some(RecordedEvent::CwdChanged {
+                    pane_id: *pane_id,
+                    cwd: cwd.to_string_lossy().into_owned(),
+                })
+            }
+            Event::FileSystemCreate(paths) => Some(Self::file_system("create", paths)),
+            Event::FileSystemUpdate(paths) => Some(Self::file_system("update", paths)),
+            Event::FileSystemDelete(paths) => Some(Self::file_system("delete", paths)),
+            _ => None,
+        }
+    }
+
+    fn file_system(change: &str, paths: &[(PathBuf, Option<FileMetadata>)]) -> Self {
+        RecordedEvent::FileSystem {
+            change: change.to_owned(),
+            paths: paths
+                .iter()
+                .map(|(path, _)| path.to_string_lossy().into_owned())
+                .collect(),
+        }
+    }
+
+    pub(crate) fn pane_update(manifest: &PaneManifest) -> Self {
+        let mut panes = manifest
+            .panes
+            .iter()
+            .flat_map(|(tab_index, panes)| {
+                panes
+                    .iter()
+                    .map(|pane| RecordedPane::from_pane(*tab_index, pane))
+            })
+            .collect::<Vec<_>>();
+        // Manifest tabs come from a HashMap; sort so recordings diff cleanly.
+        panes.sort_by_key(|pane| (pane.tab_index, pane.is_plugin, pane.id));
+        RecordedEvent::PaneUpdate { panes }
+    }
+
+    pub(crate) fn tab_update(tabs: &[TabInfo]) -> Self {
+        RecordedEvent::TabUpdate {
+            tabs: tabs.iter().map(RecordedTab::from_tab).collect(),
+        }
+    }
+
+    pub(crate) fn pipe(pipe_message: &PipeMessage) -> Self {
+        let source = match &pipe_message.source {
+            PipeSource::Cli(pipe_id) => RecordedPipeSource::Cli {
+                pipe_id: pipe_id.clone(),
+            },
+            PipeSource::Plugin(plugin_id) => RecordedPipeSource::Plugin {
+                plugin_id: *plugin_id,
+            },
+            PipeSource::Keybind => RecordedPipeSource::Keybind,
+        };
+        RecordedEvent::Pipe {
+            name: pipe_message.name.clone(),
+            source,
+            payload: pipe_message.payload.as_deref().map(redact_launch_token),
+            args: pipe_message.args.clone(),
+        }
+    }
+
+    /// The plugin event this entry was recorded from, if it was one.
+    pub fn to_event(&self) -> Option<Event> {
+        match self {
+            RecordedEvent::PermissionResult { granted } => {
+                Some(Event::PermissionRequestResult(if *granted {
+                    PermissionStatus::Granted
+                } else {
+                    PermissionStatus::Denied
+                }))
+            }
+            RecordedEvent::PaneUpdate { panes } => {
+                let mut manifest = PaneManifest::default();
+                for pane in panes {
+                    manifest
+                        .panes
+                        .entry(pane.tab_index)
+                        .or_default()
+                        .push(pane.to_pane());
+                }
+                Some(Event::PaneUpdate(manifest))
+            }
+            RecordedEvent::TabUpdate { tabs } => Some(Event::TabUpdate(
+                tabs.iter().map(RecordedTab::to_tab).collect(),
+            )),
+            RecordedEvent::ListClients { clients } => Some(Event::ListClients(
+                clients
+                    .iter()
+                    .map(|client| ClientInfo {
+                        client_id: client.client_id,
+                        pane_id: if client.is_plugin {
+                            PaneId::Plugin(client.pane_id)
+                        } else {
+                            PaneId::Terminal(client.pane_id)
+                        },
+                        running_command: client.running_command.clone(),
+                        is_current_client: client.is_current_client,
+                    })
+                    .collect(),
+            )),
+            RecordedEvent::Timer { elapsed_secs } => Some(Event::Timer(*elapsed_secs)),
+            RecordedEvent::CwdChanged { pane_id, cwd } => Some(Event::CwdChanged(
+                PaneId::Terminal(*pane_id),
+                PathBuf::from(cwd),
+                Vec::new(),
+            )),
+            RecordedEvent::FileSystem { change, paths } => {
+                let paths = paths
+                    .iter()
+                    .map(|path| (PathBuf::from(path), None))
+                    .collect();
+                match change.as_str() {
+                    "create" => Some(Event::FileSystemCreate(paths)),
+                    "update" => Some(Event::FileSystemUpdate(paths)),
+                    "delete" => Some(Event::FileSystemDelete(paths)),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    pub fn to_pipe_message(&self) -> Option<PipeMessage> {
+        let RecordedEvent::Pipe {
+            name,
+            source,
+            payload,
+            args,
+        } = self
+        else {
+            return None;
+        };
+        let source = match source {
+            RecordedPipeSource::Cli { pipe_id } => PipeSource::Cli(pipe_id.clone()),
+            RecordedPipeSource::Plugin { plugin_id } => PipeSource::Plugin(*plugin_id),
+            RecordedPipeSource::Keybind => PipeSource::Keybind,
+        };
+        Some(PipeMessage {
+            source,
+            name: name.clone(),
+            payload: payload.clone(),
+            args: args.clone(),
+            is_private: false,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedEntry {
+    pub seq: u64,
+    pub epoch_ms: u64,
+    pub event: RecordedEvent,
+}
+
+/// Bounded buffer of recorded events; the oldest entries are dropped first.
+#[derive(Debug)]
+pub(crate) struct EventRecorder {
+    entries: VecDeque<RecordedEntry>,
+    limit: usize,
+    seq: u64,
+    dropped: u64,
+}
+
+impl EventRecorder {
+    pub(crate) fn new(limit: usize) -> Self {
+        EventRecorder {
+            entries: VecDeque::new(),
+            limit: limit.max(1),
+            seq: 0,
+            dropped: 0,
+        }
+    }
+
+    pub(crate) fn record(&mut self, epoch_ms: u128, event: RecordedEvent) {
+        self.seq = self.seq.saturating_add(1);
+        if self.entries.len() >= self.limit {
+            self.entries.pop_front();
+            self.dropped = self.dropped.saturating_add(1);
+        }
+        self.entries.push_back(RecordedEntry {
+            seq: self.seq,
+            epoch_ms: u64::try_from(epoch_ms).unwrap_or(u64::MAX),
+            event,
+        });
+    }
+
+    pub(crate) fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    pub(crate) fn entries_since(&self, since_seq: Option<u64>) -> Vec<Value> {
+        self.entries
+            .iter()
+            .filter(|entry| since_seq.is_none_or(|since| entry.seq > since))
+            .filter_map(|entry| serde_json::to_value(entry).ok())
+            .collect()
+    }
+}
+
+/// Parses a recording as returned by `get_recording`: the full response envelope,
+/// its `result` object, or a bare array of entries.
+pub fn parse_recording(json: &str) -> Result<Vec<RecordedEntry>, serde_json::Error> {
+    let value = serde_json::from_str::<Value>(json)?;
+    let entries = value
+        .get("result")
+        .and_then(|result| result.get("entries"))
+        .or_else(|| value.get("entries"))
+        .cloned()
+        .unwrap_or(value);
+    serde_json::from_value(entries)
+}
+
+/// A `register_ui_pane` request with its launch token replaced; other payloads as is.
+fn redact_launch_token(payload: &str) -> String {
+    let Ok(mut request) = serde_json::from_str::<Value>(payload) else {
+        return payload.to_owned();
+    };
+    if request["op"] != "register_ui_pane" || request.get("token").is_none() {
+        return payload.to_owned();
+    }
+    request["token"] = Value::from(REDACTED);
+    request.to_string()
+}
+
+impl<H: ZellijHost> State<H> {
+    /// Appends to the event recording when one is active. The event is built lazily
+    /// so unrecorded sessions don't pay for cloning manifests.
+    pub(crate) fn record(&mut self, event: impl FnOnce() -> RecordedEvent) {
+        let now_ms = self.host.now_epoch_millis();
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.record(now_ms, event());
+        }
+    }
+
+    /// Starts a fresh recording seeded with the current caches, so a replay begins
+    /// from the same workspace the butler was looking at.
+    pub(crate) fn start_recording(&mut self, limit: Option<usize>) {
+        self.recorder = Some(EventRecorder::new(limit.unwrap_or(DEFAULT_RECORDING_LIMIT)));
+        let configuration = redacted_configuration(&self.configuration);
+        self.record(|| RecordedEvent::Load { configuration });
+        if self.permission_result_seen {
+            let granted = !self.permission_denied;
+            self.record(|| RecordedEvent::PermissionResult { granted });
+        }
+        if let Some(tabs) = self.tabs.clone() {
+            self.record(|| RecordedEvent::tab_update(&tabs));
+        }
+        if let Some(manifest) = self.panes.clone() {
+            self.record(|| RecordedEvent::pane_update(&manifest));
+        }
+        self.push_trace(TraceCategory::Request, "recording started");
+    }
+
+    pub(crate) fn recording_response(&self, since_seq: Option<u64>) -> Value {
+        Self::serialized_response(&RecordingResult {
+            recording: self.recorder.is_some(),
+            dropped: self.recorder.as_ref().map_or(0, EventRecorder::dropped),
+            entries: self
+                .recorder
+                .as_ref()
+                .map(|recorder| recorder.entries_since(since_seq))
+                .unwrap_or_default(),
+        })
+    }
+}
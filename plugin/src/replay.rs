@@ -0,0 +1,40 @@
+//! Replays a recorded event trace against [`FakeHost`].
+//!
+//! The fake host's clock is set to each entry's timestamp before it is delivered, and
+//! its launches are scripted from the recorded `launch_result` entries, so the state
+//! machine sees the same inputs it saw in the field. Assert on `state.host().calls`.
+
+use crate::fake_host::FakeHost;
+use crate::recording::{RecordedEntry, RecordedEvent};
+use crate::State;
+
+pub fn replay(entries: &[RecordedEntry]) -> State<FakeHost> {
+    let mut host = FakeHost::default();
+    host.scripted_launches = entries
+        .iter()
+        .filter_map(|entry| match &entry.event {
+            RecordedEvent::LaunchResult { pane_id, error } => Some(match pane_id {
+                Some(pane_id) => Ok(*pane_id),
+                None => Err(error.clone().unwrap_or_default()),
+            }),
+            _ => None,
+        })
+        .collect();
+
+    let mut state = State::new(host);
+    for entry in entries {
+        state.host_mut().now_ms = u128::from(entry.epoch_ms);
+        match &entry.event {
+            RecordedEvent::Load { configuration } => state.load(configuration.clone()),
+            RecordedEvent::LaunchResult { .. } => {}
+            event => {
+                if let Some(plugin_event) = event.to_event() {
+                    state.update(plugin_event);
+                } else if let Some(pipe_message) = event.to_pipe_message() {
+                    state.pipe(pipe_message);
+                }
+            }
+        }
+    }
+    state
+}
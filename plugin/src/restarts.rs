@@ -0,0 +1,144 @@
+//! `on_exit` handling for owned Jelly panes whose command exited, with crash-loop
+//! backoff: restarts beyond a small allowance wait for a timer, doubling each time.
+
+use crate::host::ZellijHost;
+use crate::protocol::{TraceCategory, TraceLevel};
+use crate::state::{ExitPolicy, PanePolicy, State};
+
+/// Automatic restarts are counted over this window for crash-loop backoff.
+const CRASH_LOOP_WINDOW_MS: u128 = 5 * 60_000;
+/// Restarts within the window that happen immediately; later ones back off.
+pub const CRASH_LOOP_FREE_RESTARTS: usize = 3;
+const CRASH_LOOP_MAX_BACKOFF_MS: u128 = 60_000;
+
+impl<H: ZellijHost> State<H> {
+    /// Applies `on_exit` to owned Jelly panes whose command exited since the last
+    /// PaneUpdate. Restarts beyond the crash-loop allowance wait for a timer.
+    pub(crate) fn handle_exited_jelly_panes(&mut self) {
+        let exited = self
+            .owned_jelly_pane_ids()
+            .into_iter()
+            .filter_map(|pane_id| self.exited_terminal_pane(pane_id))
+            .collect::<Vec<_>>();
+        self.handled_exits
+            .retain(|pane_id| exited.iter().any(|pane| pane.id == *pane_id));
+        for pane in exited {
+            if !self.handled_exits.insert(pane.id) {
+                continue;
+            }
+            self.metrics.pane_exits_mut().detected += 1;
+            let on_exit = self.exit_policy.as_str();
+            let entry = self
+                .push_trace_at(TraceLevel::Warn, TraceCategory::Toggle, "jelly_pane_exited")
+                .field("id", pane.id)
+                .field("on_exit", on_exit);
+            if let Some(exit_status) = pane.exit_status {
+                entry.field("exit_status", exit_status);
+            }
+            if self.exit_policy == ExitPolicy::Keep {
+                self.forget_jelly_pane(pane.id);
+                continue;
+            }
+            let backoff_ms = self.restart_backoff_ms();
+            if backoff_ms == 0 {
+                self.restart_exited_pane(pane.id);
+            } else {
+                let recent_restarts = self.exit_restarts.len();
+                self.push_trace_at(
+                    TraceLevel::Warn,
+                    TraceCategory::Toggle,
+                    "jelly_restart_backed_off",
+                )
+                .field("id", pane.id)
+                .field("backoff_ms", backoff_ms)
+                .field("recent_restarts", recent_restarts);
+                self.metrics.pane_exits_mut().backoffs += 1;
+                let due_ms = self.host.now_epoch_millis() + backoff_ms;
+                self.deferred_restarts.insert(pane.id, due_ms);
+                self.host.set_timeout(backoff_ms as f64 / 1000.0);
+            }
+        }
+    }
+
+    /// Reruns the deferred restarts that are due, then re-arms the timer for the next
+    /// one. Every backoff sets its own timer, so a timer may find nothing due.
+    pub(crate) fn run_due_restarts(&mut self) {
+        let now_ms = self.host.now_epoch_millis();
+        let due = self
+            .deferred_restarts
+            .iter()
+            .filter(|(_, due_ms)| **due_ms <= now_ms)
+            .map(|(pane_id, _)| *pane_id)
+            .collect::<Vec<_>>();
+        for pane_id in due {
+            self.deferred_restarts.remove(&pane_id);
+            self.restart_exited_pane(pane_id);
+        }
+        if let Some(next_ms) = self.deferred_restarts.values().min() {
+            self.host
+                .set_timeout(next_ms.saturating_sub(now_ms) as f64 / 1000.0);
+        }
+    }
+
+    /// No delay for the first [`CRASH_LOOP_FREE_RESTARTS`] restarts in the window, then
+    /// 1s, 2s, 4s, ... up to a minute.
+    fn restart_backoff_ms(&mut self) -> u128 {
+        let now_ms = self.host.now_epoch_millis();
+        while self
+            .exit_restarts
+            .front()
+            .is_some_and(|restart_ms| now_ms.saturating_sub(*restart_ms) > CRASH_LOOP_WINDOW_MS)
+        {
+            self.exit_restarts.pop_front();
+        }
+        let excess = self
+            .exit_restarts
+            .len()
+            .saturating_sub(CRASH_LOOP_FREE_RESTARTS);
+        if self.exit_restarts.len() < CRASH_LOOP_FREE_RESTARTS {
+            0
+        } else {
+            (1_000u128 << excess.min(16)).min(CRASH_LOOP_MAX_BACKOFF_MS)
+        }
+    }
+
+    /// Skips panes that were closed, started running again, or were released while
+    /// the restart waited.
+    pub(crate) fn restart_exited_pane(&mut self, pane_id: u32) {
+        let Some(pane) = self.exited_terminal_pane(pane_id) else {
+            return;
+        };
+        if !self.owned_jelly_pane_ids().contains(&pane_id) {
+            return;
+        }
+        self.exit_restarts.push_back(self.host.now_epoch_millis());
+        match self.exit_policy {
+            ExitPolicy::Rerun => {
+                self.push_trace(TraceCategory::Toggle, "rerunning_exited_jelly_pane")
+                    .field("id", pane_id);
+                self.metrics.pane_exits_mut().reruns += 1;
+                self.host.rerun_command_pane(pane_id);
+            }
+            ExitPolicy::Close => {
+                // Only the single shared pane has an obvious place to come back to;
+                // per-client and per-tab panes relaunch on their next toggle.
+                let relaunch = !pane.is_suppressed
+                    && self.pane_policy == PanePolicy::Single
+                    && self.jelly_pane_id == Some(pane_id);
+                self.push_trace(TraceCategory::Toggle, "closing_exited_jelly_pane")
+                    .field("id", pane_id)
+                    .field("relaunch", relaunch);
+                self.metrics.pane_exits_mut().closed += 1;
+                self.host.close_terminal_pane(pane_id);
+                self.forget_jelly_pane(pane_id);
+                if relaunch {
+                    let origin_pane = self
+                        .active_tab_index()
+                        .and_then(|tab| self.origin_pane(None, tab));
+                    self.launch_new_jelly_terminal(origin_pane);
+                }
+            }
+            ExitPolicy::Keep => {}
+        }
+    }
+}
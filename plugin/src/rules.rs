@@ -0,0 +1,670 @@
+//! Declarative workspace rules: "when X happens, run these request ops".
+//!
+//! Rules come from the `rules` plugin config (a JSON array, read-only) and from a JSON
+//! file in `/data` that `add_rule`/`remove_rule` edit. The butler diffs each
+//! `PaneUpdate`/`TabUpdate` against the previous one, matches the changes against
+//! every rule's trigger and runs the rule's actions through the normal request path,
+//! so an action can do exactly what a `request` pipe message can.
+
+use crate::host::ZellijHost;
+use crate::protocol::{
+    ButlerRequest, ListRulesResult, ListedRule, RuleTrigger, TraceCategory, TraceLevel,
+    WorkspaceRule,
+};
+use crate::state::State;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use zellij_tile::prelude::{PaneInfo, PaneManifest, TabInfo};
+
+pub(crate) const DEFAULT_RULES_PATH: &str = "/data/butler-rules.json";
+/// Minimum time between two firings of a rule without its own `cooldown_ms`.
+pub(crate) const DEFAULT_RULE_COOLDOWN_MS: u64 = 1_000;
+/// Rule-opened panes remembered so rules don't fire for them; the oldest go first.
+const RULE_SPAWNED_PANE_LIMIT: usize = 256;
+
+/// Ops a rule may not run: rule management (rules editing rules), batches, and ops that
+/// destroy panes or write files nobody asked for at the moment they happen.
+const FORBIDDEN_ACTION_OPS: &[&str] = &[
+    "add_rule",
+    "remove_rule",
+    "list_rules",
+    "batch",
+    "close_panes",
+    "export_trace",
+    "clear_trace",
+];
+
+/// A state change that fired a rule, with what its actions' placeholders expand to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct RuleMatch {
+    pub(crate) rule_id: String,
+    pub(crate) pane_id: Option<u32>,
+    pub(crate) tab_position: Option<usize>,
+}
+
+/// Reads and writes the editable rules file.
+#[derive(Debug)]
+pub(crate) struct RuleStore {
+    path: String,
+}
+
+impl RuleStore {
+    /// `rules_path` overrides the file location.
+    pub(crate) fn from_configuration(configuration: &BTreeMap<String, String>) -> Self {
+        let path = configuration
+            .get("rules_path")
+            .map(|value| value.trim())
+            .filter(|value| !value.is_empty())
+            .unwrap_or(DEFAULT_RULES_PATH);
+        RuleStore {
+            path: path.to_owned(),
+        }
+    }
+
+    pub(crate) fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// An empty list when the file doesn't exist yet.
+    pub(crate) fn read<H: ZellijHost>(&self, host: &H) -> Result<Vec<WorkspaceRule>, String> {
+        if host.file_len(&self.path).is_none() {
+            return Ok(Vec::new());
+        }
+        parse_rules(&host.read_file(&self.path)?)
+    }
+
+    pub(crate) fn write<H: ZellijHost>(
+        &self,
+        host: &mut H,
+        rules: &[WorkspaceRule],
+    ) -> Result<(), String> {
+        let contents = serde_json::to_string_pretty(rules).map_err(|error| error.to_string())?;
+        host.write_file(&self.path, &contents)
+    }
+}
+
+pub(crate) fn parse_rules(json: &str) -> Result<Vec<WorkspaceRule>, String> {
+    let rules =
+        serde_json::from_str::<Vec<WorkspaceRule>>(json).map_err(|error| error.to_string())?;
+    for rule in &rules {
+        validate_rule(rule)?;
+    }
+    Ok(rules)
+}
+
+/// Checks that every action is a request the butler would accept once the trigger
+/// fills in its placeholders.
+pub(crate) fn validate_rule(rule: &WorkspaceRule) -> Result<(), String> {
+    if rule.id.trim().is_empty() {
+        return Err("rule id must not be empty".to_owned());
+    }
+    if rule.actions.is_empty() && rule.trace_message.is_none() {
+        return Err(format!(
+            "rule {:?} has no actions and no trace_message",
+            rule.id
+        ));
+    }
+    for (index, action) in rule.actions.iter().enumerate() {
+        let op = action.get("op").and_then(Value::as_str).unwrap_or("");
+        if FORBIDDEN_ACTION_OPS.contains(&op) {
+            return Err(format!(
+                "rule {:?} action {} uses {:?}, which rules may not run",
+                rule.id, index, op
+            ));
+        }
+        let pane_id = match rule.when {
+            RuleTrigger::TabAppeared { .. } => {
+                if uses_placeholder(action, "$pane_id") {
+                    return Err(format!(
+                        "rule {:?} action {} uses $pane_id, but tab_appeared has no pane",
+                        rule.id, index
+                    ));
+                }
+                None
+            }
+            RuleTrigger::PaneExited { .. } | RuleTrigger::PaneOpened { .. } => Some(0),
+        };
+        let sample = expand_action(action, pane_id, Some(0));
+        serde_json::from_value::<ButlerRequest>(sample).map_err(|error| {
+            format!(
+                "rule {:?} action {} is not a valid request: {}",
+                rule.id, index, error
+            )
+        })?;
+    }
+    Ok(())
+}
+
+/// Replaces the placeholders `$pane_id` and `$tab_position` in every string value. A
+/// string that is only a placeholder becomes a number (`null` when the trigger has no
+/// such value); inside longer strings an unknown value is left as is.
+pub(crate) fn expand_action(
+    action: &Value,
+    pane_id: Option<u32>,
+    tab_position: Option<usize>,
+) -> Value {
+    match action {
+        Value::String(text) if text == "$pane_id" => pane_id.map_or(Value::Null, Value::from),
+        Value::String(text) if text == "$tab_position" => {
+            tab_position.map_or(Value::Null, Value::from)
+        }
+        Value::String(text) => {
+            let mut text = text.clone();
+            if let Some(pane_id) = pane_id {
+                text = text.replace("$pane_id", &pane_id.to_string());
+            }
+            if let Some(tab_position) = tab_position {
+                text = text.replace("$tab_position", &tab_position.to_string());
+            }
+            Value::String(text)
+        }
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| expand_action(item, pane_id, tab_position))
+                .collect(),
+        ),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(key, value)| (key.clone(), expand_action(value, pane_id, tab_position)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+fn uses_placeholder(action: &Value, placeholder: &str) -> bool {
+    match action {
+        Value::String(text) => text.contains(placeholder),
+        Value::Array(items) => items.iter().any(|item| uses_placeholder(item, placeholder)),
+        Value::Object(fields) => fields
+            .values()
+            .any(|value| uses_placeholder(value, placeholder)),
+        _ => false,
+    }
+}
+
+/// Triggers fired by going from `before` to `after`. Nothing fires without a
+/// `before`: the first snapshot after load is a baseline, not a change.
+pub(crate) fn match_pane_changes(
+    rules: &[&WorkspaceRule],
+    before: Option<&PaneManifest>,
+    after: &PaneManifest,
+) -> Vec<RuleMatch> {
+    let Some(before) = before else {
+        return Vec::new();
+    };
+    let previous = terminal_panes(before);
+    let mut matches = Vec::new();
+    for (tab_position, pane) in terminal_panes(after).into_values() {
+        let was = previous.get(&pane.id).map(|(_, pane)| pane);
+        for rule in rules {
+            let fired = match &rule.when {
+                RuleTrigger::PaneExited {
+                    command,
+                    nonzero_only,
+                } => {
+                    pane.exited
+                        && was.is_some_and(|was| !was.exited)
+                        && command_matches(pane, command.as_deref())
+                        && (!nonzero_only || pane.exit_status.is_some_and(|status| status != 0))
+                }
+                RuleTrigger::PaneOpened { command, title } => {
+                    was.is_none()
+                        && command_matches(pane, command.as_deref())
+                        && title
+                            .as_deref()
+                            .is_none_or(|title| pane.title.contains(title))
+                }
+                RuleTrigger::TabAppeared { .. } => false,
+            };
+            if fired {
+                matches.push(RuleMatch {
+                    rule_id: rule.id.clone(),
+                    pane_id: Some(pane.id),
+                    tab_position: Some(tab_position),
+                });
+            }
+        }
+    }
+    matches
+}
+
+/// `tab_appeared` fires for a tab whose name was not among the previous tabs',
+/// which covers both new tabs and renames.
+pub(crate) fn match_tab_changes(
+    rules: &[&WorkspaceRule],
+    before: Option<&[TabInfo]>,
+    after: &[TabInfo],
+) -> Vec<RuleMatch> {
+    let Some(before) = before else {
+        return Vec::new();
+    };
+    let previous = before
+        .iter()
+        .map(|tab| tab.name.as_str())
+        .collect::<BTreeSet<_>>();
+    let mut matches = Vec::new();
+    for tab in after {
+        if previous.contains(tab.name.as_str()) {
+            continue;
+        }
+        for rule in rules {
+            if let RuleTrigger::TabAppeared { name } = &rule.when {
+                if *name == tab.name {
+                    matches.push(RuleMatch {
+                        rule_id: rule.id.clone(),
+                        pane_id: None,
+                        tab_position: Some(tab.position),
+                    });
+                }
+            }
+        }
+    }
+    matches
+}
+
+fn terminal_panes(manifest: &PaneManifest) -> BTreeMap<u32, (usize, &PaneInfo)> {
+    manifest
+        .panes
+        .iter()
+        .flat_map(|(tab_position, panes)| {
+            panes
+                .iter()
+                .filter(|pane| !pane.is_plugin)
+                .map(|pane| (pane.id, (*tab_position, pane)))
+        })
+        .collect()
+}
+
+fn command_matches(pane: &PaneInfo, command: Option<&str>) -> bool {
+    command.is_none_or(|command| {
+        pane.terminal_command
+            .as_deref()
+            .is_some_and(|running| running.contains(command))
+    })
+}
+
+impl<H: ZellijHost> State<H> {
+    pub(crate) fn load_rules(&mut self, configuration: &BTreeMap<String, String>) {
+        if let Some(value) = configuration.get("rules") {
+            match parse_rules(value) {
+                Ok(config_rules) => self.config_rules = config_rules,
+                Err(error) => {
+                    self.push_trace_at(
+                        TraceLevel::Warn,
+                        TraceCategory::Rule,
+                        "invalid rules config, ignoring",
+                    )
+                    .field("error", error);
+                }
+            }
+        }
+        let store = RuleStore::from_configuration(configuration);
+        match store.read(&self.host) {
+            Ok(file_rules) => self.file_rules = file_rules,
+            Err(error) => {
+                let path = store.path().to_owned();
+                self.push_trace_at(TraceLevel::Warn, TraceCategory::Rule, "rules_file_ignored")
+                    .field("path", path)
+                    .field("error", error);
+            }
+        }
+        self.rule_store = Some(store);
+    }
+
+    pub(crate) fn rules(&self) -> Vec<&WorkspaceRule> {
+        self.config_rules.iter().chain(&self.file_rules).collect()
+    }
+
+    /// Runs each matched rule's actions as if they had arrived on the `request` pipe.
+    /// Panes and tab names the actions produce never fire rules themselves, and a rule
+    /// that fired within its cooldown is skipped, so rules cannot feed each other.
+    pub(crate) fn fire_rules(&mut self, matches: Vec<RuleMatch>) {
+        if self.firing_rules {
+            return;
+        }
+        let now_ms = self.host.now_epoch_millis();
+        // Judged before this update fires anything, so all of its matches run.
+        let cooling = self
+            .rules()
+            .into_iter()
+            .filter(|rule| {
+                let cooldown_ms = rule.cooldown_ms.unwrap_or(DEFAULT_RULE_COOLDOWN_MS);
+                self.rule_stats
+                    .get(&rule.id)
+                    .is_some_and(|(fired, last_ms)| {
+                        *fired > 0 && now_ms.saturating_sub(*last_ms) < u128::from(cooldown_ms)
+                    })
+            })
+            .map(|rule| rule.id.clone())
+            .collect::<BTreeSet<_>>();
+        self.firing_rules = true;
+        for rule_match in matches {
+            let Some(rule) = self
+                .rules()
+                .into_iter()
+                .find(|rule| rule.id == rule_match.rule_id)
+                .cloned()
+            else {
+                continue;
+            };
+            if let Some(skipped) = self.rule_caused(&rule_match) {
+                self.push_trace_at(TraceLevel::Debug, TraceCategory::Rule, skipped)
+                    .field("rule", &rule.id);
+                continue;
+            }
+            if cooling.contains(&rule.id) {
+                self.push_trace(TraceCategory::Rule, "rule_cooling_down")
+                    .field("rule", &rule.id);
+                continue;
+            }
+            let stats = self.rule_stats.entry(rule.id.clone()).or_default();
+            *stats = (stats.0 + 1, now_ms);
+            let entry = self
+                .push_trace(TraceCategory::Rule, "rule_fired")
+                .field("rule", &rule.id);
+            if let Some(pane_id) = rule_match.pane_id {
+                entry.field("pane_id", pane_id);
+            }
+            if let Some(tab_position) = rule_match.tab_position {
+                entry.field("tab_position", tab_position);
+            }
+            if let Some(message) = rule.trace_message.as_deref() {
+                self.push_trace_at(TraceLevel::Warn, TraceCategory::Rule, "rule_trace_message")
+                    .field("rule", &rule.id)
+                    .field("message", message);
+            }
+            for action in &rule.actions {
+                let request = expand_action(action, rule_match.pane_id, rule_match.tab_position);
+                let response = self.dispatch_request_value(request.clone());
+                if response.get("ok") == Some(&Value::Bool(true)) {
+                    self.note_rule_effects(&request, &response);
+                } else {
+                    let op = action.get("op").and_then(Value::as_str).unwrap_or("?");
+                    let code = response
+                        .get("code")
+                        .and_then(Value::as_str)
+                        .unwrap_or("unknown");
+                    self.push_trace_at(TraceLevel::Warn, TraceCategory::Rule, "rule_action_failed")
+                        .field("rule", &rule.id)
+                        .field("op", op)
+                        .field("code", code);
+                }
+            }
+        }
+        self.firing_rules = false;
+    }
+
+    /// Why a match was caused by an earlier rule action, if it was. A rule-named tab
+    /// is only exempt the first time it appears.
+    fn rule_caused(&mut self, rule_match: &RuleMatch) -> Option<&'static str> {
+        if let Some(pane_id) = rule_match.pane_id {
+            return self
+                .rule_spawned_panes
+                .contains(&pane_id)
+                .then_some("rule_skipped_rule_pane");
+        }
+        let name = self
+            .tabs
+            .as_ref()?
+            .iter()
+            .find(|tab| Some(tab.position) == rule_match.tab_position)?
+            .name
+            .clone();
+        self.rule_named_tabs
+            .remove(&name)
+            .then_some("rule_skipped_rule_tab_name")
+    }
+
+    /// Remembers the panes and tab names a successful rule action produced.
+    fn note_rule_effects(&mut self, request: &Value, response: &Value) {
+        if let Some(pane_id) = response["result"]["pane_id"].as_u64() {
+            self.rule_spawned_panes.insert(pane_id as u32);
+            while self.rule_spawned_panes.len() > RULE_SPAWNED_PANE_LIMIT {
+                self.rule_spawned_panes.pop_first();
+            }
+        }
+        if request["op"] == "rename_tab" {
+            if let Some(name) = request["name"].as_str() {
+                self.rule_named_tabs.insert(name.to_owned());
+            }
+        }
+    }
+
+    pub(crate) fn list_rules_response(&self) -> Value {
+        let listed = |rule: &WorkspaceRule, source: &str| {
+            let (fired, last_fired_ms) = self.rule_stats.get(&rule.id).copied().unwrap_or_default();
+            ListedRule {
+                rule: rule.clone(),
+                source: source.to_owned(),
+                fired,
+                last_fired_epoch_ms: (fired > 0)
+                    .then(|| u64::try_from(last_fired_ms).unwrap_or(u64::MAX)),
+            }
+        };
+        Self::serialized_response(&ListRulesResult {
+            path: self
+                .rule_store
+                .as_ref()
+                .map(|store| store.path().to_owned())
+                .unwrap_or_default(),
+            rules: self
+                .config_rules
+                .iter()
+                .map(|rule| listed(rule, "config"))
+                .chain(self.file_rules.iter().map(|rule| listed(rule, "file")))
+                .collect(),
+        })
+    }
+
+    pub(crate) fn add_rule(&mut self, rule: WorkspaceRule) -> Value {
+        if let Err(error) = validate_rule(&rule) {
+            return Self::error_response("invalid_rule", error);
+        }
+        if self.rules().iter().any(|existing| existing.id == rule.id) {
+            return Self::error_response(
+                "rule_exists",
+                format!("a rule with id {:?} already exists", rule.id),
+            );
+        }
+        self.push_trace(TraceCategory::Request, "add_rule")
+            .field("id", &rule.id);
+        self.file_rules.push(rule);
+        self.write_rules_file()
+    }
+
+    pub(crate) fn remove_rule(&mut self, id: &str) -> Value {
+        if self.config_rules.iter().any(|rule| rule.id == id) {
+            return Self::error_response(
+                "rule_readonly",
+                format!("rule {:?} comes from the plugin config", id),
+            );
+        }
+        let Some(index) = self.file_rules.iter().position(|rule| rule.id == id) else {
+            return Self::error_response("rule_not_found", format!("no rule with id {:?}", id));
+        };
+        self.push_trace(TraceCategory::Request, "remove_rule")
+            .field("id", id);
+        self.file_rules.remove(index);
+        self.rule_stats.remove(id);
+        self.write_rules_file()
+    }
+
+    /// The in-memory rules stay changed even if the write fails, so the session
+    /// behaves as asked; the error tells the caller it won't survive a reload.
+    fn write_rules_file(&mut self) -> Value {
+        let Some(store) = self.rule_store.as_ref() else {
+            return Self::ack_response();
+        };
+        match store.write(&mut self.host, &self.file_rules) {
+            Ok(()) => Self::ack_response(),
+            Err(error) => Self::error_response("io_error", format!("{}: {}", store.path(), error)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rule(json: Value) -> WorkspaceRule {
+        serde_json::from_value(json).unwrap()
+    }
+
+    fn manifest(panes: Vec<PaneInfo>) -> PaneManifest {
+        PaneManifest {
+            panes: [(0, panes)].into(),
+        }
+    }
+
+    fn pane(id: u32, command: &str, exit_status: Option<i32>) -> PaneInfo {
+        PaneInfo {
+            id,
+            terminal_command: Some(command.to_owned()),
+            exited: exit_status.is_some(),
+            exit_status,
+            ..PaneInfo::default()
+        }
+    }
+
+    #[test]
+    fn expand_action_replaces_placeholders_in_string_values_at_any_depth() {
+        let action = json!({
+            "op": "batch",
+            "requests": [{ "pane_id": "$pane_id", "position": "$tab_position" }],
+            "selector": "tab:$tab_position title:^$pane_id$",
+            "$pane_id": 1,
+        });
+
+        assert_eq!(
+            expand_action(&action, Some(4), Some(2)),
+            json!({
+                "op": "batch",
+                "requests": [{ "pane_id": 4, "position": 2 }],
+                "selector": "tab:2 title:^4$",
+                "$pane_id": 1,
+            })
+        );
+        assert_eq!(
+            expand_action(
+                &json!(["$pane_id", "$tab_position", "pane $pane_id"]),
+                None,
+                None
+            ),
+            json!([null, null, "pane $pane_id"])
+        );
+    }
+
+    #[test]
+    fn uses_placeholder_looks_inside_values_but_not_keys() {
+        assert!(uses_placeholder(
+            &json!({ "op": "x", "nested": [{ "pane_id": "$pane_id" }] }),
+            "$pane_id"
+        ));
+        assert!(uses_placeholder(
+            &json!({ "selector": "title:$pane_id" }),
+            "$pane_id"
+        ));
+        assert!(!uses_placeholder(&json!({ "$pane_id": 1 }), "$pane_id"));
+    }
+
+    #[test]
+    fn validate_rule_rejects_empty_forbidden_and_invalid_actions() {
+        for (json, error) in [
+            (
+                json!({ "id": " ", "when": { "event": "pane_opened" }, "trace_message": "x" }),
+                "id must not be empty",
+            ),
+            (
+                json!({ "id": "r", "when": { "event": "pane_opened" } }),
+                "no actions and no trace_message",
+            ),
+            (
+                json!({ "id": "r", "when": { "event": "pane_opened" },
+                        "actions": [{ "op": "batch", "requests": [] }] }),
+                "which rules may not run",
+            ),
+            (
+                json!({ "id": "r", "when": { "event": "pane_exited" },
+                        "actions": [{ "op": "close_panes", "selector": "tab:$tab_position" }] }),
+                "which rules may not run",
+            ),
+            (
+                json!({ "id": "r", "when": { "event": "pane_opened" },
+                        "actions": [{ "op": "no_such_op" }] }),
+                "not a valid request",
+            ),
+        ] {
+            let result = validate_rule(&rule(json)).unwrap_err();
+            assert!(result.contains(error), "{}", result);
+        }
+        assert!(validate_rule(&rule(json!({
+            "id": "r",
+            "when": { "event": "pane_exited" },
+            "actions": [{ "op": "hide_pane", "pane_id": "$pane_id" }],
+        })))
+        .is_ok());
+    }
+
+    #[test]
+    fn pane_changes_need_a_baseline_and_a_transition() {
+        let exited = rule(json!({
+            "id": "failed-build",
+            "when": { "event": "pane_exited", "command": "cargo", "nonzero_only": true },
+            "trace_message": "build failed",
+        }));
+        let rules = [&exited];
+        let running = manifest(vec![
+            pane(1, "cargo build", None),
+            pane(2, "cargo test", None),
+        ]);
+        let done = manifest(vec![
+            pane(1, "cargo build", Some(101)),
+            pane(2, "cargo test", Some(0)),
+        ]);
+
+        assert_eq!(match_pane_changes(&rules, None, &done), []);
+        assert_eq!(
+            match_pane_changes(&rules, Some(&running), &done),
+            [RuleMatch {
+                rule_id: "failed-build".to_owned(),
+                pane_id: Some(1),
+                tab_position: Some(0),
+            }]
+        );
+        assert_eq!(match_pane_changes(&rules, Some(&done), &done), []);
+    }
+
+    #[test]
+    fn tab_appeared_fires_for_new_names_only() {
+        let logs = rule(json!({
+            "id": "logs",
+            "when": { "event": "tab_appeared", "name": "logs" },
+            "trace_message": "logs tab",
+        }));
+        let rules = [&logs];
+        let tab = |position: usize, name: &str| TabInfo {
+            position,
+            name: name.to_owned(),
+            ..TabInfo::default()
+        };
+        let before = [tab(0, "main"), tab(1, "logs")];
+
+        assert_eq!(
+            match_tab_changes(&rules, Some(&before), &[tab(0, "logs"), tab(1, "x")]),
+            [],
+            "moving an existing name is not an appearance"
+        );
+        assert_eq!(
+            match_tab_changes(&rules, Some(&[tab(0, "main")]), &[tab(0, "logs")]),
+            [RuleMatch {
+                rule_id: "logs".to_owned(),
+                pane_id: None,
+                tab_position: Some(0),
+            }]
+        );
+    }
+}
@@ -0,0 +1,131 @@
+//! Scratch panes: named floating panes (a quick shell, `htop`, a notes editor) that the
+//! butler toggles the way it toggles Jelly J. Each comes from a `scratch.<name>.command`
+//! config key and is toggled with `toggle_scratch { name }` or a `scratch:<name>` pipe.
+
+use crate::host::ZellijHost;
+use crate::protocol::{TraceCategory, TraceLevel};
+use crate::state::{OwnedPane, State, TRACKED_PANE_MISSING_GRACE_MS};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use zellij_tile::prelude::*;
+
+/// Pipe names starting with this toggle the scratch pane named by the rest.
+pub(crate) const SCRATCH_PIPE_PREFIX: &str = "scratch:";
+
+/// Commands by scratch name, plus the config keys that were rejected. Names are ASCII
+/// letters, digits, `-` and `_`, so they are safe in pipe names and pane titles.
+pub(crate) fn scratch_commands(
+    configuration: &BTreeMap<String, String>,
+) -> (BTreeMap<String, String>, Vec<String>) {
+    let mut commands = BTreeMap::new();
+    let mut rejected = Vec::new();
+    for (key, value) in configuration {
+        let Some(name) = key
+            .strip_prefix("scratch.")
+            .and_then(|rest| rest.strip_suffix(".command"))
+        else {
+            continue;
+        };
+        let command = value.trim();
+        if !valid_scratch_name(name) || command.is_empty() {
+            rejected.push(key.clone());
+            continue;
+        }
+        commands.insert(name.to_owned(), command.to_owned());
+    }
+    (commands, rejected)
+}
+
+fn valid_scratch_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// What a launched scratch pane is titled, instead of its command line.
+pub(crate) fn scratch_pane_title(name: &str) -> String {
+    format!("{}{}", SCRATCH_PIPE_PREFIX, name)
+}
+
+impl<H: ZellijHost> State<H> {
+    /// Toggles scratch pane `name` with the same hide/show/move logic as the Jelly
+    /// pane, launching it when it was never started, was closed, or has exited.
+    pub(crate) fn toggle_scratch(
+        &mut self,
+        name: &str,
+        client_id: Option<ClientId>,
+    ) -> Result<(Option<u32>, &'static str), String> {
+        let current_tab = client_id
+            .and_then(|client_id| self.client_tab_index(client_id))
+            .or_else(|| self.active_tab_index())
+            .unwrap_or(0);
+        self.push_trace(TraceCategory::Toggle, "toggle_scratch")
+            .field("name", name)
+            .field("current_tab", current_tab);
+        if let Some(scratch_pane) = self.scratch_panes.get(name).copied() {
+            let pane_id = scratch_pane.pane_id;
+            if let Some((tab, pane)) = self.find_terminal_pane_by_id(pane_id) {
+                return Ok((
+                    Some(pane_id),
+                    self.toggle_existing_pane(tab, pane, current_tab),
+                ));
+            }
+            if self.exited_terminal_pane(pane_id).is_some() {
+                self.push_trace(TraceCategory::Toggle, "closing_exited_scratch_pane")
+                    .field("name", name)
+                    .field("id", pane_id);
+                self.host.close_terminal_pane(pane_id);
+            } else if scratch_pane.launched_ms.is_some_and(|launched_ms| {
+                self.host.now_epoch_millis().saturating_sub(launched_ms)
+                    <= TRACKED_PANE_MISSING_GRACE_MS
+            }) {
+                self.push_trace(TraceCategory::Toggle, "launch_skipped_scratch_pending")
+                    .field("name", name)
+                    .field("id", pane_id);
+                return Ok((Some(pane_id), "launch_skipped"));
+            }
+            self.scratch_panes.remove(name);
+        }
+
+        // Through `sh -c`, so the command line is quoted and expanded like in a shell.
+        let command = self.scratch_commands[name].clone();
+        let origin_pane = self.origin_pane(client_id, current_tab);
+        let launched = self.host.open_command_pane_floating(CommandToRun {
+            path: PathBuf::from("sh"),
+            args: vec!["-c".to_owned(), command],
+            cwd: self.launch_cwd_for(origin_pane),
+        });
+        self.record_launch_result(&launched);
+        match launched {
+            Ok(PaneId::Terminal(pane_id)) => {
+                self.push_trace(TraceCategory::Toggle, "launched_scratch_pane")
+                    .field("name", name)
+                    .field("pane_id", pane_id);
+                self.host
+                    .rename_pane(PaneId::Terminal(pane_id), &scratch_pane_title(name));
+                self.host.show_pane(PaneId::Terminal(pane_id), true, true);
+                let launched_ms = Some(self.host.now_epoch_millis());
+                self.scratch_panes.insert(
+                    name.to_owned(),
+                    OwnedPane {
+                        pane_id,
+                        launched_ms,
+                    },
+                );
+                Ok((Some(pane_id), "launch"))
+            }
+            Ok(pane_id) => Err(format!("unexpected pane kind {:?}", pane_id)),
+            Err(error) => {
+                self.push_trace_at(
+                    TraceLevel::Error,
+                    TraceCategory::Toggle,
+                    "open_scratch_pane_failed",
+                )
+                .field("name", name)
+                .field("error", &error);
+                Err(error)
+            }
+        }
+    }
+}
@@ -0,0 +1,176 @@
+//! Pane selectors: strings that pick terminal panes by what the butler knows about them.
+//!
+//! A selector is space-separated `key:value` terms that must all match:
+//! `tag:<tag>`, `tab:<position>`, `title:<regex>`, `command:<regex>`,
+//! `exited:<true|false>` and `floating:<true|false>`. Regexes are unanchored and cannot
+//! contain spaces (use `\s`). Ops that take a `pane_id` accept one in its place, and
+//! `hide_panes`/`show_panes`/`close_panes` take one as `selector`.
+
+use crate::tags::validate_tag;
+use regex::Regex;
+use std::collections::BTreeSet;
+use zellij_tile::prelude::PaneInfo;
+
+#[derive(Debug, Clone)]
+enum Term {
+    Tag(String),
+    Tab(usize),
+    Title(Regex),
+    Command(Regex),
+    Exited(bool),
+    Floating(bool),
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct PaneSelector {
+    terms: Vec<Term>,
+}
+
+/// What a selector can test about one terminal pane.
+pub(crate) struct SelectorFacts<'a> {
+    pub(crate) tab_index: usize,
+    pub(crate) pane: &'a PaneInfo,
+    /// Foreground command as reported by `ListClients`, when known.
+    pub(crate) foreground_command: Option<&'a str>,
+    pub(crate) tags: Option<&'a BTreeSet<String>>,
+}
+
+impl PaneSelector {
+    pub(crate) fn parse(selector: &str) -> Result<Self, String> {
+        let terms = selector
+            .split_whitespace()
+            .map(parse_term)
+            .collect::<Result<Vec<_>, _>>()?;
+        if terms.is_empty() {
+            return Err("selector is empty".to_owned());
+        }
+        Ok(PaneSelector { terms })
+    }
+
+    pub(crate) fn matches(&self, facts: &SelectorFacts) -> bool {
+        self.terms.iter().all(|term| match term {
+            Term::Tag(tag) => facts.tags.is_some_and(|tags| tags.contains(tag)),
+            Term::Tab(position) => facts.tab_index == *position,
+            Term::Title(regex) => regex.is_match(&facts.pane.title),
+            Term::Command(regex) => facts
+                .foreground_command
+                .into_iter()
+                .chain(facts.pane.terminal_command.as_deref())
+                .any(|command| regex.is_match(command)),
+            Term::Exited(exited) => facts.pane.exited == *exited,
+            Term::Floating(floating) => facts.pane.is_floating == *floating,
+        })
+    }
+}
+
+fn parse_term(term: &str) -> Result<Term, String> {
+    let Some((key, value)) = term.split_once(':') else {
+        return Err(format!("selector term {:?} is not key:value", term));
+    };
+    let flag = |value: &str| match value {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => Err(format!("{}: expects true or false, got {:?}", key, value)),
+    };
+    let regex = |value: &str| {
+        Regex::new(value).map_err(|error| format!("{}: invalid regex: {}", key, error))
+    };
+    match key {
+        "tag" => validate_tag(value).map(|()| Term::Tag(value.to_owned())),
+        "tab" => value
+            .parse()
+            .map(Term::Tab)
+            .map_err(|_| format!("tab: expects a 0-based position, got {:?}", value)),
+        "title" => regex(value).map(Term::Title),
+        "command" => regex(value).map(Term::Command),
+        "exited" => flag(value).map(Term::Exited),
+        "floating" => flag(value).map(Term::Floating),
+        _ => Err(format!(
+            "unknown selector key {:?} (expected tag, tab, title, command, exited or floating)",
+            key
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pane(title: &str, terminal_command: Option<&str>) -> PaneInfo {
+        PaneInfo {
+            id: 7,
+            title: title.to_owned(),
+            terminal_command: terminal_command.map(str::to_owned),
+            ..PaneInfo::default()
+        }
+    }
+
+    fn matches(selector: &str, facts: &SelectorFacts) -> bool {
+        PaneSelector::parse(selector).unwrap().matches(facts)
+    }
+
+    #[test]
+    fn parse_rejects_empty_malformed_and_unknown_terms() {
+        for (selector, error) in [
+            ("", "selector is empty"),
+            ("   ", "selector is empty"),
+            ("tag", "is not key:value"),
+            ("color:red", "unknown selector key"),
+            ("tab:-1", "0-based position"),
+            ("exited:yes", "expects true or false"),
+            ("title:(", "invalid regex"),
+            ("tag:", "invalid tag"),
+        ] {
+            let parsed = PaneSelector::parse(selector).unwrap_err();
+            assert!(parsed.contains(error), "{:?}: {}", selector, parsed);
+        }
+    }
+
+    #[test]
+    fn every_term_must_match() {
+        let pane = pane("logs", Some("tail -f app.log"));
+        let tags = BTreeSet::from(["watch".to_owned()]);
+        let facts = SelectorFacts {
+            tab_index: 1,
+            pane: &pane,
+            foreground_command: None,
+            tags: Some(&tags),
+        };
+
+        assert!(matches("tag:watch tab:1 title:^lo exited:false", &facts));
+        assert!(!matches("tag:watch tab:0", &facts));
+        assert!(!matches("tag:other", &facts));
+        assert!(!matches("floating:true", &facts));
+        // The value is everything after the first colon.
+        assert!(!matches("title:logs:1", &facts));
+    }
+
+    #[test]
+    fn untagged_panes_never_match_a_tag() {
+        let pane = pane("shell", None);
+        let facts = SelectorFacts {
+            tab_index: 0,
+            pane: &pane,
+            foreground_command: None,
+            tags: None,
+        };
+
+        assert!(!matches("tag:watch", &facts));
+        assert!(!matches("command:.", &facts), "no command is known");
+    }
+
+    #[test]
+    fn command_matches_the_foreground_or_the_launch_command() {
+        let pane = pane("shell", Some("bash"));
+        let facts = SelectorFacts {
+            tab_index: 0,
+            pane: &pane,
+            foreground_command: Some("cargo watch -x test"),
+            tags: None,
+        };
+
+        assert!(matches(r"command:cargo\swatch", &facts));
+        assert!(matches("command:^bash$", &facts));
+        assert!(!matches("command:^vim", &facts));
+    }
+}
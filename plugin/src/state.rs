@@ -0,0 +1,2183 @@
+use crate::activity::ActivityTracker;
+use crate::focus_history::FocusHistory;
+use crate::fs_watch::{self, FsChangeKind, FsWatch};
+use crate::host::ZellijHost;
+use crate::launch::{redacted_configuration, LaunchCwd};
+use crate::liveness::UiClient;
+use crate::metrics::Metrics;
+use crate::persist::{PersistedState, StateStore};
+use crate::protocol::{
+    self, AckResult, ActivityResult, BatchResult, ButlerClientPane, ButlerPaneInfo,
+    ButlerPaneState, ButlerRequest, ButlerRuntimeState, ButlerScratchPane, ButlerTabPane,
+    ButlerTabState, ButlerUiClient, ButlerWorkspaceState, ErrorResponse, ExportTraceResult,
+    FocusBackResult, FocusHistoryResult, FsChangesResult, HelloResult, LifetimeCounters,
+    ListTagsResult, OpenFileResult, PaneActivity, PaneGeometry, PaneTarget, PanesResult,
+    ScratchResult, TabActivity, TabNameSuggestionsResult, TagSummary, TraceCategory, TraceEntry,
+    TraceLevel, TraceResult, WorkspaceRule, PROTOCOL_VERSION, SUPPORTED_OPS,
+};
+use crate::recording::{EventRecorder, RecordedEvent, DEFAULT_RECORDING_LIMIT};
+use crate::rules::{self, RuleStore};
+use crate::scratch::{self, SCRATCH_PIPE_PREFIX};
+use crate::selector::{PaneSelector, SelectorFacts};
+use crate::tab_names::DEFAULT_REPO_ROOTS;
+use crate::tags;
+use crate::toggle::{KeybindAction, KEYBIND_CLIENT_WAIT_MS};
+use crate::trace::{self, TraceFilter, TraceLog};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::path::PathBuf;
+use zellij_tile::prelude::*;
+
+pub const PANE_NAME: &str = "Jelly J";
+const COMMAND: &str = "jelly-j ui";
+pub const TRACKED_PANE_MISSING_GRACE_MS: u128 = 1_500;
+/// How many Jelly panes the butler keeps, from the `pane_policy` config.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum PanePolicy {
+    /// One pane per session, moved to whichever tab toggles it.
+    #[default]
+    Single,
+    /// One pane per attached client, when the toggle names its client.
+    PerClient,
+    /// One pane pinned in each tab; panes are never moved between tabs.
+    PerTab,
+}
+
+impl PanePolicy {
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "single" => Some(PanePolicy::Single),
+            "per_client" => Some(PanePolicy::PerClient),
+            "per_tab" => Some(PanePolicy::PerTab),
+            _ => None,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            PanePolicy::Single => "single",
+            PanePolicy::PerClient => "per_client",
+            PanePolicy::PerTab => "per_tab",
+        }
+    }
+}
+
+/// What to do when a Jelly pane's command exits, from the `on_exit` config.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum ExitPolicy {
+    /// Restart the command in the same pane.
+    #[default]
+    Rerun,
+    /// Close the pane; relaunch right away if it was on screen.
+    Close,
+    /// Leave the exited pane for inspection and stop tracking it.
+    Keep,
+}
+
+impl ExitPolicy {
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "rerun" => Some(ExitPolicy::Rerun),
+            "close" => Some(ExitPolicy::Close),
+            "keep" => Some(ExitPolicy::Keep),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            ExitPolicy::Rerun => "rerun",
+            ExitPolicy::Close => "close",
+            ExitPolicy::Keep => "keep",
+        }
+    }
+}
+
+/// Deterministic renaming of `Tab #N` tabs, from the `auto_name_tabs` config.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum AutoNameTabs {
+    #[default]
+    Off,
+    /// Only answer `get_tab_name_suggestions`.
+    Suggest,
+    /// Also rename default-named tabs on `TabUpdate`.
+    Apply,
+}
+
+impl AutoNameTabs {
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "off" => Some(AutoNameTabs::Off),
+            "suggest" => Some(AutoNameTabs::Suggest),
+            "apply" => Some(AutoNameTabs::Apply),
+            _ => None,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            AutoNameTabs::Off => "off",
+            AutoNameTabs::Suggest => "suggest",
+            AutoNameTabs::Apply => "apply",
+        }
+    }
+}
+
+/// A Jelly pane owned by one client ([`PanePolicy::PerClient`]) or one tab
+/// ([`PanePolicy::PerTab`]), or a scratch pane.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct OwnedPane {
+    pub(crate) pane_id: u32,
+    /// Set when we launched it, so a pane that hasn't shown up in a PaneUpdate yet
+    /// isn't launched twice.
+    pub(crate) launched_ms: Option<u128>,
+}
+
+const REQUESTED_PERMISSIONS: &[PermissionType] = &[
+    PermissionType::ReadApplicationState,
+    PermissionType::ChangeApplicationState,
+    PermissionType::OpenTerminalsOrPlugins,
+    PermissionType::RunCommands,
+    PermissionType::ReadCliPipes,
+    PermissionType::OpenFiles,
+];
+
+/// The butler state machine. All Zellij side effects go through `host`, so the same
+/// logic runs inside the wasm plugin and against [`crate::fake_host::FakeHost`] in tests.
+#[derive(Default)]
+pub struct State<H> {
+    pub(crate) host: H,
+    pub(crate) panes: Option<PaneManifest>,
+    pub(crate) tabs: Option<Vec<TabInfo>>,
+    pub(crate) ready: bool,
+    pub(crate) permission_result_seen: bool,
+    pub(crate) permission_denied: bool,
+    pub(crate) pending_toggle: bool,
+    /// Client that sent the pending toggle, when the pipe message named one.
+    pub(crate) pending_toggle_client: Option<ClientId>,
+    /// Keybind pipes carry no client id; these wait for the next `ListClients`, whose
+    /// `is_current_client` entry is the client that pressed the key.
+    pub(crate) keybind_actions: Vec<KeybindAction>,
+    /// When the oldest waiting keybind pipe arrived.
+    pub(crate) keybind_wait_since_ms: Option<u128>,
+    pub(crate) pane_policy: PanePolicy,
+    /// Last `ListClients` answer: each client's focused pane.
+    pub(crate) clients: Vec<ClientInfo>,
+    pub(crate) client_panes: BTreeMap<ClientId, OwnedPane>,
+    /// Tab position to its pinned Jelly pane under [`PanePolicy::PerTab`].
+    pub(crate) tab_panes: BTreeMap<usize, OwnedPane>,
+    pub(crate) jelly_pane_id: Option<u32>,
+    /// Tokens handed to Jelly panes we launched, by token.
+    pub(crate) ui_tokens: BTreeMap<String, u32>,
+    /// Panes whose UI proved it is Jelly J by echoing its launch token.
+    pub(crate) registered_jelly_panes: BTreeSet<u32>,
+    /// Registered UIs by pane id.
+    pub(crate) ui_clients: BTreeMap<u32, UiClient>,
+    /// Milliseconds the plugin has been receiving events, gaps capped at
+    /// [`crate::liveness::ACTIVITY_GAP_CAP_MS`]; UI silence is measured on this clock.
+    pub(crate) activity_ms: u128,
+    pub(crate) last_event_ms: Option<u128>,
+    pub(crate) exit_policy: ExitPolicy,
+    /// When recent automatic restarts happened, oldest first.
+    pub(crate) exit_restarts: VecDeque<u128>,
+    /// Exited panes already acted on; cleared once they run again or go away.
+    pub(crate) handled_exits: BTreeSet<u32>,
+    /// Exited panes waiting for the backoff timer, with the epoch ms each is due at.
+    pub(crate) deferred_restarts: BTreeMap<u32, u128>,
+    pub(crate) launch_command: Option<String>,
+    pub(crate) launch_cwd: LaunchCwd,
+    /// Extra environment for `jelly-j ui`, from `launch_env.<NAME>` config keys.
+    pub(crate) launch_env: BTreeMap<String, String>,
+    /// Working directories reported by `CwdChanged`, by terminal pane id.
+    pub(crate) pane_cwds: BTreeMap<u32, PathBuf>,
+    /// Foreground commands reported by `ListClients` for focused panes.
+    pub(crate) pane_commands: BTreeMap<u32, String>,
+    pub(crate) auto_name_tabs: AutoNameTabs,
+    /// Directories whose children count as repositories when naming tabs.
+    pub(crate) tab_name_repo_roots: Vec<String>,
+    pub(crate) rule_store: Option<RuleStore>,
+    /// Rules from the `rules` config; read-only.
+    pub(crate) config_rules: Vec<WorkspaceRule>,
+    /// Rules from the rules file, edited by `add_rule`/`remove_rule`.
+    pub(crate) file_rules: Vec<WorkspaceRule>,
+    /// Times fired and when last, by rule id.
+    pub(crate) rule_stats: BTreeMap<String, (u64, u128)>,
+    /// Set while rule actions run, so nothing they do can fire rules in turn.
+    pub(crate) firing_rules: bool,
+    /// Panes opened by rule actions; rules never fire for them.
+    pub(crate) rule_spawned_panes: BTreeSet<u32>,
+    /// Tab names set by rule actions; their next appearance fires no `tab_appeared`.
+    pub(crate) rule_named_tabs: BTreeSet<String>,
+    /// Names `apply` gave each tab position, so a stale TabUpdate still showing
+    /// `Tab #N` doesn't rename it again.
+    pub(crate) auto_named_tabs: BTreeMap<usize, String>,
+    pub(crate) activity: ActivityTracker,
+    pub(crate) focus_history: FocusHistory,
+    /// Labels from `tag_pane`, by terminal pane id.
+    pub(crate) pane_tags: BTreeMap<u32, BTreeSet<String>>,
+    /// Commands from the `scratch.<name>.command` config, by name.
+    pub(crate) scratch_commands: BTreeMap<String, String>,
+    /// The pane each scratch name is using.
+    pub(crate) scratch_panes: BTreeMap<String, OwnedPane>,
+    /// Set when the `fs_watch` config is on.
+    pub(crate) fs_watch: Option<FsWatch>,
+    /// When the timer armed to cut the pending filesystem burst fires.
+    pub(crate) fs_cut_timer_ms: Option<u128>,
+    pub(crate) pane_update_count: u64,
+    pub(crate) tab_update_count: u64,
+    pub(crate) seen_pane_update: bool,
+    pub(crate) seen_tab_update: bool,
+    pub(crate) last_cli_toggle_pipe_id: Option<String>,
+    pub(crate) trace: TraceLog,
+    /// Keyed by invoking client so two users pressing Alt+j together aren't deduped.
+    pub(crate) last_toggle_epoch_ms: BTreeMap<Option<ClientId>, u128>,
+    pub(crate) tracked_pane_missing_since_ms: Option<u128>,
+    pub(crate) configuration: BTreeMap<String, String>,
+    pub(crate) recorder: Option<EventRecorder>,
+    pub(crate) metrics: Metrics,
+    /// When the toggle currently pending (or being run) arrived, for latency metrics.
+    pub(crate) toggle_received_ms: Option<u128>,
+    pub(crate) state_store: Option<StateStore>,
+    /// State read at load, held until the first PaneUpdate can vouch for it.
+    pub(crate) restored: Option<PersistedState>,
+    pub(crate) restored_jelly_pane: bool,
+    pub(crate) jelly_geometry: Option<PaneGeometry>,
+    pub(crate) lifetime: LifetimeCounters,
+}
+
+impl<H: ZellijHost> State<H> {
+    pub fn new(host: H) -> Self
+    where
+        H: Default,
+    {
+        Self {
+            host,
+            ..Self::default()
+        }
+    }
+
+    pub fn host(&self) -> &H {
+        &self.host
+    }
+
+    pub fn host_mut(&mut self) -> &mut H {
+        &mut self.host
+    }
+
+    pub fn jelly_pane_id(&self) -> Option<u32> {
+        self.jelly_pane_id
+    }
+
+    pub fn load(&mut self, configuration: BTreeMap<String, String>) {
+        let rejected_trace_log = self.trace.configure(&configuration).err();
+        self.metrics.start(self.host.now_epoch_millis());
+        if let Some(dir) = rejected_trace_log {
+            self.push_trace_at(
+                TraceLevel::Warn,
+                TraceCategory::Lifecycle,
+                "trace_log_rejected",
+            )
+            .field("dir", dir)
+            .field(
+                "reason",
+                "trace_log must be data, tmp or a directory under /data or /tmp",
+            );
+        }
+        self.read_persisted_state(&configuration);
+        if configuration
+            .get("record_events")
+            .is_some_and(|value| value.trim() == "true")
+        {
+            let limit = configuration
+                .get("record_events_limit")
+                .and_then(|value| value.trim().parse::<usize>().ok())
+                .unwrap_or(DEFAULT_RECORDING_LIMIT);
+            self.recorder = Some(EventRecorder::new(limit));
+        }
+        self.record(|| RecordedEvent::Load {
+            configuration: redacted_configuration(&configuration),
+        });
+        self.configuration = configuration.clone();
+        if let Some(value) = configuration.get("pane_policy") {
+            match PanePolicy::parse(value) {
+                Some(pane_policy) => self.pane_policy = pane_policy,
+                None => {
+                    self.push_trace_at(
+                        TraceLevel::Warn,
+                        TraceCategory::Lifecycle,
+                        "unknown pane_policy, using single",
+                    )
+                    .field("value", value);
+                }
+            }
+        }
+        if let Some(value) = configuration.get("on_exit") {
+            match ExitPolicy::parse(value) {
+                Some(exit_policy) => self.exit_policy = exit_policy,
+                None => {
+                    self.push_trace_at(
+                        TraceLevel::Warn,
+                        TraceCategory::Lifecycle,
+                        "unknown on_exit, using rerun",
+                    )
+                    .field("value", value);
+                }
+            }
+        }
+        if let Some(launch_command) = configuration.get("launch_command").map(|s| s.trim()) {
+            if !launch_command.is_empty() {
+                self.launch_command = Some(launch_command.to_owned());
+            }
+        }
+        if let Some(value) = configuration.get("launch_cwd") {
+            match LaunchCwd::parse(value) {
+                Some(launch_cwd) => self.launch_cwd = launch_cwd,
+                None => {
+                    self.push_trace_at(
+                        TraceLevel::Warn,
+                        TraceCategory::Lifecycle,
+                        "unknown launch_cwd, using focused",
+                    )
+                    .field("value", value);
+                }
+            }
+        }
+        if let Some(value) = configuration.get("auto_name_tabs") {
+            match AutoNameTabs::parse(value) {
+                Some(auto_name_tabs) => self.auto_name_tabs = auto_name_tabs,
+                None => {
+                    self.push_trace_at(
+                        TraceLevel::Warn,
+                        TraceCategory::Lifecycle,
+                        "unknown auto_name_tabs, using off",
+                    )
+                    .field("value", value);
+                }
+            }
+        }
+        self.tab_name_repo_roots = match configuration.get("tab_name_repo_roots") {
+            Some(value) => value
+                .split(',')
+                .map(str::trim)
+                .filter(|root| !root.is_empty())
+                .map(str::to_owned)
+                .collect(),
+            None => DEFAULT_REPO_ROOTS
+                .iter()
+                .map(|root| (*root).to_owned())
+                .collect(),
+        };
+        self.load_rules(&configuration);
+        for (key, value) in &configuration {
+            let Some(name) = key.strip_prefix("launch_env.") else {
+                continue;
+            };
+            if name.is_empty() || name.contains('=') || name.contains(char::is_whitespace) {
+                self.push_trace_at(
+                    TraceLevel::Warn,
+                    TraceCategory::Lifecycle,
+                    "invalid launch_env name, ignoring",
+                )
+                .field("key", key);
+                continue;
+            }
+            self.launch_env.insert(name.to_owned(), value.clone());
+        }
+        let (scratch_commands, rejected) = scratch::scratch_commands(&configuration);
+        for key in rejected {
+            self.push_trace_at(
+                TraceLevel::Warn,
+                TraceCategory::Lifecycle,
+                "invalid scratch pane config, ignoring",
+            )
+            .field("key", key);
+        }
+        self.scratch_commands = scratch_commands;
+        if matches!(
+            configuration.get("fs_watch").map(|value| value.trim()),
+            Some("true") | Some("on")
+        ) {
+            let debounce_ms = match configuration.get("fs_watch_debounce_ms") {
+                Some(value) => value.trim().parse::<u128>().unwrap_or_else(|_| {
+                    self.push_trace_at(
+                        TraceLevel::Warn,
+                        TraceCategory::Lifecycle,
+                        "invalid fs_watch_debounce_ms, using default",
+                    )
+                    .field("value", value);
+                    fs_watch::DEFAULT_DEBOUNCE_MS
+                }),
+                None => fs_watch::DEFAULT_DEBOUNCE_MS,
+            };
+            self.fs_watch = Some(FsWatch::new(
+                configuration
+                    .get("fs_watch_include")
+                    .map_or("", String::as_str),
+                configuration
+                    .get("fs_watch_exclude")
+                    .map_or(fs_watch::DEFAULT_EXCLUDE, String::as_str),
+                debounce_ms,
+            ));
+        }
+        let launch_command = self.launch_command().to_owned();
+        let launch_cwd = self.launch_cwd.describe();
+        let trace_log = self.trace.log_path().unwrap_or("off").to_owned();
+        let pane_policy = self.pane_policy.as_str();
+        let on_exit = self.exit_policy.as_str();
+        let auto_name_tabs = self.auto_name_tabs.as_str();
+        self.push_trace(TraceCategory::Lifecycle, "load")
+            .field("launch_command", launch_command)
+            .field("launch_cwd", launch_cwd)
+            .field("pane_policy", pane_policy)
+            .field("on_exit", on_exit)
+            .field("auto_name_tabs", auto_name_tabs)
+            .field("trace_log", trace_log);
+
+        self.host.subscribe(&[
+            EventType::PaneUpdate,
+            EventType::TabUpdate,
+            EventType::PermissionRequestResult,
+            EventType::ListClients,
+            EventType::Timer,
+            EventType::CwdChanged,
+        ]);
+        self.push_trace_at(
+            TraceLevel::Debug,
+            TraceCategory::Lifecycle,
+            "subscribed to PaneUpdate/TabUpdate/PermissionRequestResult/ListClients/Timer/CwdChanged",
+        );
+        if self.fs_watch.is_some() {
+            self.host.subscribe(&[
+                EventType::FileSystemCreate,
+                EventType::FileSystemUpdate,
+                EventType::FileSystemDelete,
+            ]);
+            self.host.watch_filesystem();
+            self.push_trace_at(
+                TraceLevel::Debug,
+                TraceCategory::Lifecycle,
+                "watching /host for filesystem changes",
+            );
+        }
+        self.host.request_permission(REQUESTED_PERMISSIONS);
+        self.push_trace_at(
+            TraceLevel::Debug,
+            TraceCategory::Permission,
+            "requested permissions",
+        );
+        self.host.request_plugin_state_snapshot();
+        self.push_trace_at(
+            TraceLevel::Debug,
+            TraceCategory::Lifecycle,
+            "requested initial plugin state snapshot",
+        );
+        self.trace.flush(&mut self.host);
+    }
+
+    pub fn update(&mut self, mut event: Event) -> bool {
+        self.note_activity();
+        self.redact_event(&mut event);
+        if self.recorder.is_some() {
+            if let Some(recorded) = RecordedEvent::from_event(&event) {
+                self.record(|| recorded);
+            }
+        }
+        match event {
+            Event::PermissionRequestResult(PermissionStatus::Granted) => {
+                self.metrics.count_event("permission_result");
+                self.permission_result_seen = true;
+                self.permission_denied = false;
+                self.ready = true;
+                self.push_trace(TraceCategory::Permission, "permission granted");
+                self.host.request_plugin_state_snapshot();
+                self.host.list_clients();
+                self.push_trace_at(
+                    TraceLevel::Debug,
+                    TraceCategory::Lifecycle,
+                    "requested plugin state snapshot after permission grant",
+                );
+                self.try_run_toggle();
+            }
+            Event::PermissionRequestResult(PermissionStatus::Denied) => {
+                self.metrics.count_event("permission_result");
+                self.permission_result_seen = true;
+                self.permission_denied = true;
+                self.ready = false;
+                self.push_trace_at(
+                    TraceLevel::Warn,
+                    TraceCategory::Permission,
+                    "permission denied",
+                );
+            }
+            Event::PaneUpdate(manifest) => {
+                self.metrics.count_event("pane_update");
+                self.pane_update_count = self.pane_update_count.saturating_add(1);
+                if !self.seen_pane_update {
+                    self.seen_pane_update = true;
+                    self.push_trace(TraceCategory::Lifecycle, "first PaneUpdate received");
+                }
+                let previous_panes = self.panes.replace(manifest);
+                if let Some(persisted) = self.restored.take() {
+                    self.adopt_persisted_state(persisted);
+                }
+                self.forget_closed_panes();
+                if let Some(pane_id) = self.jelly_pane_id {
+                    // An exited pane is still there; `on_exit` decides what happens to it.
+                    if !self.terminal_pane_present(pane_id) {
+                        let now_ms = self.host.now_epoch_millis();
+                        match self.tracked_pane_missing_since_ms {
+                            None => {
+                                self.tracked_pane_missing_since_ms = Some(now_ms);
+                                self.push_trace(
+                                    TraceCategory::Toggle,
+                                    "tracked_jelly_pane_temporarily_missing",
+                                )
+                                .field("id", pane_id);
+                            }
+                            Some(since_ms)
+                                if now_ms.saturating_sub(since_ms)
+                                    > TRACKED_PANE_MISSING_GRACE_MS =>
+                            {
+                                self.push_trace_at(
+                                    TraceLevel::Warn,
+                                    TraceCategory::Toggle,
+                                    "tracked_jelly_pane_missing_timeout_clear",
+                                )
+                                .field("id", pane_id);
+                                self.metrics.toggles_mut().grace_period_timeouts += 1;
+                                self.jelly_pane_id = None;
+                                self.tracked_pane_missing_since_ms = None;
+                            }
+                            _ => {}
+                        }
+                    } else {
+                        self.tracked_pane_missing_since_ms = None;
+                        self.capture_jelly_geometry(pane_id);
+                    }
+                }
+                self.remap_tab_panes();
+                self.observe_activity();
+                self.record_focus();
+                self.handle_exited_jelly_panes();
+                self.infer_cached_permission_grant();
+                self.try_run_toggle();
+                if self.ready {
+                    let matches = self.panes.as_ref().map(|panes| {
+                        rules::match_pane_changes(&self.rules(), previous_panes.as_ref(), panes)
+                    });
+                    self.fire_rules(matches.unwrap_or_default());
+                }
+            }
+            Event::TabUpdate(tab_infos) => {
+                self.metrics.count_event("tab_update");
+                self.tab_update_count = self.tab_update_count.saturating_add(1);
+                if !self.seen_tab_update {
+                    self.seen_tab_update = true;
+                    self.push_trace(TraceCategory::Lifecycle, "first TabUpdate received");
+                }
+                let previous_tabs = self.tabs.replace(tab_infos);
+                // Tab switches are when clients' focus moves; keep the list fresh.
+                if self.ready {
+                    self.host.list_clients();
+                }
+                self.remap_tab_panes();
+                self.observe_activity();
+                self.record_focus();
+                self.infer_cached_permission_grant();
+                self.try_run_toggle();
+                if self.auto_name_tabs == AutoNameTabs::Apply && self.ready {
+                    self.apply_tab_names();
+                }
+                if self.ready {
+                    let matches = self.tabs.as_ref().map(|tabs| {
+                        rules::match_tab_changes(&self.rules(), previous_tabs.as_deref(), tabs)
+                    });
+                    self.fire_rules(matches.unwrap_or_default());
+                }
+            }
+            Event::ListClients(clients) => {
+                self.metrics.count_event("list_clients");
+                for client in &clients {
+                    if let PaneId::Terminal(pane_id) = client.pane_id {
+                        if !client.running_command.trim().is_empty() {
+                            self.pane_commands
+                                .insert(pane_id, client.running_command.trim().to_owned());
+                        }
+                    }
+                }
+                self.clients = clients;
+                if self.keybind_wait_since_ms.is_some() {
+                    self.run_keybind_actions();
+                }
+                self.try_run_toggle();
+            }
+            Event::Timer(_) => {
+                self.metrics.count_event("timer");
+                self.run_due_restarts();
+                self.cut_quiet_fs_burst();
+                let now_ms = self.host.now_epoch_millis();
+                if self.keybind_wait_since_ms.is_some_and(|since_ms| {
+                    now_ms.saturating_sub(since_ms) >= KEYBIND_CLIENT_WAIT_MS
+                }) {
+                    self.push_trace(TraceCategory::Pipe, "keybind client wait timed out");
+                    self.run_keybind_actions();
+                }
+            }
+            Event::CwdChanged(PaneId::Terminal(pane_id), cwd, _) => {
+                self.metrics.count_event("cwd_changed");
+                self.pane_cwds.insert(pane_id, cwd);
+            }
+            Event::FileSystemCreate(paths) => {
+                self.observe_fs_changes(FsChangeKind::Created, &paths)
+            }
+            Event::FileSystemUpdate(paths) => {
+                self.observe_fs_changes(FsChangeKind::Updated, &paths)
+            }
+            Event::FileSystemDelete(paths) => {
+                self.observe_fs_changes(FsChangeKind::Deleted, &paths)
+            }
+            _ => {}
+        }
+        self.save_persisted_state();
+        self.trace.flush(&mut self.host);
+        // We don't render UI; rendering only calls hide_self(). Returning true on every
+        // state event creates a feedback loop of render/hide/update cycles.
+        false
+    }
+
+    pub fn pipe(&mut self, pipe_message: PipeMessage) -> bool {
+        self.note_activity();
+        self.record(|| RecordedEvent::pipe(&pipe_message));
+        match pipe_message.name.as_str() {
+            "toggle" => {
+                self.metrics.count_event("pipe_toggle");
+                let client_id = pipe_client_id(&pipe_message);
+                self.handle_toggle_pipe(pipe_message.source, client_id);
+            }
+            name if name.starts_with(SCRATCH_PIPE_PREFIX) => {
+                self.metrics.count_event("pipe_scratch");
+                self.push_trace(TraceCategory::Pipe, "pipe scratch")
+                    .field("name", name);
+                let name = name[SCRATCH_PIPE_PREFIX.len()..].to_owned();
+                let client_id = pipe_client_id(&pipe_message);
+                if client_id.is_none() && matches!(pipe_message.source, PipeSource::Keybind) {
+                    self.await_keybind_client(KeybindAction::Scratch(name));
+                } else {
+                    let response =
+                        self.execute_request(ButlerRequest::ToggleScratch { name, client_id });
+                    self.respond_to_cli(&pipe_message.source, Some(response));
+                }
+            }
+            "jump_back" => {
+                self.metrics.count_event("pipe_jump_back");
+                self.push_trace(TraceCategory::Pipe, "pipe jump_back");
+                let response = self.execute_request(ButlerRequest::FocusBack { steps: None });
+                self.respond_to_cli(&pipe_message.source, Some(response));
+            }
+            "request" => {
+                self.metrics.count_event("pipe_request");
+                self.push_trace(TraceCategory::Pipe, "pipe request");
+                self.handle_request_pipe(pipe_message);
+            }
+            _ => self.metrics.count_event("pipe_other"),
+        }
+        self.save_persisted_state();
+        self.trace.flush(&mut self.host);
+        false
+    }
+}
+
+impl<H: ZellijHost> State<H> {
+    fn infer_cached_permission_grant(&mut self) {
+        if self.ready
+            || self.permission_result_seen
+            || self.permission_denied
+            || self.panes.is_none()
+        {
+            return;
+        }
+        self.ready = true;
+        self.push_trace(
+            TraceCategory::Permission,
+            "permission inferred via cached grant (no result event)",
+        );
+    }
+
+    pub(crate) fn launch_command(&self) -> &str {
+        self.launch_command.as_deref().unwrap_or(COMMAND)
+    }
+
+    pub(crate) fn push_trace(&mut self, category: TraceCategory, message: &str) -> &mut TraceEntry {
+        self.push_trace_at(TraceLevel::Info, category, message)
+    }
+
+    pub(crate) fn push_trace_at(
+        &mut self,
+        level: TraceLevel,
+        category: TraceCategory,
+        message: &str,
+    ) -> &mut TraceEntry {
+        let now_ms = self.host.now_epoch_millis();
+        self.trace.push(now_ms, level, category, message)
+    }
+
+    fn export_trace(&mut self, path: &str) -> Value {
+        // Make sure the file includes everything up to this request.
+        self.trace.flush(&mut self.host);
+        let contents = self.trace.export_lines(&self.host);
+        if let Err(error) = self.host.write_file(path, &contents) {
+            self.push_trace_at(
+                TraceLevel::Warn,
+                TraceCategory::Request,
+                "export_trace failed",
+            )
+            .field("path", path)
+            .field("error", &error);
+            return Self::error_response("io_error", format!("{}: {}", path, error));
+        }
+        let entries = contents.lines().count();
+        self.push_trace(TraceCategory::Request, "export_trace")
+            .field("path", path)
+            .field("entries", entries);
+        Self::serialized_response(&ExportTraceResult {
+            path: path.to_owned(),
+            entries,
+            bytes: contents.len() as u64,
+            persisted: self.trace.log_path().is_some(),
+        })
+    }
+
+    pub(crate) fn ok_response(result: Value) -> Value {
+        json!({
+            "ok": true,
+            "result": result,
+        })
+    }
+
+    pub(crate) fn serialized_response(result: &impl Serialize) -> Value {
+        Self::ok_response(serde_json::to_value(result).unwrap_or_else(|_| json!({})))
+    }
+
+    pub(crate) fn ack_response() -> Value {
+        Self::serialized_response(&AckResult { ok: true })
+    }
+
+    pub(crate) fn error_response(code: &str, message: impl Into<String>) -> Value {
+        serde_json::to_value(ErrorResponse {
+            ok: false,
+            code: code.to_owned(),
+            error: message.into(),
+        })
+        .unwrap_or_else(|_| json!({ "ok": false, "code": code }))
+    }
+
+    fn validation_failed_response(failures: Vec<Value>) -> Value {
+        json!({
+            "ok": false,
+            "code": "validation_failed",
+            "error": format!("{} batch step(s) failed validation", failures.len()),
+            "failures": failures,
+        })
+    }
+
+    fn not_ready_response() -> Value {
+        Self::error_response("not_ready", "butler permissions not granted yet")
+    }
+
+    fn ensure_ready(&self) -> Result<(), Value> {
+        if self.ready {
+            Ok(())
+        } else {
+            Err(Self::not_ready_response())
+        }
+    }
+
+    fn tab_position_exists(&self, position: usize) -> bool {
+        self.tabs
+            .as_ref()
+            .is_some_and(|tabs| tabs.iter().any(|tab| tab.position == position))
+    }
+
+    fn terminal_pane_exists(&self, pane_id: u32) -> bool {
+        self.find_terminal_pane_by_id(pane_id).is_some()
+    }
+
+    fn ensure_tab_position_available(&self, position: usize) -> Result<(), Value> {
+        self.ensure_ready()?;
+        if self.tab_position_exists(position) {
+            Ok(())
+        } else {
+            Err(Self::error_response(
+                "tab_not_found",
+                format!("tab at position {} was not found", position),
+            ))
+        }
+    }
+
+    fn show_pane_options(
+        should_float_if_hidden: Option<bool>,
+        should_focus_pane: Option<bool>,
+    ) -> (bool, bool) {
+        (
+            should_float_if_hidden.unwrap_or(true),
+            should_focus_pane.unwrap_or(true),
+        )
+    }
+
+    pub(crate) fn respond_to_cli(&mut self, source: &PipeSource, response: Option<Value>) {
+        if let PipeSource::Cli(pipe_id) = source {
+            if let Some(response) = response {
+                self.host.cli_pipe_output(pipe_id, &response.to_string());
+            }
+            self.host.unblock_cli_pipe_input(pipe_id);
+        }
+    }
+
+    fn handle_request_pipe(&mut self, pipe_message: PipeMessage) {
+        let source = pipe_message.source;
+        let Some(payload) = pipe_message.payload else {
+            self.respond_to_cli(
+                &source,
+                Some(Self::error_response(
+                    "invalid_request",
+                    "missing request payload",
+                )),
+            );
+            return;
+        };
+
+        let (op, request_id, response) = match serde_json::from_str::<Value>(&payload) {
+            Ok(request) => {
+                let op = request
+                    .get("op")
+                    .and_then(Value::as_str)
+                    .unwrap_or("invalid")
+                    .to_owned();
+                let request_id = request.get("id").cloned();
+                (op, request_id, self.dispatch_request_value(request))
+            }
+            Err(err) => (
+                "invalid".to_owned(),
+                None,
+                Self::error_response(
+                    "invalid_request",
+                    format!("failed to parse request JSON: {}", err),
+                ),
+            ),
+        };
+
+        self.metrics.count_request(
+            &op,
+            response
+                .get("code")
+                .and_then(Value::as_str)
+                .filter(|_| response.get("ok") != Some(&Value::Bool(true))),
+        );
+        self.respond_to_cli(&source, Some(Self::with_request_id(response, request_id)));
+    }
+
+    pub(crate) fn dispatch_request_value(&mut self, request: Value) -> Value {
+        if let Some(unsupported) = Self::find_unsupported_op(&request) {
+            return unsupported;
+        }
+        match serde_json::from_value::<ButlerRequest>(request) {
+            Ok(request) => self.execute_request(request),
+            Err(err) => Self::error_response(
+                "invalid_request",
+                format!("failed to parse request JSON: {}", err),
+            ),
+        }
+    }
+
+    /// Rejects unknown ops (including inside a batch) with a structured error
+    /// before serde reports them as a generic parse failure.
+    fn find_unsupported_op(request: &Value) -> Option<Value> {
+        let unsupported = |op: &str, step: Option<usize>| {
+            json!({
+                "ok": false,
+                "code": "unsupported_op",
+                "error": format!("unsupported op {:?}", op),
+                "op": op,
+                "step": step,
+                "supported_ops": SUPPORTED_OPS,
+            })
+        };
+        let op = request.get("op")?.as_str()?;
+        if !SUPPORTED_OPS.contains(&op) {
+            return Some(unsupported(op, None));
+        }
+        if op == "batch" {
+            let steps = request.get("requests")?.as_array()?;
+            for (index, step) in steps.iter().enumerate() {
+                if let Some(step_op) = step.get("op").and_then(Value::as_str) {
+                    if !SUPPORTED_OPS.contains(&step_op) {
+                        return Some(unsupported(step_op, Some(index)));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn with_request_id(mut response: Value, request_id: Option<Value>) -> Value {
+        if let (Some(request_id), Some(envelope)) = (request_id, response.as_object_mut()) {
+            envelope.insert("id".to_owned(), request_id);
+        }
+        response
+    }
+
+    fn permission_status(&self) -> &'static str {
+        if self.permission_denied {
+            "denied"
+        } else if self.permission_result_seen {
+            "granted"
+        } else if self.ready {
+            "inferred"
+        } else {
+            "pending"
+        }
+    }
+
+    fn hello_response(&self) -> Value {
+        let granted = if self.ready {
+            REQUESTED_PERMISSIONS
+                .iter()
+                .map(|permission| format!("{:?}", permission))
+                .collect::<Vec<_>>()
+        } else {
+            Vec::new()
+        };
+        Self::serialized_response(&HelloResult {
+            protocol_version: PROTOCOL_VERSION,
+            crate_version: env!("CARGO_PKG_VERSION").to_owned(),
+            supported_ops: SUPPORTED_OPS.iter().map(|op| (*op).to_owned()).collect(),
+            permission_status: self.permission_status().to_owned(),
+            granted_permissions: granted,
+        })
+    }
+
+    /// Checks a request's preconditions against the cached snapshot without
+    /// performing any side effect.
+    fn validate_request(&self, request: &ButlerRequest) -> Result<(), Value> {
+        match request {
+            ButlerRequest::Hello
+            | ButlerRequest::GetSchema
+            | ButlerRequest::StartRecording { .. }
+            | ButlerRequest::StopRecording
+            | ButlerRequest::GetRecording { .. }
+            | ButlerRequest::Ping
+            | ButlerRequest::GetState
+            | ButlerRequest::GetMetrics
+            | ButlerRequest::GetTrace { .. }
+            | ButlerRequest::ClearTrace
+            | ButlerRequest::RegisterUiPane { .. }
+            | ButlerRequest::UiHeartbeat { .. }
+            | ButlerRequest::ListRules
+            | ButlerRequest::AddRule { .. }
+            | ButlerRequest::RemoveRule { .. }
+            | ButlerRequest::GetActivity
+            | ButlerRequest::GetFocusHistory
+            | ButlerRequest::GetFsChanges { .. } => Ok(()),
+            ButlerRequest::ExportTrace { path } => {
+                let is_file = !path.ends_with('/') && path != "/data" && path != "/tmp";
+                if is_file && trace::in_plugin_dirs(path) {
+                    Ok(())
+                } else {
+                    Err(Self::error_response(
+                        "invalid_path",
+                        "export_trace writes only to a file under /data or /tmp",
+                    ))
+                }
+            }
+            ButlerRequest::FocusBack { steps } => {
+                self.ensure_ready()?;
+                if *steps == Some(0) {
+                    return Err(Self::error_response(
+                        "invalid_request",
+                        "steps must be at least 1",
+                    ));
+                }
+                if self.focus_back_target(steps.unwrap_or(1)).is_none() {
+                    return Err(Self::error_response(
+                        "no_focus_history",
+                        format!(
+                            "the focus history has no pane {} step(s) back",
+                            steps.unwrap_or(1)
+                        ),
+                    ));
+                }
+                Ok(())
+            }
+            ButlerRequest::GetTabNameSuggestions => {
+                self.ensure_ready()?;
+                if self.auto_name_tabs == AutoNameTabs::Off {
+                    Err(Self::error_response(
+                        "auto_name_tabs_off",
+                        "set auto_name_tabs to suggest or apply in the plugin config",
+                    ))
+                } else {
+                    Ok(())
+                }
+            }
+            ButlerRequest::GetPaneInfo { pane_id } => {
+                self.ensure_ready()?;
+                self.resolve_single_pane(pane_id, true).map(|_| ())
+            }
+            // A held pane can still be renamed or tagged, e.g. by a `pane_exited` rule.
+            ButlerRequest::RenamePane { pane_id, .. } => {
+                self.ensure_ready()?;
+                self.resolve_pane_target(pane_id, true).map(|_| ())
+            }
+            ButlerRequest::TagPane { pane_id, tags } => {
+                self.ensure_ready()?;
+                if tags.is_empty() {
+                    return Err(Self::error_response(
+                        "invalid_tag",
+                        "tags must not be empty",
+                    ));
+                }
+                Self::validate_tags(tags)?;
+                self.resolve_pane_target(pane_id, true).map(|_| ())
+            }
+            ButlerRequest::UntagPane { pane_id, tags } => {
+                self.ensure_ready()?;
+                Self::validate_tags(tags.as_deref().unwrap_or_default())?;
+                self.resolve_pane_target(pane_id, true).map(|_| ())
+            }
+            ButlerRequest::ListTags => Ok(()),
+            ButlerRequest::OpenFile {
+                path,
+                line,
+                floating,
+                in_place_of_pane_id,
+            } => {
+                self.ensure_ready()?;
+                if path.trim().is_empty() {
+                    return Err(Self::error_response(
+                        "invalid_request",
+                        "path must not be empty",
+                    ));
+                }
+                if *line == Some(0) {
+                    return Err(Self::error_response("invalid_request", "line is 1-based"));
+                }
+                if let Some(target) = in_place_of_pane_id {
+                    if *floating {
+                        return Err(Self::error_response(
+                            "invalid_request",
+                            "floating and in_place_of_pane_id are exclusive",
+                        ));
+                    }
+                    self.resolve_single_pane(target, false)?;
+                }
+                Ok(())
+            }
+            ButlerRequest::ToggleScratch { name, .. } => {
+                self.ensure_ready()?;
+                if self.panes.is_none() {
+                    return Err(Self::not_ready_response());
+                }
+                if !self.scratch_commands.contains_key(name) {
+                    return Err(Self::error_response(
+                        "scratch_not_found",
+                        format!("no scratch.{}.command in the plugin config", name),
+                    ));
+                }
+                Ok(())
+            }
+            ButlerRequest::HidePanes { selector }
+            | ButlerRequest::ShowPanes { selector, .. }
+            | ButlerRequest::FloatPanes { selector }
+            | ButlerRequest::ClosePanes { selector, .. } => {
+                self.ensure_ready()?;
+                Self::parse_selector(selector).map(|_| ())
+            }
+            ButlerRequest::RenameTab { position, .. } => {
+                self.ensure_tab_position_available(*position)
+            }
+            ButlerRequest::HidePane { pane_id } | ButlerRequest::ShowPane { pane_id, .. } => {
+                self.ensure_ready()?;
+                self.resolve_pane_target(pane_id, false).map(|_| ())
+            }
+            ButlerRequest::RegisterUi { pane_id, .. } => {
+                self.ensure_ready()?;
+                self.resolve_pane_target(&PaneTarget::Id(*pane_id), false)
+                    .map(|_| ())
+            }
+            ButlerRequest::Batch { requests, .. } => {
+                if requests
+                    .iter()
+                    .any(|step| matches!(step, ButlerRequest::Batch { .. }))
+                {
+                    Err(Self::error_response(
+                        "invalid_request",
+                        "batch requests cannot be nested",
+                    ))
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    fn execute_batch(
+        &mut self,
+        requests: Vec<ButlerRequest>,
+        stop_on_error: bool,
+        atomic: bool,
+    ) -> Value {
+        self.push_trace(TraceCategory::Request, "batch")
+            .field("steps", requests.len())
+            .field("stop_on_error", stop_on_error)
+            .field("atomic", atomic);
+        if atomic {
+            // Every step is checked against the same cached snapshot before the
+            // first side effect, so a bad reference rejects the whole batch.
+            let failures = requests
+                .iter()
+                .enumerate()
+                .filter_map(|(index, step)| {
+                    self.validate_request(step)
+                        .err()
+                        .map(|err| json!({ "index": index, "response": err }))
+                })
+                .collect::<Vec<_>>();
+            if !failures.is_empty() {
+                self.push_trace_at(
+                    TraceLevel::Warn,
+                    TraceCategory::Request,
+                    "batch validation_failed",
+                )
+                .field("count", failures.len());
+                return Self::validation_failed_response(failures);
+            }
+        }
+
+        let total = requests.len();
+        let mut steps = Vec::with_capacity(total);
+        let mut stopped_early = false;
+        for step in requests {
+            let response = self.execute_request(step);
+            let failed = response.get("ok") != Some(&Value::Bool(true));
+            steps.push(response);
+            if failed && stop_on_error {
+                stopped_early = steps.len() < total;
+                break;
+            }
+        }
+        Self::serialized_response(&BatchResult {
+            completed: steps.len(),
+            steps,
+            stopped_early,
+        })
+    }
+
+    pub(crate) fn execute_request(&mut self, request: ButlerRequest) -> Value {
+        if let Err(err) = self.validate_request(&request) {
+            return err;
+        }
+        match request {
+            ButlerRequest::Hello => self.hello_response(),
+            ButlerRequest::GetSchema => Self::ok_response(protocol::protocol_schema()),
+            ButlerRequest::StartRecording { limit } => {
+                self.start_recording(limit);
+                Self::ack_response()
+            }
+            ButlerRequest::StopRecording => {
+                let response = self.recording_response(None);
+                self.recorder = None;
+                self.push_trace(TraceCategory::Request, "recording stopped");
+                response
+            }
+            ButlerRequest::GetRecording { since_seq } => self.recording_response(since_seq),
+            ButlerRequest::Ping => Self::ack_response(),
+            ButlerRequest::GetState => {
+                if let Err(not_ready) = self.ensure_ready() {
+                    return not_ready;
+                }
+                let Some(state) = self.workspace_state_snapshot() else {
+                    self.host.request_plugin_state_snapshot();
+                    return Self::error_response(
+                        "not_ready",
+                        "workspace cache is not ready yet (waiting for PaneUpdate)",
+                    );
+                };
+                Self::serialized_response(&state)
+            }
+            ButlerRequest::GetMetrics => {
+                let now_ms = self.host.now_epoch_millis();
+                Self::serialized_response(&self.metrics.snapshot(now_ms))
+            }
+            ButlerRequest::GetTrace {
+                limit,
+                level,
+                category,
+                since_seq,
+                format,
+            } => {
+                let filter = TraceFilter {
+                    limit,
+                    level,
+                    category,
+                    since_seq,
+                };
+                let entries = self.trace.snapshot(&filter, format.unwrap_or_default());
+                Self::serialized_response(&TraceResult { entries })
+            }
+            ButlerRequest::ExportTrace { path } => self.export_trace(&path),
+            ButlerRequest::ClearTrace => {
+                self.trace.clear();
+                Self::ack_response()
+            }
+            ButlerRequest::RenameTab { position, name } => {
+                self.push_trace(TraceCategory::Request, "rename_tab")
+                    .field("position", position)
+                    .field("name", &name);
+                // Zellij's rename_tab API takes a 1-based tab index, but butler
+                // state reports 0-based positions. Convert accordingly.
+                self.host.rename_tab((position + 1) as u32, &name);
+                Self::ack_response()
+            }
+            ButlerRequest::RenamePane { pane_id, name } => {
+                for pane_id in self.resolve_pane_target(&pane_id, true).unwrap_or_default() {
+                    self.push_trace(TraceCategory::Request, "rename_pane")
+                        .field("pane_id", pane_id)
+                        .field("name", &name);
+                    self.host.rename_pane(PaneId::Terminal(pane_id), &name);
+                }
+                Self::ack_response()
+            }
+            ButlerRequest::HidePane { pane_id } => {
+                for pane_id in self
+                    .resolve_pane_target(&pane_id, false)
+                    .unwrap_or_default()
+                {
+                    self.push_trace(TraceCategory::Request, "hide_pane")
+                        .field("pane_id", pane_id);
+                    self.host.hide_pane(PaneId::Terminal(pane_id));
+                }
+                Self::ack_response()
+            }
+            ButlerRequest::ShowPane {
+                pane_id,
+                should_float_if_hidden,
+                should_focus_pane,
+            } => {
+                let (float_if_hidden, focus_pane) =
+                    Self::show_pane_options(should_float_if_hidden, should_focus_pane);
+                for pane_id in self
+                    .resolve_pane_target(&pane_id, false)
+                    .unwrap_or_default()
+                {
+                    self.push_trace(TraceCategory::Request, "show_pane")
+                        .field("pane_id", pane_id)
+                        .field("float_if_hidden", float_if_hidden)
+                        .field("focus", focus_pane);
+                    self.host
+                        .show_pane(PaneId::Terminal(pane_id), float_if_hidden, focus_pane);
+                }
+                Self::ack_response()
+            }
+            ButlerRequest::Batch {
+                requests,
+                stop_on_error,
+                atomic,
+            } => self.execute_batch(requests, stop_on_error, atomic),
+            ButlerRequest::RegisterUiPane { pane_id, token } => {
+                self.register_ui_pane(pane_id, &token)
+            }
+            ButlerRequest::RegisterUi {
+                pane_id,
+                client_id,
+                pid,
+            } => {
+                self.push_trace(TraceCategory::Request, "register_ui")
+                    .field("pane_id", pane_id)
+                    .field("client_id", &client_id)
+                    .field("pid", pid);
+                let last_heartbeat_ms = self.host.now_epoch_millis();
+                self.ui_clients.insert(
+                    pane_id,
+                    UiClient {
+                        client_id,
+                        pid,
+                        last_heartbeat_ms,
+                        heartbeat_activity_ms: self.activity_ms,
+                        stale_since_activity_ms: None,
+                    },
+                );
+                Self::ack_response()
+            }
+            ButlerRequest::UiHeartbeat { pane_id } => {
+                let now_ms = self.host.now_epoch_millis();
+                match self.ui_clients.get_mut(&pane_id) {
+                    Some(ui_client) => {
+                        ui_client.last_heartbeat_ms = now_ms;
+                        ui_client.heartbeat_activity_ms = self.activity_ms;
+                        ui_client.stale_since_activity_ms = None;
+                        Self::ack_response()
+                    }
+                    None => Self::error_response(
+                        "ui_not_registered",
+                        format!("no UI is registered for pane {}", pane_id),
+                    ),
+                }
+            }
+            ButlerRequest::GetActivity => {
+                if let Err(not_ready) = self.ensure_ready() {
+                    return not_ready;
+                }
+                match self.activity_result() {
+                    Some(result) => Self::serialized_response(&result),
+                    None => Self::error_response(
+                        "not_ready",
+                        "workspace cache is not ready yet (waiting for PaneUpdate)",
+                    ),
+                }
+            }
+            ButlerRequest::FocusBack { steps } => {
+                let Some((pane_id, tab_position)) = self.focus_back_target(steps.unwrap_or(1))
+                else {
+                    return Self::error_response("no_focus_history", "the focus history is empty");
+                };
+                self.push_trace(TraceCategory::Request, "focus_back")
+                    .field("pane_id", pane_id)
+                    .field("tab_position", tab_position);
+                self.host.focus_terminal_pane(pane_id, true, false);
+                Self::serialized_response(&FocusBackResult {
+                    pane_id,
+                    tab_position,
+                })
+            }
+            ButlerRequest::GetFocusHistory => Self::serialized_response(&FocusHistoryResult {
+                panes: self.focus_history.pane_entries(),
+                tabs: self.focus_history.tab_entries(),
+            }),
+            ButlerRequest::ListRules => self.list_rules_response(),
+            ButlerRequest::AddRule { rule } => self.add_rule(rule),
+            ButlerRequest::RemoveRule { id } => self.remove_rule(&id),
+            ButlerRequest::GetTabNameSuggestions => {
+                Self::serialized_response(&TabNameSuggestionsResult {
+                    mode: self.auto_name_tabs.as_str().to_owned(),
+                    suggestions: self.tab_name_suggestions(),
+                })
+            }
+            ButlerRequest::TagPane { pane_id, tags } => {
+                for pane_id in self.resolve_pane_target(&pane_id, true).unwrap_or_default() {
+                    self.push_trace(TraceCategory::Request, "tag_pane")
+                        .field("pane_id", pane_id)
+                        .field("tags", tags.join(","));
+                    self.pane_tags
+                        .entry(pane_id)
+                        .or_default()
+                        .extend(tags.iter().cloned());
+                }
+                Self::ack_response()
+            }
+            ButlerRequest::UntagPane { pane_id, tags } => {
+                for pane_id in self.resolve_pane_target(&pane_id, true).unwrap_or_default() {
+                    self.push_trace(TraceCategory::Request, "untag_pane")
+                        .field("pane_id", pane_id)
+                        .field(
+                            "tags",
+                            tags.as_deref()
+                                .map_or("*".to_owned(), |tags| tags.join(",")),
+                        );
+                    let now_empty = match (&tags, self.pane_tags.get_mut(&pane_id)) {
+                        (Some(tags), Some(pane_tags)) => {
+                            for tag in tags {
+                                pane_tags.remove(tag);
+                            }
+                            pane_tags.is_empty()
+                        }
+                        _ => true,
+                    };
+                    if now_empty {
+                        self.pane_tags.remove(&pane_id);
+                    }
+                }
+                Self::ack_response()
+            }
+            ButlerRequest::ListTags => Self::serialized_response(&self.list_tags()),
+            ButlerRequest::HidePanes { selector } => {
+                let pane_ids = self.bulk_targets(&selector, |pane| !pane.is_suppressed);
+                self.push_trace(TraceCategory::Request, "hide_panes")
+                    .field("selector", &selector)
+                    .field("pane_ids", Self::join_ids(&pane_ids));
+                for pane_id in &pane_ids {
+                    self.host.hide_pane(PaneId::Terminal(*pane_id));
+                }
+                Self::serialized_response(&PanesResult {
+                    pane_ids,
+                    dry_run: false,
+                })
+            }
+            ButlerRequest::FloatPanes { selector } => {
+                let pane_ids =
+                    self.bulk_targets(&selector, |pane| !pane.is_suppressed && !pane.is_floating);
+                self.push_trace(TraceCategory::Request, "float_panes")
+                    .field("selector", &selector)
+                    .field("pane_ids", Self::join_ids(&pane_ids));
+                for pane_id in &pane_ids {
+                    self.host
+                        .toggle_pane_embed_or_eject(PaneId::Terminal(*pane_id));
+                }
+                Self::serialized_response(&PanesResult {
+                    pane_ids,
+                    dry_run: false,
+                })
+            }
+            ButlerRequest::ShowPanes {
+                selector,
+                should_float_if_hidden,
+                should_focus_pane,
+            } => {
+                let pane_ids = self.bulk_targets(&selector, |pane| pane.is_suppressed);
+                let (float_if_hidden, focus_pane) = (
+                    should_float_if_hidden.unwrap_or(true),
+                    should_focus_pane.unwrap_or(false),
+                );
+                self.push_trace(TraceCategory::Request, "show_panes")
+                    .field("selector", &selector)
+                    .field("pane_ids", Self::join_ids(&pane_ids));
+                for pane_id in &pane_ids {
+                    self.host
+                        .show_pane(PaneId::Terminal(*pane_id), float_if_hidden, focus_pane);
+                }
+                Self::serialized_response(&PanesResult {
+                    pane_ids,
+                    dry_run: false,
+                })
+            }
+            ButlerRequest::OpenFile {
+                path,
+                line,
+                floating,
+                in_place_of_pane_id,
+            } => {
+                let in_place_of_pane_id = match in_place_of_pane_id
+                    .map(|target| self.resolve_single_pane(&target, false))
+                    .transpose()
+                {
+                    Ok(pane_id) => pane_id,
+                    Err(response) => return response,
+                };
+                // The editor starts where the pane it is opened from (or replaces) is.
+                let cwd = in_place_of_pane_id
+                    .or_else(|| {
+                        self.active_tab_index()
+                            .and_then(|tab| self.origin_pane(None, tab))
+                    })
+                    .and_then(|pane_id| self.pane_cwds.get(&pane_id).cloned());
+                let file = FileToOpen {
+                    path: PathBuf::from(&path),
+                    line_number: line,
+                    cwd,
+                };
+                let opened =
+                    self.host
+                        .open_file(file, floating, in_place_of_pane_id.map(PaneId::Terminal));
+                match opened {
+                    Ok(PaneId::Terminal(pane_id)) => {
+                        let entry = self
+                            .push_trace(TraceCategory::Request, "open_file")
+                            .field("path", &path)
+                            .field("pane_id", pane_id);
+                        if let Some(line) = line {
+                            entry.field("line", line);
+                        }
+                        Self::serialized_response(&OpenFileResult { pane_id })
+                    }
+                    Ok(pane_id) => Self::error_response(
+                        "open_file_failed",
+                        format!("unexpected pane kind {:?}", pane_id),
+                    ),
+                    Err(error) => Self::error_response("open_file_failed", error),
+                }
+            }
+            ButlerRequest::GetFsChanges { since } => {
+                let now_ms = self.host.now_epoch_millis();
+                let cut = self
+                    .fs_watch
+                    .as_mut()
+                    .and_then(|fs_watch| fs_watch.cut_if_quiet(now_ms));
+                self.trace_fs_summary(cut);
+                let result = match self.fs_watch.as_ref() {
+                    Some(fs_watch) => FsChangesResult {
+                        watching: true,
+                        include: fs_watch.include(),
+                        exclude: fs_watch.exclude(),
+                        summaries: fs_watch.summaries_since(since),
+                    },
+                    None => FsChangesResult {
+                        watching: false,
+                        include: Vec::new(),
+                        exclude: Vec::new(),
+                        summaries: Vec::new(),
+                    },
+                };
+                Self::serialized_response(&result)
+            }
+            ButlerRequest::ToggleScratch { name, client_id } => {
+                match self.toggle_scratch(&name, client_id) {
+                    Ok((pane_id, action)) => Self::serialized_response(&ScratchResult {
+                        name,
+                        pane_id,
+                        action: action.to_owned(),
+                    }),
+                    Err(error) => Self::error_response("launch_failed", error),
+                }
+            }
+            ButlerRequest::ClosePanes { selector, dry_run } => {
+                let pane_ids = self.bulk_targets(&selector, |_| true);
+                self.push_trace(TraceCategory::Request, "close_panes")
+                    .field("selector", &selector)
+                    .field("pane_ids", Self::join_ids(&pane_ids))
+                    .field("dry_run", dry_run);
+                if !dry_run {
+                    for pane_id in &pane_ids {
+                        self.host.close_terminal_pane(*pane_id);
+                    }
+                }
+                Self::serialized_response(&PanesResult { pane_ids, dry_run })
+            }
+            ButlerRequest::GetPaneInfo { pane_id } => {
+                let pane_id = match self.resolve_pane_target(&pane_id, true).as_deref() {
+                    Ok([pane_id]) => *pane_id,
+                    _ => return Self::error_response("pane_not_found", "no single pane matched"),
+                };
+                match self.pane_info(pane_id) {
+                    Some(info) => Self::serialized_response(&info),
+                    None => Self::error_response(
+                        "pane_not_found",
+                        format!("pane id {} was not found", pane_id),
+                    ),
+                }
+            }
+        }
+    }
+
+    /// Pane ids are never reused within a session, but registrations would otherwise
+    /// pile up with every relaunch. The tracked pane keeps its own grace period.
+    fn forget_closed_panes(&mut self) {
+        let closed = self
+            .registered_jelly_panes
+            .iter()
+            .chain(self.ui_clients.keys())
+            .copied()
+            .filter(|pane_id| {
+                self.jelly_pane_id != Some(*pane_id) && !self.terminal_pane_exists(*pane_id)
+            })
+            .collect::<Vec<_>>();
+        for pane_id in closed {
+            self.registered_jelly_panes.remove(&pane_id);
+            self.ui_clients.remove(&pane_id);
+        }
+        let closed_details = self
+            .pane_cwds
+            .keys()
+            .chain(self.pane_commands.keys())
+            .chain(self.pane_tags.keys())
+            .copied()
+            .filter(|pane_id| !self.terminal_pane_present(*pane_id))
+            .collect::<Vec<_>>();
+        for pane_id in closed_details {
+            self.pane_cwds.remove(&pane_id);
+            self.pane_commands.remove(&pane_id);
+            self.pane_tags.remove(&pane_id);
+        }
+    }
+
+    /// Includes panes whose command has exited.
+    pub(crate) fn terminal_pane_present(&self, pane_id: u32) -> bool {
+        self.exited_terminal_pane(pane_id).is_some() || self.terminal_pane_exists(pane_id)
+    }
+
+    pub(crate) fn exited_terminal_pane(&self, pane_id: u32) -> Option<PaneInfo> {
+        self.panes.as_ref()?.panes.values().find_map(|panes| {
+            panes
+                .iter()
+                .find(|pane| pane.id == pane_id && !pane.is_plugin && pane.exited)
+                .cloned()
+        })
+    }
+
+    /// Every pane the butler currently considers its own Jelly pane.
+    pub(crate) fn owned_jelly_pane_ids(&self) -> BTreeSet<u32> {
+        self.jelly_pane_id
+            .into_iter()
+            .chain(self.client_panes.values().map(|owned| owned.pane_id))
+            .chain(self.tab_panes.values().map(|owned| owned.pane_id))
+            .collect()
+    }
+
+    /// Drops every reference to a pane we closed, including from the cached manifest,
+    /// so the rest of this toggle doesn't act on it before the next PaneUpdate.
+    pub(crate) fn forget_jelly_pane(&mut self, pane_id: u32) {
+        if self.jelly_pane_id == Some(pane_id) {
+            self.jelly_pane_id = None;
+            self.tracked_pane_missing_since_ms = None;
+        }
+        self.client_panes
+            .retain(|_, client_pane| client_pane.pane_id != pane_id);
+        self.tab_panes
+            .retain(|_, tab_pane| tab_pane.pane_id != pane_id);
+        self.registered_jelly_panes.remove(&pane_id);
+        self.ui_clients.remove(&pane_id);
+        self.deferred_restarts.remove(&pane_id);
+        self.ui_tokens.retain(|_, launched| *launched != pane_id);
+        if let Some(manifest) = self.panes.as_mut() {
+            for panes in manifest.panes.values_mut() {
+                panes.retain(|pane| pane.is_plugin || pane.id != pane_id);
+            }
+        }
+    }
+
+    fn pane_state(&self, tab_index: usize, pane: &PaneInfo) -> ButlerPaneState {
+        let (cwd, foreground_command, activity) = if pane.is_plugin {
+            (None, None, None)
+        } else {
+            (
+                self.pane_cwds
+                    .get(&pane.id)
+                    .map(|cwd| cwd.display().to_string()),
+                self.pane_commands
+                    .get(&pane.id)
+                    .or(pane.terminal_command.as_ref())
+                    .cloned(),
+                self.activity.pane(pane.id, self.host.now_epoch_millis()),
+            )
+        };
+        ButlerPaneState {
+            id: pane.id,
+            tab_index,
+            title: pane.title.clone(),
+            terminal_command: pane.terminal_command.clone(),
+            is_plugin: pane.is_plugin,
+            is_focused: pane.is_focused,
+            is_floating: pane.is_floating,
+            is_suppressed: pane.is_suppressed,
+            exited: pane.exited,
+            cwd,
+            foreground_command,
+            tags: self
+                .pane_tags
+                .get(&pane.id)
+                .map(|tags| tags.iter().cloned().collect())
+                .unwrap_or_default(),
+            activity,
+        }
+    }
+
+    /// Folds the latest tab and pane snapshots into the activity records.
+    fn observe_activity(&mut self) {
+        let now_ms = self.host.now_epoch_millis();
+        if let (Some(tabs), Some(panes)) = (self.tabs.as_ref(), self.panes.as_ref()) {
+            self.activity.observe(now_ms, tabs, panes);
+        }
+    }
+
+    /// Moves the active tab and its focused working pane to the front of the history.
+    fn record_focus(&mut self) {
+        let now_ms = self.host.now_epoch_millis();
+        let active_tab = self.active_tab_index();
+        let focused_pane = active_tab.and_then(|tab| self.origin_pane(None, tab));
+        self.focus_history.observe(now_ms, active_tab, focused_pane);
+        let tab_count = self.tabs.as_ref().map_or(usize::MAX, Vec::len);
+        let panes = self.panes.as_ref().map(|manifest| {
+            manifest
+                .panes
+                .iter()
+                .flat_map(|(tab_index, panes)| {
+                    panes
+                        .iter()
+                        .filter(|pane| !pane.is_plugin && !pane.exited)
+                        .map(move |pane| (pane.id, *tab_index))
+                })
+                .collect::<BTreeMap<_, _>>()
+        });
+        if let Some(panes) = panes {
+            self.focus_history
+                .retain(|pane_id| panes.get(&pane_id).copied(), tab_count);
+        }
+    }
+
+    /// The terminal panes `target` names: one id, or every pane a selector matches.
+    /// Exited panes count only with `include_exited`.
+    fn resolve_pane_target(
+        &self,
+        target: &PaneTarget,
+        include_exited: bool,
+    ) -> Result<Vec<u32>, Value> {
+        let selector = match target {
+            PaneTarget::Id(pane_id) => {
+                let found = if include_exited {
+                    self.terminal_pane_present(*pane_id)
+                } else {
+                    self.terminal_pane_exists(*pane_id)
+                };
+                return if found {
+                    Ok(vec![*pane_id])
+                } else {
+                    Err(Self::error_response(
+                        "pane_not_found",
+                        format!("pane id {} was not found", pane_id),
+                    ))
+                };
+            }
+            PaneTarget::Selector(selector) => selector,
+        };
+        let pane_ids = self
+            .select_panes(&Self::parse_selector(selector)?)
+            .into_iter()
+            .filter(|pane| include_exited || !pane.exited)
+            .map(|pane| pane.id)
+            .collect::<Vec<_>>();
+        if pane_ids.is_empty() {
+            return Err(Self::error_response(
+                "pane_not_found",
+                format!("no pane matches selector {:?}", selector),
+            ));
+        }
+        Ok(pane_ids)
+    }
+
+    /// Like [`Self::resolve_pane_target`], for ops that act on exactly one pane: a
+    /// selector matching several fails with `ambiguous_selector`.
+    fn resolve_single_pane(&self, target: &PaneTarget, include_exited: bool) -> Result<u32, Value> {
+        match self.resolve_pane_target(target, include_exited)?.as_slice() {
+            [pane_id] => Ok(*pane_id),
+            pane_ids => Err(Self::error_response(
+                "ambiguous_selector",
+                format!("selector matches {} panes", pane_ids.len()),
+            )),
+        }
+    }
+
+    fn parse_selector(selector: &str) -> Result<PaneSelector, Value> {
+        PaneSelector::parse(selector)
+            .map_err(|error| Self::error_response("invalid_selector", error))
+    }
+
+    /// Terminal panes `selector` matches, in tab order (the manifest map is unordered).
+    fn select_panes(&self, selector: &PaneSelector) -> Vec<PaneInfo> {
+        let Some(manifest) = self.panes.as_ref() else {
+            return Vec::new();
+        };
+        let mut tabs = manifest.panes.iter().collect::<Vec<_>>();
+        tabs.sort_by_key(|(tab_index, _)| **tab_index);
+        tabs.into_iter()
+            .flat_map(|(tab_index, panes)| panes.iter().map(move |pane| (*tab_index, pane)))
+            .filter(|(tab_index, pane)| {
+                !pane.is_plugin
+                    && selector.matches(&SelectorFacts {
+                        tab_index: *tab_index,
+                        pane,
+                        foreground_command: self.pane_commands.get(&pane.id).map(String::as_str),
+                        tags: self.pane_tags.get(&pane.id),
+                    })
+            })
+            .map(|(_, pane)| pane.clone())
+            .collect()
+    }
+
+    /// Matches for a bulk op that also pass `applies`, minus the Jelly panes.
+    fn bulk_targets(&self, selector: &str, applies: impl Fn(&PaneInfo) -> bool) -> Vec<u32> {
+        let Ok(selector) = Self::parse_selector(selector) else {
+            return Vec::new();
+        };
+        let owned_jelly_panes = self.owned_jelly_pane_ids();
+        self.select_panes(&selector)
+            .into_iter()
+            .filter(|pane| {
+                applies(pane) && !self.is_jelly_pane(pane) && !owned_jelly_panes.contains(&pane.id)
+            })
+            .map(|pane| pane.id)
+            .collect()
+    }
+
+    fn join_ids(pane_ids: &[u32]) -> String {
+        pane_ids
+            .iter()
+            .map(u32::to_string)
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    fn validate_tags(tags: &[String]) -> Result<(), Value> {
+        tags.iter()
+            .try_for_each(|tag| tags::validate_tag(tag))
+            .map_err(|error| Self::error_response("invalid_tag", error))
+    }
+
+    fn list_tags(&self) -> ListTagsResult {
+        let mut by_tag = BTreeMap::<&str, Vec<u32>>::new();
+        for (pane_id, tags) in &self.pane_tags {
+            for tag in tags {
+                by_tag.entry(tag).or_default().push(*pane_id);
+            }
+        }
+        ListTagsResult {
+            tags: by_tag
+                .into_iter()
+                .map(|(tag, pane_ids)| TagSummary {
+                    tag: tag.to_owned(),
+                    pane_ids,
+                })
+                .collect(),
+        }
+    }
+
+    fn focus_back_target(&self, steps: usize) -> Option<(u32, usize)> {
+        let current = self
+            .active_tab_index()
+            .and_then(|tab| self.origin_pane(None, tab));
+        self.focus_history.pane_back(steps, current)
+    }
+
+    fn activity_result(&self) -> Option<ActivityResult> {
+        let now_ms = self.host.now_epoch_millis();
+        let mut tabs = self
+            .tabs
+            .as_ref()?
+            .iter()
+            .filter_map(|tab| {
+                Some(TabActivity {
+                    position: tab.position,
+                    name: tab.name.clone(),
+                    activity: self.activity.tab(tab.position, now_ms)?,
+                })
+            })
+            .collect::<Vec<_>>();
+        let owned_jelly_panes = self.owned_jelly_pane_ids();
+        let mut panes = self
+            .panes
+            .as_ref()?
+            .panes
+            .iter()
+            .flat_map(|(tab_index, panes)| panes.iter().map(move |pane| (*tab_index, pane)))
+            .filter(|(_, pane)| !pane.is_plugin)
+            .filter_map(|(tab_index, pane)| {
+                Some(PaneActivity {
+                    id: pane.id,
+                    tab_index,
+                    title: pane.title.clone(),
+                    is_jelly: self.is_jelly_pane(pane) || owned_jelly_panes.contains(&pane.id),
+                    activity: self.activity.pane(pane.id, now_ms)?,
+                })
+            })
+            .collect::<Vec<_>>();
+        tabs.sort_by_key(|tab| std::cmp::Reverse(tab.activity.idle_ms));
+        panes.sort_by_key(|pane| std::cmp::Reverse(pane.activity.idle_ms));
+        Some(ActivityResult {
+            now_epoch_ms: u64::try_from(now_ms).unwrap_or(u64::MAX),
+            tabs,
+            panes,
+        })
+    }
+
+    fn pane_info(&self, pane_id: u32) -> Option<ButlerPaneInfo> {
+        let (tab_index, pane) =
+            self.panes
+                .as_ref()?
+                .panes
+                .iter()
+                .find_map(|(tab_index, panes)| {
+                    panes
+                        .iter()
+                        .find(|pane| pane.id == pane_id && !pane.is_plugin)
+                        .map(|pane| (*tab_index, pane))
+                })?;
+        Some(ButlerPaneInfo {
+            pane: self.pane_state(tab_index, pane),
+            geometry: PaneGeometry {
+                x: pane.pane_x,
+                y: pane.pane_y,
+                columns: pane.pane_columns,
+                rows: pane.pane_rows,
+            },
+            is_fullscreen: pane.is_fullscreen,
+            is_held: pane.is_held,
+            exit_status: pane.exit_status,
+            is_jelly: self.is_jelly_pane(pane) || self.owned_jelly_pane_ids().contains(&pane_id),
+            registered_ui: self.registered_jelly_panes.contains(&pane_id),
+        })
+    }
+
+    fn workspace_state_snapshot(&self) -> Option<ButlerWorkspaceState> {
+        let now_ms = self.host.now_epoch_millis();
+        let tabs = self
+            .tabs
+            .as_ref()
+            .map(|tabs| {
+                tabs.iter()
+                    .map(|tab| ButlerTabState {
+                        position: tab.position,
+                        name: tab.name.clone(),
+                        active: tab.active,
+                        selectable_tiled_panes_count: tab.selectable_tiled_panes_count,
+                        selectable_floating_panes_count: tab.selectable_floating_panes_count,
+                        activity: self.activity.tab(tab.position, now_ms),
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        let panes = self
+            .panes
+            .as_ref()?
+            .panes
+            .iter()
+            .flat_map(|(tab_index, pane_infos)| {
+                pane_infos
+                    .iter()
+                    .map(|pane| self.pane_state(*tab_index, pane))
+            })
+            .collect::<Vec<_>>();
+
+        let butler = ButlerRuntimeState {
+            ready: self.ready,
+            permission_result_seen: self.permission_result_seen,
+            permission_denied: self.permission_denied,
+            pending_toggle: self.pending_toggle,
+            pane_update_count: self.pane_update_count,
+            tab_update_count: self.tab_update_count,
+            trace_len: self.trace.len(),
+            trace_log_path: self.trace.log_path().map(str::to_owned),
+            jelly_pane_id: self.jelly_pane_id,
+            registered_jelly_panes: self.registered_jelly_panes.iter().copied().collect(),
+            on_exit: self.exit_policy.as_str().to_owned(),
+            deferred_restarts: self.deferred_restarts.keys().copied().collect(),
+            ui_clients: self
+                .ui_clients
+                .iter()
+                .map(|(pane_id, ui_client)| ButlerUiClient {
+                    pane_id: *pane_id,
+                    client_id: ui_client.client_id.clone(),
+                    pid: ui_client.pid,
+                    last_heartbeat_epoch_ms: u64::try_from(ui_client.last_heartbeat_ms)
+                        .unwrap_or(u64::MAX),
+                    alive: self.ui_client_is_alive(ui_client),
+                    stale: ui_client.stale_since_activity_ms.is_some(),
+                })
+                .collect(),
+            pane_policy: self.pane_policy.as_str().to_owned(),
+            client_panes: self
+                .client_panes
+                .iter()
+                .map(|(client_id, client_pane)| ButlerClientPane {
+                    client_id: *client_id,
+                    pane_id: client_pane.pane_id,
+                })
+                .collect(),
+            tab_panes: self
+                .tab_panes
+                .iter()
+                .map(|(tab_index, tab_pane)| ButlerTabPane {
+                    tab_index: *tab_index,
+                    pane_id: tab_pane.pane_id,
+                })
+                .collect(),
+            jelly_geometry: self.jelly_geometry,
+            state_path: self
+                .state_store
+                .as_ref()
+                .map(|store| store.path().to_owned()),
+            restored_jelly_pane: self.restored_jelly_pane,
+            lifetime: self.lifetime.clone(),
+            last_cli_toggle_pipe_id: self.last_cli_toggle_pipe_id.clone(),
+            launch_command: self.launch_command().to_owned(),
+            launch_cwd: self.launch_cwd.describe(),
+            launch_env: self.launch_env.keys().cloned().collect(),
+            auto_name_tabs: self.auto_name_tabs.as_str().to_owned(),
+            scratch_panes: self
+                .scratch_commands
+                .iter()
+                .map(|(name, command)| ButlerScratchPane {
+                    name: name.clone(),
+                    command: command.clone(),
+                    pane_id: self
+                        .scratch_panes
+                        .get(name)
+                        .map(|scratch_pane| scratch_pane.pane_id),
+                })
+                .collect(),
+        };
+
+        Some(ButlerWorkspaceState {
+            tabs,
+            panes,
+            butler,
+        })
+    }
+
+    /// Panes we launched or whose UI registered are Jelly by definition; anything else
+    /// falls back to the title/command heuristic.
+    pub(crate) fn is_jelly_pane(&self, pane: &PaneInfo) -> bool {
+        !pane.exited
+            && !pane.is_plugin
+            && (self.is_confirmed_jelly_pane(pane.id) || self.looks_like_jelly_pane(pane))
+    }
+
+    fn is_confirmed_jelly_pane(&self, pane_id: u32) -> bool {
+        self.registered_jelly_panes.contains(&pane_id)
+            || self.ui_tokens.values().any(|launched| *launched == pane_id)
+    }
+
+    pub(crate) fn looks_like_jelly_pane(&self, pane: &PaneInfo) -> bool {
+        pane.title == PANE_NAME
+            || pane
+                .terminal_command
+                .as_deref()
+                .is_some_and(|command| self.runs_launch_command(command))
+    }
+
+    /// Compares programs rather than substrings, so `vim jelly-j.md` is not Jelly.
+    fn runs_launch_command(&self, command: &str) -> bool {
+        let launch_command = self.launch_command();
+        command.contains(launch_command)
+            || command_program(command)
+                .is_some_and(|program| command_program(launch_command) == Some(program))
+    }
+
+    /// Closing is destructive: a heuristic match only qualifies when the title and the
+    /// program both say Jelly J. A pane with no command is a shell someone renamed.
+    pub(crate) fn may_close_jelly_pane(&self, pane: &PaneInfo) -> bool {
+        self.is_confirmed_jelly_pane(pane.id)
+            || (pane.title == PANE_NAME
+                && pane
+                    .terminal_command
+                    .as_deref()
+                    .is_some_and(|command| self.runs_launch_command(command)))
+    }
+
+    pub(crate) fn active_tab_index(&self) -> Option<usize> {
+        if let Some(tabs) = self.tabs.as_ref() {
+            if let Some(tab) = tabs.iter().find(|tab| tab.active) {
+                return Some(tab.position);
+            }
+        }
+
+        let manifest = self.panes.as_ref()?;
+        if let Some((tab_index, _)) = manifest.panes.iter().find(|(_, panes)| {
+            panes.iter().any(|pane| {
+                pane.is_focused && !pane.exited && !pane.is_plugin && !self.is_jelly_pane(pane)
+            })
+        }) {
+            return Some(*tab_index);
+        }
+        if let Some((tab_index, _)) = manifest.panes.iter().find(|(_, panes)| {
+            panes
+                .iter()
+                .any(|pane| pane.is_focused && !pane.exited && !pane.is_plugin)
+        }) {
+            return Some(*tab_index);
+        }
+
+        manifest.panes.keys().min().copied()
+    }
+
+    pub(crate) fn focusable_non_jelly_terminal_in_tab(
+        &self,
+        tab_index: usize,
+        except: u32,
+    ) -> Option<u32> {
+        self.panes
+            .as_ref()?
+            .panes
+            .get(&tab_index)?
+            .iter()
+            .find(|pane| {
+                pane.id != except && !pane.is_plugin && !pane.exited && !self.is_jelly_pane(pane)
+            })
+            .map(|pane| pane.id)
+    }
+
+    pub(crate) fn all_jelly_panes(&self) -> Vec<(usize, PaneInfo)> {
+        self.panes
+            .as_ref()
+            .map(|manifest| {
+                manifest
+                    .panes
+                    .iter()
+                    .flat_map(|(tab_index, panes)| {
+                        panes.iter().filter_map(|pane| {
+                            if self.is_jelly_pane(pane) {
+                                Some((*tab_index, pane.clone()))
+                            } else {
+                                None
+                            }
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn find_terminal_pane_by_id(&self, pane_id: u32) -> Option<(usize, PaneInfo)> {
+        self.panes
+            .as_ref()?
+            .panes
+            .iter()
+            .find_map(|(tab_index, panes)| {
+                panes
+                    .iter()
+                    .find(|pane| pane.id == pane_id && !pane.is_plugin && !pane.exited)
+                    .cloned()
+                    .map(|pane| (*tab_index, pane))
+            })
+    }
+
+    pub(crate) fn client_is_known(&self, client_id: ClientId) -> bool {
+        self.clients
+            .iter()
+            .any(|client| client.client_id == client_id)
+    }
+
+    /// The tab the given client is looking at: the tab of its focused pane, else the
+    /// tab listing it in `other_focused_clients`.
+    pub(crate) fn client_tab_index(&self, client_id: ClientId) -> Option<usize> {
+        let focused_pane = self
+            .clients
+            .iter()
+            .find(|client| client.client_id == client_id)
+            .map(|client| client.pane_id);
+        if let (Some(focused_pane), Some(manifest)) = (focused_pane, self.panes.as_ref()) {
+            let found = manifest.panes.iter().find_map(|(tab_index, panes)| {
+                panes
+                    .iter()
+                    .any(|pane| match focused_pane {
+                        PaneId::Terminal(id) => !pane.is_plugin && pane.id == id,
+                        PaneId::Plugin(id) => pane.is_plugin && pane.id == id,
+                    })
+                    .then_some(*tab_index)
+            });
+            if found.is_some() {
+                return found;
+            }
+        }
+        self.tabs.as_ref()?.iter().find_map(|tab| {
+            tab.other_focused_clients
+                .contains(&client_id)
+                .then_some(tab.position)
+        })
+    }
+}
+
+/// The program a shell command runs, ignoring `env`, `VAR=value` prefixes and
+/// directories.
+/// The `client_id` arg a CLI pipe may carry (`--args client_id=<id>`).
+fn pipe_client_id(pipe_message: &PipeMessage) -> Option<ClientId> {
+    pipe_message
+        .args
+        .get("client_id")
+        .and_then(|value| value.trim().parse::<ClientId>().ok())
+}
+
+pub(crate) fn command_program(command: &str) -> Option<&str> {
+    let program = command
+        .split_whitespace()
+        .find(|word| !word.contains('=') && *word != "env" && !word.ends_with("/env"))?;
+    Some(program.rsplit('/').next().unwrap_or(program))
+}
@@ -0,0 +1,319 @@
+//! Deterministic tab names for `auto_name_tabs`.
+//!
+//! The heartbeat used to ask an LLM to name every `Tab #N`. Most tabs are named after
+//! the project they sit in or the one program they run, which needs no model: each
+//! pane votes for a label (repo name, else directory name, else program) and the tab
+//! takes the most common one, preferring the focused pane's on a tie.
+
+use crate::host::ZellijHost;
+use crate::protocol::{TabNameSuggestion, TraceCategory};
+use crate::state::{command_program, State};
+use std::collections::BTreeSet;
+
+/// Directories whose children are usually repository checkouts (`~/src/<repo>`).
+pub(crate) const DEFAULT_REPO_ROOTS: &[&str] = &[
+    "src",
+    "code",
+    "projects",
+    "repos",
+    "git",
+    "dev",
+    "work",
+    "workspace",
+];
+
+/// Shells say nothing about what a pane is for.
+const SHELLS: &[&str] = &["sh", "bash", "zsh", "fish", "dash", "nu", "elvish", "xonsh"];
+
+/// What the engine knows about one pane of a tab.
+pub(crate) struct PaneFacts<'a> {
+    pub(crate) is_focused: bool,
+    pub(crate) cwd: Option<&'a str>,
+    pub(crate) foreground_command: Option<&'a str>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct TabLabel {
+    pub(crate) name: String,
+    /// `repo`, `cwd` or `command`.
+    pub(crate) reason: &'static str,
+}
+
+/// Zellij's name for a tab nobody has named: `Tab #3`.
+pub(crate) fn is_default_tab_name(name: &str) -> bool {
+    name.strip_prefix("Tab #")
+        .is_some_and(|number| !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()))
+}
+
+pub(crate) fn suggest_tab_label(panes: &[PaneFacts], repo_roots: &[String]) -> Option<TabLabel> {
+    let labels = panes
+        .iter()
+        .map(|pane| (pane.is_focused, pane_label(pane, repo_roots)))
+        .filter_map(|(is_focused, label)| label.map(|label| (is_focused, label)))
+        .collect::<Vec<_>>();
+    let votes = |label: &TabLabel| {
+        labels
+            .iter()
+            .filter(|(_, other)| other.name == label.name)
+            .count()
+    };
+    let mut best: Option<(usize, bool, &TabLabel)> = None;
+    for (is_focused, label) in &labels {
+        let candidate = (votes(label), *is_focused, label);
+        if best.is_none_or(|(count, focused, _)| (candidate.0, candidate.1) > (count, focused)) {
+            best = Some(candidate);
+        }
+    }
+    best.map(|(_, _, label)| label.clone())
+}
+
+fn pane_label(pane: &PaneFacts, repo_roots: &[String]) -> Option<TabLabel> {
+    if let Some(cwd) = pane.cwd {
+        if let Some(repo) = repo_name(cwd, repo_roots) {
+            return Some(TabLabel {
+                name: repo.to_owned(),
+                reason: "repo",
+            });
+        }
+        if let Some(dir) = dir_label(cwd) {
+            return Some(TabLabel {
+                name: dir,
+                reason: "cwd",
+            });
+        }
+    }
+    let program = command_program(pane.foreground_command?)?;
+    (!SHELLS.contains(&program)).then(|| TabLabel {
+        name: program.to_owned(),
+        reason: "command",
+    })
+}
+
+/// The directory right below a repo root, or below `<root>/<host>/<owner>` in
+/// Go-style trees (`~/src/github.com/acme/api`).
+fn repo_name<'a>(cwd: &'a str, repo_roots: &[String]) -> Option<&'a str> {
+    let components = cwd
+        .split('/')
+        .filter(|component| !component.is_empty())
+        .collect::<Vec<_>>();
+    let root = components
+        .iter()
+        .position(|component| repo_roots.iter().any(|root| root == component))?;
+    let next = components.get(root + 1)?;
+    let repo = if next.contains('.') {
+        components.get(root + 3)?
+    } else {
+        next
+    };
+    (!repo.starts_with('.')).then_some(*repo)
+}
+
+/// The last path component; a home directory reads as `~`.
+fn dir_label(cwd: &str) -> Option<String> {
+    let components = cwd
+        .split('/')
+        .filter(|component| !component.is_empty())
+        .collect::<Vec<_>>();
+    match components.as_slice() {
+        [] => None,
+        ["home" | "Users", _] | ["root"] => Some("~".to_owned()),
+        [.., last] => Some((*last).to_owned()),
+    }
+}
+
+/// `name`, or `name 2`, `name 3`, ... if another tab already uses it.
+pub(crate) fn unique_tab_name(name: &str, taken: &BTreeSet<String>) -> String {
+    if !taken.contains(name) {
+        return name.to_owned();
+    }
+    (2..)
+        .map(|n| format!("{} {}", name, n))
+        .find(|candidate| !taken.contains(candidate))
+        .expect("unbounded range yields a free name")
+}
+
+impl<H: ZellijHost> State<H> {
+    /// A name for every default-named tab the rules can say something about. Names
+    /// already used by other tabs get a numeric suffix.
+    pub(crate) fn tab_name_suggestions(&self) -> Vec<TabNameSuggestion> {
+        let (Some(tabs), Some(manifest)) = (self.tabs.as_ref(), self.panes.as_ref()) else {
+            return Vec::new();
+        };
+        let mut taken = tabs
+            .iter()
+            .filter(|tab| !is_default_tab_name(&tab.name))
+            .map(|tab| tab.name.clone())
+            .collect::<BTreeSet<_>>();
+        let mut suggestions = Vec::new();
+        for tab in tabs {
+            if !is_default_tab_name(&tab.name) {
+                continue;
+            }
+            let facts = manifest
+                .panes
+                .get(&tab.position)
+                .into_iter()
+                .flatten()
+                .filter(|pane| !pane.is_plugin && !pane.exited && !self.is_jelly_pane(pane))
+                .map(|pane| PaneFacts {
+                    is_focused: pane.is_focused,
+                    cwd: self.pane_cwds.get(&pane.id).and_then(|cwd| cwd.to_str()),
+                    foreground_command: self
+                        .pane_commands
+                        .get(&pane.id)
+                        .or(pane.terminal_command.as_ref())
+                        .map(String::as_str),
+                })
+                .collect::<Vec<_>>();
+            let Some(label) = suggest_tab_label(&facts, &self.tab_name_repo_roots) else {
+                continue;
+            };
+            let name = unique_tab_name(&label.name, &taken);
+            taken.insert(name.clone());
+            suggestions.push(TabNameSuggestion {
+                position: tab.position,
+                current_name: tab.name.clone(),
+                name,
+                reason: label.reason.to_owned(),
+            });
+        }
+        suggestions
+    }
+
+    /// [`AutoNameTabs::Apply`]: renames default-named tabs the rules can name.
+    pub(crate) fn apply_tab_names(&mut self) {
+        if let Some(tabs) = self.tabs.as_ref() {
+            // A tab that no longer has a default name was renamed (by us or the user).
+            let named = tabs
+                .iter()
+                .filter(|tab| !is_default_tab_name(&tab.name))
+                .map(|tab| tab.position)
+                .collect::<BTreeSet<_>>();
+            self.auto_named_tabs
+                .retain(|position, _| !named.contains(position));
+        }
+        for suggestion in self.tab_name_suggestions() {
+            if self.auto_named_tabs.get(&suggestion.position) == Some(&suggestion.name) {
+                continue;
+            }
+            self.push_trace(TraceCategory::Lifecycle, "auto_named_tab")
+                .field("position", suggestion.position)
+                .field("name", &suggestion.name)
+                .field("reason", &suggestion.reason);
+            self.host
+                .rename_tab((suggestion.position + 1) as u32, &suggestion.name);
+            self.auto_named_tabs
+                .insert(suggestion.position, suggestion.name);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roots() -> Vec<String> {
+        DEFAULT_REPO_ROOTS
+            .iter()
+            .map(|root| root.to_string())
+            .collect()
+    }
+
+    fn facts<'a>(
+        is_focused: bool,
+        cwd: Option<&'a str>,
+        foreground_command: Option<&'a str>,
+    ) -> PaneFacts<'a> {
+        PaneFacts {
+            is_focused,
+            cwd,
+            foreground_command,
+        }
+    }
+
+    fn label(panes: &[PaneFacts]) -> Option<(String, &'static str)> {
+        suggest_tab_label(panes, &roots()).map(|label| (label.name, label.reason))
+    }
+
+    #[test]
+    fn default_names_are_tab_and_a_number() {
+        assert!(is_default_tab_name("Tab #1"));
+        assert!(is_default_tab_name("Tab #12"));
+        for name in ["Tab #", "Tab #1a", "Tab 1", "tab #1", "Tab #-1"] {
+            assert!(!is_default_tab_name(name), "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn pane_labels_prefer_repo_then_directory_then_program() {
+        let cases = [
+            (
+                Some("/home/ana/src/api/crates/db"),
+                None,
+                Some(("api", "repo")),
+            ),
+            (
+                Some("/home/ana/src/github.com/acme/web/app"),
+                None,
+                Some(("web", "repo")),
+            ),
+            (
+                Some("/home/ana/src/github.com/acme"),
+                None,
+                Some(("acme", "cwd")),
+            ),
+            (
+                Some("/home/ana/src/.dotfiles"),
+                None,
+                Some((".dotfiles", "cwd")),
+            ),
+            (Some("/home/ana"), None, Some(("~", "cwd"))),
+            (
+                Some("/"),
+                Some("env TERM=xterm /usr/bin/htop -d 10"),
+                Some(("htop", "command")),
+            ),
+            (Some("/"), Some("zsh"), None),
+            (None, None, None),
+        ];
+        for (cwd, command, expected) in cases {
+            assert_eq!(
+                label(&[facts(false, cwd, command)]),
+                expected.map(|(name, reason)| (name.to_owned(), reason)),
+                "{:?} {:?}",
+                cwd,
+                command
+            );
+        }
+    }
+
+    #[test]
+    fn majority_wins_and_focus_breaks_ties() {
+        let api = Some("/home/ana/src/api");
+        let web = Some("/home/ana/src/web");
+        assert_eq!(
+            label(&[
+                facts(true, web, None),
+                facts(false, api, None),
+                facts(false, api, None)
+            ]),
+            Some(("api".to_owned(), "repo"))
+        );
+        assert_eq!(
+            label(&[facts(false, api, None), facts(true, web, None)]),
+            Some(("web".to_owned(), "repo"))
+        );
+        assert_eq!(
+            label(&[facts(false, api, None), facts(false, web, None)]),
+            Some(("api".to_owned(), "repo")),
+            "without focus the first pane wins"
+        );
+    }
+
+    #[test]
+    fn unique_names_skip_every_taken_suffix() {
+        let taken = BTreeSet::from(["api".to_owned(), "api 2".to_owned()]);
+        assert_eq!(unique_tab_name("web", &taken), "web");
+        assert_eq!(unique_tab_name("api", &taken), "api 3");
+    }
+}
@@ -0,0 +1,18 @@
+//! Pane tags: free-form labels (`project:api`, `role:logs`) kept by pane id, so they
+//! survive renames. Selectors pick panes by them with `tag:<tag>` (see
+//! `plugin/src/selector.rs`).
+
+pub(crate) const MAX_TAG_LEN: usize = 64;
+
+pub(crate) fn validate_tag(tag: &str) -> Result<(), String> {
+    if tag.is_empty()
+        || tag.len() > MAX_TAG_LEN
+        || tag.chars().any(|c| c.is_whitespace() || c.is_control())
+    {
+        return Err(format!(
+            "invalid tag {:?}: use 1-{} characters without whitespace",
+            tag, MAX_TAG_LEN
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,462 @@
+//! The Jelly pane toggle: pipes and keybinds in, then show, hide, move or launch the
+//! pane for the single, per-client or per-tab policy.
+
+use crate::host::ZellijHost;
+use crate::protocol::{ButlerRequest, TraceCategory, TraceLevel};
+use crate::state::{OwnedPane, PanePolicy, State, TRACKED_PANE_MISSING_GRACE_MS};
+use serde_json::json;
+use std::collections::BTreeMap;
+use zellij_tile::prelude::*;
+
+pub const TOGGLE_DEDUP_WINDOW_MS: u128 = 100;
+/// How long a keybind pipe waits for the `ListClients` answer naming its client before
+/// running with the cached one.
+pub const KEYBIND_CLIENT_WAIT_MS: u128 = 250;
+
+/// A keybind pipe waiting to learn which client pressed the key.
+#[derive(Debug, Clone)]
+pub(crate) enum KeybindAction {
+    Toggle,
+    Scratch(String),
+}
+
+impl<H: ZellijHost> State<H> {
+    pub(crate) fn handle_toggle_pipe(&mut self, source: PipeSource, client_id: Option<ClientId>) {
+        let now_epoch_ms = self.host.now_epoch_millis();
+        self.metrics.toggles_mut().received += 1;
+        if self.toggle_within_dedup_window(client_id, now_epoch_ms) {
+            self.metrics.toggles_mut().dedup_window_ignored += 1;
+            self.push_trace(TraceCategory::Pipe, "pipe toggle dedup_window_ignored")
+                .field("source", format!("{:?}", source));
+            self.respond_to_cli(
+                &source,
+                Some(Self::ok_response(
+                    json!({ "ok": true, "dedup_window": true }),
+                )),
+            );
+            return;
+        }
+        if let PipeSource::Cli(pipe_id) = &source {
+            if self.last_cli_toggle_pipe_id.as_deref() == Some(pipe_id.as_str()) {
+                self.metrics.toggles_mut().duplicate_ignored += 1;
+                self.push_trace(TraceCategory::Pipe, "pipe toggle duplicate_ignored")
+                    .field("source", format!("{:?}", source));
+                self.respond_to_cli(
+                    &source,
+                    Some(Self::ok_response(json!({ "ok": true, "duplicate": true }))),
+                );
+                return;
+            }
+            self.last_cli_toggle_pipe_id = Some(pipe_id.clone());
+        }
+        let entry = self
+            .push_trace(TraceCategory::Pipe, "pipe toggle")
+            .field("source", format!("{:?}", source));
+        if let Some(client_id) = client_id {
+            entry.field("client_id", client_id);
+        }
+        if client_id.is_none() && matches!(source, PipeSource::Keybind) {
+            self.toggle_received_ms.get_or_insert(now_epoch_ms);
+            self.await_keybind_client(KeybindAction::Toggle);
+            return;
+        }
+        if client_id.is_some_and(|client_id| !self.client_is_known(client_id)) {
+            self.host.list_clients();
+        }
+        self.pending_toggle = true;
+        self.pending_toggle_client = client_id;
+        self.toggle_received_ms.get_or_insert(now_epoch_ms);
+        self.try_run_toggle();
+        if let PipeSource::Cli(_) = source {
+            self.respond_to_cli(&source, Some(Self::ok_response(json!({ "ok": true }))));
+        }
+    }
+
+    /// Asks Zellij which client is current and holds `action` until it answers.
+    pub(crate) fn await_keybind_client(&mut self, action: KeybindAction) {
+        self.push_trace(TraceCategory::Pipe, "keybind waiting for client list")
+            .field("action", format!("{:?}", action));
+        self.keybind_actions.push(action);
+        if self.keybind_wait_since_ms.is_none() {
+            self.keybind_wait_since_ms = Some(self.host.now_epoch_millis());
+            self.host
+                .set_timeout(KEYBIND_CLIENT_WAIT_MS as f64 / 1000.0);
+        }
+        self.host.list_clients();
+    }
+
+    /// Runs the waiting keybind pipes on behalf of the current client, if known.
+    pub(crate) fn run_keybind_actions(&mut self) {
+        self.keybind_wait_since_ms = None;
+        let client_id = self
+            .clients
+            .iter()
+            .find(|client| client.is_current_client)
+            .map(|client| client.client_id);
+        for action in std::mem::take(&mut self.keybind_actions) {
+            match action {
+                KeybindAction::Toggle => {
+                    self.pending_toggle = true;
+                    self.pending_toggle_client = client_id;
+                }
+                KeybindAction::Scratch(name) => {
+                    let response =
+                        self.execute_request(ButlerRequest::ToggleScratch { name, client_id });
+                    if response["ok"] != true {
+                        self.push_trace_at(
+                            TraceLevel::Warn,
+                            TraceCategory::Pipe,
+                            "keybind scratch failed",
+                        )
+                        .field("response", response);
+                    }
+                }
+            }
+        }
+        self.try_run_toggle();
+    }
+
+    fn toggle_within_dedup_window(
+        &mut self,
+        client_id: Option<ClientId>,
+        now_epoch_ms: u128,
+    ) -> bool {
+        if let Some(last_epoch_ms) = self.last_toggle_epoch_ms.get(&client_id) {
+            if now_epoch_ms.saturating_sub(*last_epoch_ms) <= TOGGLE_DEDUP_WINDOW_MS {
+                return true;
+            }
+        }
+        self.last_toggle_epoch_ms.insert(client_id, now_epoch_ms);
+        false
+    }
+
+    pub(crate) fn try_run_toggle(&mut self) {
+        if !self.pending_toggle || !self.ready || self.panes.is_none() {
+            return;
+        }
+        self.pending_toggle = false;
+        let client_id = self.pending_toggle_client.take();
+        let action = self.launch_or_toggle(client_id);
+        self.metrics.toggles_mut().executed += 1;
+        self.lifetime.toggles += 1;
+        if let Some(received_ms) = self.toggle_received_ms.take() {
+            let latency_ms = self.host.now_epoch_millis().saturating_sub(received_ms);
+            self.metrics.observe_toggle_latency(action, latency_ms);
+        }
+    }
+
+    /// Shows, hides, moves or launches the Jelly pane; returns which, for metrics.
+    fn launch_or_toggle(&mut self, client_id: Option<ClientId>) -> &'static str {
+        let current_tab = client_id
+            .and_then(|client_id| self.client_tab_index(client_id))
+            .or_else(|| self.active_tab_index())
+            .unwrap_or(0);
+        let entry = self
+            .push_trace(TraceCategory::Toggle, "launch_or_toggle")
+            .field("current_tab", current_tab);
+        if let Some(client_id) = client_id {
+            entry.field("client_id", client_id);
+        }
+        self.close_zombie_panes();
+        // Asking for Jelly overrides the crash-loop backoff.
+        for pane_id in std::mem::take(&mut self.deferred_restarts).into_keys() {
+            self.restart_exited_pane(pane_id);
+        }
+
+        if self.pane_policy == PanePolicy::PerClient {
+            let current_client = self
+                .clients
+                .iter()
+                .find(|client| client.is_current_client)
+                .map(|client| client.client_id);
+            if let Some(client_id) = client_id.or(current_client) {
+                return self.toggle_client_pane(client_id, current_tab);
+            }
+        }
+        if self.pane_policy == PanePolicy::PerTab {
+            return self.toggle_tab_pane(current_tab);
+        }
+
+        let mut jelly_panes = self.all_jelly_panes();
+        if let Some(tracked_pane_id) = self.jelly_pane_id {
+            if let Some((tracked_tab, tracked_pane)) =
+                self.find_terminal_pane_by_id(tracked_pane_id)
+            {
+                if !jelly_panes
+                    .iter()
+                    .any(|(_, pane)| pane.id == tracked_pane_id)
+                {
+                    self.push_trace(
+                        TraceCategory::Toggle,
+                        "using_tracked_jelly_pane despite_title_or_command_drift",
+                    )
+                    .field("id", tracked_pane_id)
+                    .field("tab", tracked_tab);
+                    jelly_panes.push((tracked_tab, tracked_pane));
+                }
+            }
+        }
+        if !jelly_panes.is_empty() {
+            self.push_trace(TraceCategory::Toggle, "found_existing_jelly_panes")
+                .field("count", jelly_panes.len());
+
+            let keep_idx = jelly_panes
+                .iter()
+                .position(|(_, pane)| self.jelly_pane_id == Some(pane.id))
+                .or_else(|| jelly_panes.iter().position(|(tab, _)| *tab == current_tab))
+                .or_else(|| jelly_panes.iter().position(|(_, pane)| pane.is_focused))
+                .unwrap_or(0);
+            let (keep_tab, keep_pane) = jelly_panes.remove(keep_idx);
+            self.jelly_pane_id = Some(keep_pane.id);
+
+            // Other policies own several Jelly panes on purpose.
+            if self.pane_policy == PanePolicy::Single {
+                for (_, extra_pane) in jelly_panes {
+                    if !self.may_close_jelly_pane(&extra_pane) {
+                        self.push_trace_at(
+                            TraceLevel::Warn,
+                            TraceCategory::Toggle,
+                            "not_closing_unconfirmed_jelly_pane",
+                        )
+                        .field("id", extra_pane.id)
+                        .field("title", &extra_pane.title);
+                        continue;
+                    }
+                    self.push_trace(TraceCategory::Toggle, "closing_extra_jelly_pane")
+                        .field("id", extra_pane.id);
+                    self.host.close_terminal_pane(extra_pane.id);
+                }
+            }
+
+            return self.toggle_existing_pane(keep_tab, keep_pane, current_tab);
+        }
+
+        let origin_pane = self.origin_pane(client_id, current_tab);
+        self.launch_new_jelly_terminal(origin_pane)
+    }
+
+    /// Hides `keep_pane` if it is visible in `current_tab`, otherwise brings it there.
+    pub(crate) fn toggle_existing_pane(
+        &mut self,
+        keep_tab: usize,
+        keep_pane: PaneInfo,
+        current_tab: usize,
+    ) -> &'static str {
+        let keep_ref = PaneId::Terminal(keep_pane.id);
+        let visible_in_current_tab = keep_tab == current_tab && !keep_pane.is_suppressed;
+        if visible_in_current_tab {
+            if keep_pane.is_focused {
+                if let Some(target_focus_id) =
+                    self.focusable_non_jelly_terminal_in_tab(current_tab, keep_pane.id)
+                {
+                    self.host.focus_terminal_pane(target_focus_id, true, false);
+                    self.push_trace(TraceCategory::Toggle, "hiding_jelly")
+                        .field("shifted_focus_to", target_focus_id);
+                }
+            }
+            self.push_trace(TraceCategory::Toggle, "hiding_jelly_pane")
+                .field("id", keep_pane.id);
+            self.host.hide_pane(keep_ref);
+            "hide"
+        } else if keep_tab != current_tab {
+            self.push_trace(
+                TraceCategory::Toggle,
+                "moving_jelly_to_current_tab_via_hidden_break",
+            )
+            .field("id", keep_pane.id)
+            .field("old_tab", keep_tab)
+            .field("new_tab", current_tab);
+            self.host.hide_pane(keep_ref);
+            self.host
+                .break_panes_to_tab(&[keep_ref], current_tab, false);
+            self.push_trace(TraceCategory::Toggle, "re_float_jelly_after_break")
+                .field("id", keep_pane.id)
+                .field("to_tab", current_tab);
+            self.host.toggle_pane_embed_or_eject(keep_ref);
+            self.host.show_pane(keep_ref, true, true);
+            "move"
+        } else {
+            self.push_trace(
+                TraceCategory::Toggle,
+                "showing_jelly_pane via_show_pane_with_id",
+            )
+            .field("id", keep_pane.id)
+            .field("from_tab", keep_tab)
+            .field("to_tab", current_tab);
+            self.host.show_pane(keep_ref, true, true);
+            "show"
+        }
+    }
+
+    /// [`PanePolicy::PerClient`]: toggles the invoking client's own Jelly pane, adopting
+    /// an unclaimed one (e.g. after a reload) before launching.
+    fn toggle_client_pane(&mut self, client_id: ClientId, current_tab: usize) -> &'static str {
+        if let Some(client_pane) = self.client_panes.get(&client_id).copied() {
+            if let Some((tab, pane)) = self.find_terminal_pane_by_id(client_pane.pane_id) {
+                return self.toggle_existing_pane(tab, pane, current_tab);
+            }
+            let now_ms = self.host.now_epoch_millis();
+            if client_pane.launched_ms.is_some_and(|launched_ms| {
+                now_ms.saturating_sub(launched_ms) <= TRACKED_PANE_MISSING_GRACE_MS
+            }) {
+                self.push_trace(TraceCategory::Toggle, "launch_skipped_client_jelly_pending")
+                    .field("client_id", client_id)
+                    .field("id", client_pane.pane_id);
+                return "launch_skipped";
+            }
+            self.client_panes.remove(&client_id);
+        }
+
+        let claimed = self
+            .client_panes
+            .values()
+            .map(|client_pane| client_pane.pane_id)
+            .collect::<Vec<_>>();
+        let mut unclaimed = self
+            .all_jelly_panes()
+            .into_iter()
+            .filter(|(_, pane)| !claimed.contains(&pane.id))
+            .collect::<Vec<_>>();
+        if !unclaimed.is_empty() {
+            let index = unclaimed
+                .iter()
+                .position(|(tab, _)| *tab == current_tab)
+                .unwrap_or(0);
+            let (tab, pane) = unclaimed.remove(index);
+            self.push_trace(TraceCategory::Toggle, "adopted_jelly_pane_for_client")
+                .field("client_id", client_id)
+                .field("id", pane.id);
+            self.client_panes.insert(
+                client_id,
+                OwnedPane {
+                    pane_id: pane.id,
+                    launched_ms: None,
+                },
+            );
+            return self.toggle_existing_pane(tab, pane, current_tab);
+        }
+
+        let origin_pane = self.origin_pane(Some(client_id), current_tab);
+        match self.launch_jelly_pane(origin_pane) {
+            Ok(pane_id) => {
+                let launched_ms = Some(self.host.now_epoch_millis());
+                self.client_panes.insert(
+                    client_id,
+                    OwnedPane {
+                        pane_id,
+                        launched_ms,
+                    },
+                );
+                "launch"
+            }
+            Err(action) => action,
+        }
+    }
+
+    /// [`PanePolicy::PerTab`]: hides or shows the pane pinned in `current_tab`,
+    /// adopting an unclaimed Jelly pane already in that tab before launching.
+    fn toggle_tab_pane(&mut self, current_tab: usize) -> &'static str {
+        if let Some(tab_pane) = self.tab_panes.get(&current_tab).copied() {
+            if let Some((tab, pane)) = self.find_terminal_pane_by_id(tab_pane.pane_id) {
+                return self.toggle_existing_pane(tab, pane, current_tab);
+            }
+            let now_ms = self.host.now_epoch_millis();
+            if tab_pane.launched_ms.is_some_and(|launched_ms| {
+                now_ms.saturating_sub(launched_ms) <= TRACKED_PANE_MISSING_GRACE_MS
+            }) {
+                self.push_trace(TraceCategory::Toggle, "launch_skipped_tab_jelly_pending")
+                    .field("tab", current_tab)
+                    .field("id", tab_pane.pane_id);
+                return "launch_skipped";
+            }
+            self.tab_panes.remove(&current_tab);
+        }
+
+        let claimed = self
+            .tab_panes
+            .values()
+            .map(|tab_pane| tab_pane.pane_id)
+            .collect::<Vec<_>>();
+        let unclaimed = self
+            .all_jelly_panes()
+            .into_iter()
+            .find(|(tab, pane)| *tab == current_tab && !claimed.contains(&pane.id));
+        if let Some((tab, pane)) = unclaimed {
+            self.push_trace(TraceCategory::Toggle, "adopted_jelly_pane_for_tab")
+                .field("tab", current_tab)
+                .field("id", pane.id);
+            self.tab_panes.insert(
+                current_tab,
+                OwnedPane {
+                    pane_id: pane.id,
+                    launched_ms: None,
+                },
+            );
+            return self.toggle_existing_pane(tab, pane, current_tab);
+        }
+
+        let origin_pane = self.origin_pane(None, current_tab);
+        match self.launch_jelly_pane(origin_pane) {
+            Ok(pane_id) => {
+                let launched_ms = Some(self.host.now_epoch_millis());
+                self.tab_panes.insert(
+                    current_tab,
+                    OwnedPane {
+                        pane_id,
+                        launched_ms,
+                    },
+                );
+                "launch"
+            }
+            Err(action) => action,
+        }
+    }
+
+    /// Re-keys per-tab panes by where the manifest says they are, so closing or
+    /// moving a tab (which shifts positions) keeps each pane with its tab. Panes that
+    /// disappeared, or landed in a tab that already has one, are forgotten.
+    pub(crate) fn remap_tab_panes(&mut self) {
+        if self.tab_panes.is_empty() || self.panes.is_none() {
+            return;
+        }
+        let now_ms = self.host.now_epoch_millis();
+        let mut remapped = BTreeMap::new();
+        for (old_tab, mut tab_pane) in std::mem::take(&mut self.tab_panes) {
+            let new_tab = match self.find_terminal_pane_by_id(tab_pane.pane_id) {
+                Some((tab, _)) => {
+                    // Seen once, so from now on missing means closed.
+                    tab_pane.launched_ms = None;
+                    tab
+                }
+                None if tab_pane.launched_ms.is_some_and(|launched_ms| {
+                    now_ms.saturating_sub(launched_ms) <= TRACKED_PANE_MISSING_GRACE_MS
+                }) =>
+                {
+                    old_tab
+                }
+                None => {
+                    self.push_trace(TraceCategory::Toggle, "dropped_tab_jelly_pane")
+                        .field("tab", old_tab)
+                        .field("id", tab_pane.pane_id)
+                        .field("reason", "missing");
+                    continue;
+                }
+            };
+            if remapped.contains_key(&new_tab) {
+                self.push_trace(TraceCategory::Toggle, "dropped_tab_jelly_pane")
+                    .field("tab", new_tab)
+                    .field("id", tab_pane.pane_id)
+                    .field("reason", "tab_already_has_jelly");
+                continue;
+            }
+            if new_tab != old_tab {
+                self.push_trace(TraceCategory::Toggle, "remapped_tab_jelly_pane")
+                    .field("id", tab_pane.pane_id)
+                    .field("old_tab", old_tab)
+                    .field("new_tab", new_tab);
+            }
+            remapped.insert(new_tab, tab_pane);
+        }
+        self.tab_panes = remapped;
+    }
+}
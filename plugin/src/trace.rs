@@ -0,0 +1,223 @@
+//! Bounded, structured butler trace, optionally mirrored to a rotating log file.
+//!
+//! The in-memory ring is lost whenever the plugin reloads. With `trace_log` set in the
+//! plugin configuration, entries are also appended as JSON lines to
+//! `<dir>/butler-trace.jsonl`; once that file would exceed `trace_log_max_bytes` it is
+//! rotated to `butler-trace.jsonl.1`, so at most two files' worth is kept.
+
+use crate::host::ZellijHost;
+use crate::protocol::{TraceCategory, TraceEntries, TraceEntry, TraceFormat, TraceLevel};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Display;
+
+pub(crate) const TRACE_LIMIT: usize = 200;
+pub(crate) const TRACE_LOG_FILE_NAME: &str = "butler-trace.jsonl";
+pub(crate) const DEFAULT_TRACE_LOG_MAX_BYTES: u64 = 256 * 1024;
+
+#[derive(Debug, Default)]
+pub(crate) struct TraceLog {
+    entries: VecDeque<TraceEntry>,
+    /// Entries pushed out of the ring before they reached the log file.
+    unflushed: Vec<TraceEntry>,
+    seq: u64,
+    start_epoch_ms: Option<u128>,
+    persist: Option<TraceLogFile>,
+}
+
+#[derive(Debug)]
+struct TraceLogFile {
+    path: String,
+    max_bytes: u64,
+    /// Highest `seq` already appended to the file.
+    persisted_seq: u64,
+}
+
+impl TraceLogFile {
+    fn rotated_path(&self) -> String {
+        format!("{}.1", self.path)
+    }
+}
+
+/// Whether `path` is the plugin's `/data` or `/tmp` folder or lies below one of them,
+/// without `..` components that could climb back out.
+pub(crate) fn in_plugin_dirs(path: &str) -> bool {
+    ["/data", "/tmp"].iter().any(|dir| {
+        path.strip_prefix(dir)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    }) && !path.split('/').any(|part| part == "..")
+}
+
+/// Selection applied by `get_trace`.
+#[derive(Debug, Default)]
+pub(crate) struct TraceFilter {
+    pub(crate) limit: Option<usize>,
+    pub(crate) level: Option<TraceLevel>,
+    pub(crate) category: Option<TraceCategory>,
+    pub(crate) since_seq: Option<u64>,
+}
+
+impl TraceLog {
+    /// Appends an entry and hands it back so call sites can attach fields.
+    pub(crate) fn push(
+        &mut self,
+        now_ms: u128,
+        level: TraceLevel,
+        category: TraceCategory,
+        message: impl Into<String>,
+    ) -> &mut TraceEntry {
+        let start_ms = *self.start_epoch_ms.get_or_insert(now_ms);
+        self.seq = self.seq.saturating_add(1);
+        if self.entries.len() >= TRACE_LIMIT {
+            if let Some(evicted) = self.entries.pop_front() {
+                if self
+                    .persist
+                    .as_ref()
+                    .is_some_and(|file| evicted.seq > file.persisted_seq)
+                {
+                    self.unflushed.push(evicted);
+                }
+            }
+        }
+        self.entries.push_back(TraceEntry {
+            seq: self.seq,
+            epoch_ms: u64::try_from(now_ms).unwrap_or(u64::MAX),
+            delta_ms: u64::try_from(now_ms.saturating_sub(start_ms)).unwrap_or(u64::MAX),
+            level,
+            category,
+            message: message.into(),
+            fields: BTreeMap::new(),
+        });
+        self.entries.back_mut().expect("entry was just pushed")
+    }
+
+    /// Parses the `trace_log` / `trace_log_max_bytes` configuration. `trace_log` is
+    /// `data` or `tmp` (the plugin's `/data` or `/tmp` folder) or a directory below one
+    /// of them; any other directory is returned as the error and nothing is logged.
+    pub(crate) fn configure(
+        &mut self,
+        configuration: &BTreeMap<String, String>,
+    ) -> Result<(), String> {
+        let dir = match configuration.get("trace_log").map(|value| value.trim()) {
+            None | Some("") | Some("off") | Some("false") => return Ok(()),
+            Some("data") | Some("true") => "/data",
+            Some("tmp") => "/tmp",
+            Some(dir) if in_plugin_dirs(dir) => dir.trim_end_matches('/'),
+            Some(dir) => return Err(dir.to_owned()),
+        };
+        let max_bytes = configuration
+            .get("trace_log_max_bytes")
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .filter(|max_bytes| *max_bytes > 0)
+            .unwrap_or(DEFAULT_TRACE_LOG_MAX_BYTES);
+        self.persist = Some(TraceLogFile {
+            path: format!("{}/{}", dir, TRACE_LOG_FILE_NAME),
+            max_bytes,
+            persisted_seq: 0,
+        });
+        Ok(())
+    }
+
+    pub(crate) fn log_path(&self) -> Option<&str> {
+        self.persist.as_ref().map(|file| file.path.as_str())
+    }
+
+    /// Appends entries pushed since the last flush to the log file, rotating it first
+    /// when the write would exceed the cap. Called once per plugin callback so fields
+    /// attached after `push` are included; entries the ring dropped in the meantime are
+    /// kept aside, so each entry is written exactly once. A failed write disables the
+    /// file.
+    pub(crate) fn flush<H: ZellijHost>(&mut self, host: &mut H) {
+        let unflushed = std::mem::take(&mut self.unflushed);
+        let Some(file) = self.persist.as_mut() else {
+            return;
+        };
+        let mut chunk = String::new();
+        for entry in unflushed.iter().chain(
+            self.entries
+                .iter()
+                .filter(|entry| entry.seq > file.persisted_seq),
+        ) {
+            if let Ok(line) = serde_json::to_string(entry) {
+                chunk.push_str(&line);
+                chunk.push('\n');
+            }
+        }
+        if chunk.is_empty() {
+            return;
+        }
+        let current_len = host.file_len(&file.path).unwrap_or(0);
+        let result = if current_len > 0 && current_len + chunk.len() as u64 > file.max_bytes {
+            host.rename_file(&file.path, &file.rotated_path())
+        } else {
+            Ok(())
+        }
+        .and_then(|()| host.append_file(&file.path, &chunk));
+        file.persisted_seq = self.seq;
+        if let Err(error) = result {
+            let path = file.path.clone();
+            self.persist = None;
+            self.push(
+                host.now_epoch_millis(),
+                TraceLevel::Warn,
+                TraceCategory::Lifecycle,
+                "trace_log_disabled",
+            )
+            .field("path", path)
+            .field("error", error);
+        }
+    }
+
+    /// Everything we still have as JSON lines: both log files when persisting,
+    /// otherwise the in-memory ring.
+    pub(crate) fn export_lines<H: ZellijHost>(&self, host: &H) -> String {
+        if let Some(file) = self.persist.as_ref() {
+            let mut contents = host.read_file(&file.rotated_path()).unwrap_or_default();
+            contents.push_str(&host.read_file(&file.path).unwrap_or_default());
+            return contents;
+        }
+        let mut contents = String::new();
+        for entry in &self.entries {
+            if let Ok(line) = serde_json::to_string(entry) {
+                contents.push_str(&line);
+                contents.push('\n');
+            }
+        }
+        contents
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// The newest `filter.limit` entries matching the filter, oldest first.
+    pub(crate) fn snapshot(&self, filter: &TraceFilter, format: TraceFormat) -> TraceEntries {
+        let matching = self
+            .entries
+            .iter()
+            .filter(|entry| filter.level.is_none_or(|level| entry.level >= level))
+            .filter(|entry| {
+                filter
+                    .category
+                    .is_none_or(|category| entry.category == category)
+            })
+            .filter(|entry| filter.since_seq.is_none_or(|since| entry.seq > since))
+            .collect::<Vec<_>>();
+        let wanted = filter.limit.unwrap_or(matching.len()).min(matching.len());
+        let selected = matching[matching.len() - wanted..].iter().copied();
+        match format {
+            TraceFormat::Text => TraceEntries::Text(selected.map(TraceEntry::to_text).collect()),
+            TraceFormat::Json => TraceEntries::Json(selected.cloned().collect()),
+        }
+    }
+}
+
+impl TraceEntry {
+    pub(crate) fn field(&mut self, key: &str, value: impl Display) -> &mut Self {
+        self.fields.insert(key.to_owned(), value.to_string());
+        self
+    }
+}
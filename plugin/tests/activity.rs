@@ -0,0 +1,124 @@
+mod common;
+
+use common::{ready_state, request, sync};
+use jelly_j::fake_host::FakeHost;
+use jelly_j::State;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+fn activity(state: &mut State<FakeHost>) -> Value {
+    let response = request(state, "a", json!({ "op": "get_activity" }));
+    assert_eq!(response["ok"], true, "{}", response);
+    response["result"].clone()
+}
+
+#[test]
+fn focus_time_accumulates_and_stale_tabs_sort_first() {
+    let mut state = ready_state(3);
+    let shell_0 = state.host().panes[&0][0].id;
+    state.host_mut().advance(10_000);
+    state.host_mut().focus_tab(1);
+    sync(&mut state);
+    state.host_mut().advance(5_000);
+
+    let result = activity(&mut state);
+
+    let tabs = result["tabs"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|tab| {
+            (
+                tab["position"].as_u64().unwrap(),
+                tab["focus_ms"].as_u64().unwrap(),
+                tab["idle_ms"].as_u64().unwrap(),
+            )
+        })
+        .collect::<Vec<_>>();
+    // Tab 2 was never focused, so it has been idle since the butler first saw it.
+    assert_eq!(tabs, [(2, 0, 15_000), (0, 10_000, 5_000), (1, 5_000, 0)]);
+    let pane_0 = result["panes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|pane| pane["id"] == shell_0)
+        .unwrap();
+    assert_eq!(pane_0["focus_ms"], 10_000);
+    assert_eq!(pane_0["idle_ms"], 5_000);
+    assert_eq!(
+        pane_0["last_focused_epoch_ms"].as_u64().unwrap() + 5_000,
+        result["now_epoch_ms"].as_u64().unwrap()
+    );
+}
+
+#[test]
+fn title_and_exit_changes_count_as_activity() {
+    let mut state = ready_state(2);
+    let watcher = state
+        .host_mut()
+        .add_terminal_pane(1, "cargo", Some("cargo watch"));
+    sync(&mut state);
+    state.host_mut().advance(60_000);
+    state.host_mut().exit_pane(watcher, Some(1));
+    sync(&mut state);
+    state.host_mut().advance(1_000);
+
+    let response = request(&mut state, "s", json!({ "op": "get_state" }));
+
+    let result = &response["result"];
+    let now_ms = state.host().now_ms as u64;
+    let pane = result["panes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|pane| pane["id"] == watcher)
+        .unwrap();
+    assert_eq!(pane["activity"]["last_changed_epoch_ms"], now_ms - 1_000);
+    assert_eq!(pane["activity"]["idle_ms"], 1_000);
+    assert_eq!(pane["activity"]["last_focused_epoch_ms"], Value::Null);
+    assert_eq!(result["tabs"][1]["activity"]["idle_ms"], 1_000);
+    assert_eq!(result["tabs"][0]["activity"]["idle_ms"], 0);
+}
+
+#[test]
+fn activity_waits_for_permissions() {
+    let mut state = State::new(FakeHost::with_tabs(1));
+    state.load(BTreeMap::new());
+
+    let response = request(&mut state, "a", json!({ "op": "get_activity" }));
+
+    assert_eq!(response["ok"], false);
+    assert_eq!(response["code"], "not_ready");
+}
+
+#[test]
+fn closing_a_middle_tab_keeps_each_tabs_activity() {
+    let mut state = ready_state(3);
+    state.host_mut().advance(1_000);
+    state.host_mut().focus_tab(1);
+    sync(&mut state);
+    state.host_mut().advance(2_000);
+    state.host_mut().focus_tab(2);
+    sync(&mut state);
+    state.host_mut().advance(4_000);
+    state.host_mut().focus_tab(0);
+    sync(&mut state);
+
+    state.host_mut().close_tab(1);
+    sync(&mut state);
+
+    let result = activity(&mut state);
+    let focus_by_position = result["tabs"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|tab| {
+            (
+                tab["position"].as_u64().unwrap(),
+                tab["focus_ms"].as_u64().unwrap(),
+            )
+        })
+        .collect::<BTreeMap<_, _>>();
+    // The old tab 2 (4s of focus) is now at position 1.
+    assert_eq!(focus_by_position, BTreeMap::from([(0, 1_000), (1, 4_000)]));
+}
@@ -0,0 +1,143 @@
+mod common;
+
+use common::{ready_state, request, sync, toggle};
+use jelly_j::fake_host::{FakeHost, HostCall};
+use jelly_j::State;
+use serde_json::json;
+use zellij_tile::prelude::*;
+
+fn hidden_panes(state: &State<FakeHost>) -> Vec<PaneId> {
+    state
+        .host()
+        .calls
+        .iter()
+        .filter_map(|call| match call {
+            HostCall::HidePane(pane_id) => Some(*pane_id),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn hide_panes_matches_commands_and_skips_jelly() {
+    let mut state = ready_state(2);
+    let api = state
+        .host_mut()
+        .add_terminal_pane(0, "api", Some("tail -f api.log"));
+    let db = state
+        .host_mut()
+        .add_terminal_pane(1, "db", Some("tail -f db.log"));
+    state.host_mut().add_terminal_pane(1, "htop", Some("htop"));
+    sync(&mut state);
+    toggle(&mut state, "t1");
+    sync(&mut state);
+    state.host_mut().take_calls();
+
+    let response = request(
+        &mut state,
+        "h",
+        json!({ "op": "hide_panes", "selector": "command:^tail" }),
+    );
+
+    assert_eq!(
+        response["result"],
+        json!({ "pane_ids": [api, db], "dry_run": false })
+    );
+    assert_eq!(
+        hidden_panes(&state),
+        [PaneId::Terminal(api), PaneId::Terminal(db)]
+    );
+    sync(&mut state);
+    let again = request(
+        &mut state,
+        "h2",
+        json!({ "op": "hide_panes", "selector": "command:^tail" }),
+    );
+    assert_eq!(again["result"]["pane_ids"], json!([]));
+    let everything = request(
+        &mut state,
+        "h3",
+        json!({ "op": "hide_panes", "selector": "floating:true" }),
+    );
+    assert_eq!(everything["result"]["pane_ids"], json!([]));
+}
+
+#[test]
+fn close_panes_dry_run_previews_without_closing() {
+    let mut state = ready_state(1);
+    let build = state
+        .host_mut()
+        .add_terminal_pane(0, "build", Some("cargo build"));
+    state
+        .host_mut()
+        .add_terminal_pane(0, "server", Some("cargo run"));
+    state.host_mut().exit_pane(build, Some(0));
+    sync(&mut state);
+    state.host_mut().take_calls();
+
+    let preview = request(
+        &mut state,
+        "c1",
+        json!({ "op": "close_panes", "selector": "tab:0 exited:true", "dry_run": true }),
+    );
+    assert_eq!(
+        preview["result"],
+        json!({ "pane_ids": [build], "dry_run": true })
+    );
+    assert!(state.host().pane(build).is_some());
+
+    let closed = request(
+        &mut state,
+        "c2",
+        json!({ "op": "close_panes", "selector": "tab:0 exited:true" }),
+    );
+    assert_eq!(
+        closed["result"],
+        json!({ "pane_ids": [build], "dry_run": false })
+    );
+    assert!(state.host().pane(build).is_none());
+    assert!(state
+        .host()
+        .calls
+        .contains(&HostCall::CloseTerminalPane(build)));
+}
+
+#[test]
+fn show_panes_only_touches_hidden_panes_and_rejects_bad_selectors() {
+    let mut state = ready_state(1);
+    let logs = state
+        .host_mut()
+        .add_terminal_pane(0, "logs", Some("tail -f app.log"));
+    state
+        .host_mut()
+        .add_terminal_pane(0, "logs 2", Some("tail -f b.log"));
+    sync(&mut state);
+    request(
+        &mut state,
+        "h",
+        json!({ "op": "hide_pane", "pane_id": logs }),
+    );
+    sync(&mut state);
+    state.host_mut().take_calls();
+
+    let shown = request(
+        &mut state,
+        "s",
+        json!({ "op": "show_panes", "selector": "title:^logs" }),
+    );
+
+    assert_eq!(shown["result"]["pane_ids"], json!([logs]));
+    assert!(state.host().calls.contains(&HostCall::ShowPane {
+        pane_id: PaneId::Terminal(logs),
+        should_float_if_hidden: true,
+        should_focus_pane: false,
+    }));
+    for selector in ["", "title", "title:(", "size:big", "exited:maybe"] {
+        let invalid = request(
+            &mut state,
+            "x",
+            json!({ "op": "close_panes", "selector": selector }),
+        );
+        assert_eq!(invalid["code"], "invalid_selector", "{}", selector);
+    }
+}
@@ -0,0 +1,180 @@
+mod common;
+
+use common::{
+    attach_clients, jelly_pane, pipe_message, ready_state_with, request, sync, KEYPRESS_GAP_MS,
+};
+use jelly_j::fake_host::{FakeHost, HostCall};
+use jelly_j::{State, KEYBIND_CLIENT_WAIT_MS};
+use serde_json::json;
+use std::collections::BTreeMap;
+use zellij_tile::prelude::*;
+
+/// Client 1 looks at tab 0 (the "active" one), client 2 at tab 1.
+const TWO_CLIENTS: &[(ClientId, usize)] = &[(1, 0), (2, 1)];
+
+fn client_pane_ids(state: &mut State<FakeHost>, pipe_id: &str) -> Vec<u32> {
+    let response = request(state, pipe_id, json!({ "op": "get_state" }));
+    response["result"]["butler"]["client_panes"]
+        .as_array()
+        .expect("client_panes is an array")
+        .iter()
+        .map(|entry| entry["pane_id"].as_u64().unwrap() as u32)
+        .collect()
+}
+
+fn client_toggle(state: &mut State<FakeHost>, pipe_id: &str, client_id: u16) {
+    state.host_mut().advance(KEYPRESS_GAP_MS);
+    let mut message = pipe_message("toggle", pipe_id, Some("toggle"));
+    message.args = BTreeMap::from([("client_id".to_owned(), client_id.to_string())]);
+    state.pipe(message);
+    sync(state);
+}
+
+#[test]
+fn toggle_targets_the_invoking_clients_tab() {
+    let mut state = ready_state_with(2, &[]);
+    attach_clients(&mut state, TWO_CLIENTS);
+    client_toggle(&mut state, "t1", 1);
+    assert_eq!(jelly_pane(&state).0, 0);
+
+    client_toggle(&mut state, "t2", 2);
+
+    let (tab, pane) = jelly_pane(&state);
+    assert_eq!(tab, 1, "client 2 pulled Jelly to its own tab");
+    assert!(pane.is_floating && !pane.is_suppressed);
+}
+
+#[test]
+fn unknown_client_refreshes_the_client_list_and_falls_back_to_the_active_tab() {
+    let mut state = ready_state_with(2, &[]);
+    attach_clients(&mut state, TWO_CLIENTS);
+
+    client_toggle(&mut state, "t1", 9);
+
+    assert!(state.host().calls.contains(&HostCall::ListClients));
+    assert_eq!(jelly_pane(&state).0, 0);
+}
+
+#[test]
+fn per_client_policy_gives_each_client_its_own_pane() {
+    let mut state = ready_state_with(2, &[("pane_policy", "per_client")]);
+    attach_clients(&mut state, TWO_CLIENTS);
+    client_toggle(&mut state, "t1", 1);
+    let first = client_pane_ids(&mut state, "p0")[0];
+
+    client_toggle(&mut state, "t2", 2);
+    client_toggle(&mut state, "t3", 1);
+
+    let calls = state.host().calls.clone();
+    assert!(!calls
+        .iter()
+        .any(|call| matches!(call, HostCall::CloseTerminalPane(_))));
+    assert!(calls.contains(&HostCall::HidePane(PaneId::Terminal(first))));
+    let pane_ids = client_pane_ids(&mut state, "p1");
+    assert_eq!(pane_ids.len(), 2);
+    assert_eq!(pane_ids[0], first);
+    assert_ne!(pane_ids[0], pane_ids[1]);
+}
+
+#[test]
+fn dedup_window_is_per_client() {
+    let mut state = ready_state_with(2, &[("pane_policy", "per_client")]);
+    attach_clients(&mut state, TWO_CLIENTS);
+
+    for (pipe_id, client_id) in [("t1", 1), ("t2", 2)] {
+        let mut message = pipe_message("toggle", pipe_id, Some("toggle"));
+        message.args = BTreeMap::from([("client_id".to_owned(), client_id.to_string())]);
+        state.pipe(message);
+    }
+
+    let launches = state
+        .host()
+        .calls
+        .iter()
+        .filter(|call| matches!(call, HostCall::OpenCommandPaneFloating { .. }))
+        .count();
+    assert_eq!(launches, 2);
+}
+
+/// A key press from `client_id`: Zellij answers the `list_clients` it triggers marking
+/// that client as current.
+fn keybind_press(state: &mut State<FakeHost>, name: &str, client_id: ClientId) {
+    state.host_mut().advance(KEYPRESS_GAP_MS);
+    state.host_mut().take_calls();
+    state.pipe(PipeMessage {
+        source: PipeSource::Keybind,
+        name: name.to_owned(),
+        payload: None,
+        args: BTreeMap::new(),
+        is_private: false,
+    });
+    assert!(state.host().calls.contains(&HostCall::ListClients));
+    assert!(!state.host().calls.iter().any(|call| matches!(
+        call,
+        HostCall::OpenCommandPaneFloating { .. } | HostCall::BreakPanesToTab { .. }
+    )));
+    for client in &mut state.host_mut().clients {
+        client.is_current_client = client.client_id == client_id;
+    }
+    let clients = state.host().clients_update();
+    state.update(clients);
+    sync(state);
+}
+
+#[test]
+fn keybind_toggle_targets_the_client_that_pressed_the_key() {
+    let mut state = ready_state_with(2, &[]);
+    attach_clients(&mut state, TWO_CLIENTS);
+    keybind_press(&mut state, "toggle", 1);
+    assert_eq!(jelly_pane(&state).0, 0);
+
+    keybind_press(&mut state, "toggle", 2);
+
+    let (tab, pane) = jelly_pane(&state);
+    assert_eq!(tab, 1, "client 2 is on tab 1, not the active tab");
+    assert!(pane.is_floating && !pane.is_suppressed);
+}
+
+#[test]
+fn keybind_scratch_follows_the_client_that_pressed_the_key() {
+    let mut state = ready_state_with(2, &[("scratch.top.command", "htop")]);
+    attach_clients(&mut state, TWO_CLIENTS);
+    keybind_press(&mut state, "scratch:top", 1);
+    let scratch = state.host().panes[&0]
+        .iter()
+        .find(|pane| {
+            pane.terminal_command
+                .as_deref()
+                .is_some_and(|command| command.contains("htop"))
+        })
+        .expect("scratch pane launched in client 1's tab")
+        .id;
+
+    keybind_press(&mut state, "scratch:top", 2);
+
+    assert_eq!(state.host().pane(scratch).unwrap().0, 1);
+}
+
+#[test]
+fn keybind_toggle_falls_back_to_the_cached_client_when_the_list_never_comes() {
+    let mut state = ready_state_with(2, &[]);
+    attach_clients(&mut state, TWO_CLIENTS);
+    state.host_mut().advance(KEYPRESS_GAP_MS);
+
+    state.pipe(PipeMessage {
+        source: PipeSource::Keybind,
+        name: "toggle".to_owned(),
+        payload: None,
+        args: BTreeMap::new(),
+        is_private: false,
+    });
+    assert!(state
+        .host()
+        .calls
+        .contains(&HostCall::SetTimeout(KEYBIND_CLIENT_WAIT_MS as u64)));
+    state.host_mut().advance(KEYBIND_CLIENT_WAIT_MS);
+    state.update(Event::Timer(0.25));
+    sync(&mut state);
+
+    assert_eq!(jelly_pane(&state).0, 0);
+}
@@ -0,0 +1,86 @@
+#![allow(dead_code)]
+
+use jelly_j::fake_host::FakeHost;
+use jelly_j::State;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use zellij_tile::prelude::*;
+
+/// Moves the clock past the toggle dedup window between simulated keypresses.
+pub const KEYPRESS_GAP_MS: u128 = 1_000;
+
+/// A loaded butler with permissions granted, caches primed, and recorded calls cleared.
+pub fn ready_state(tab_count: usize) -> State<FakeHost> {
+    ready_state_with(tab_count, &[])
+}
+
+/// [`ready_state`] loaded with the given plugin configuration.
+pub fn ready_state_with(tab_count: usize, configuration: &[(&str, &str)]) -> State<FakeHost> {
+    let mut state = State::new(FakeHost::with_tabs(tab_count));
+    state.load(
+        configuration
+            .iter()
+            .map(|(key, value)| ((*key).to_owned(), (*value).to_owned()))
+            .collect(),
+    );
+    state.update(Event::PermissionRequestResult(PermissionStatus::Granted));
+    sync(&mut state);
+    state.host_mut().take_calls();
+    state
+}
+
+/// Attaches one client per `(client_id, tab_index)`, each focused on that tab's first
+/// pane, and delivers the client list.
+pub fn attach_clients(state: &mut State<FakeHost>, clients: &[(ClientId, usize)]) {
+    for &(client_id, tab_index) in clients {
+        let pane_id = state.host().panes[&tab_index][0].id;
+        state.host_mut().add_client(client_id, pane_id);
+    }
+    let clients = state.host().clients_update();
+    state.update(clients);
+    sync(state);
+    state.host_mut().take_calls();
+}
+
+/// Delivers the fake host's current tabs and panes, as Zellij would after side effects.
+pub fn sync(state: &mut State<FakeHost>) {
+    let tab_update = state.host().tab_update();
+    let pane_update = state.host().pane_update();
+    state.update(tab_update);
+    state.update(pane_update);
+}
+
+pub fn pipe_message(name: &str, pipe_id: &str, payload: Option<&str>) -> PipeMessage {
+    PipeMessage {
+        source: PipeSource::Cli(pipe_id.to_owned()),
+        name: name.to_owned(),
+        payload: payload.map(str::to_owned),
+        args: BTreeMap::new(),
+        is_private: false,
+    }
+}
+
+/// One Alt+j press from the CLI, followed by the resulting state events.
+pub fn toggle(state: &mut State<FakeHost>, pipe_id: &str) {
+    state.host_mut().advance(KEYPRESS_GAP_MS);
+    state.pipe(pipe_message("toggle", pipe_id, Some("toggle")));
+    sync(state);
+}
+
+/// Sends a `request` pipe message and returns the parsed response envelope.
+pub fn request(state: &mut State<FakeHost>, pipe_id: &str, payload: Value) -> Value {
+    state.pipe(pipe_message("request", pipe_id, Some(&payload.to_string())));
+    state
+        .host()
+        .last_cli_output(pipe_id)
+        .expect("request pipe produced a response")
+}
+
+pub fn jelly_pane(state: &State<FakeHost>) -> (usize, PaneInfo) {
+    let pane_id = state.jelly_pane_id().expect("jelly pane is tracked");
+    let (tab_index, pane) = state
+        .host()
+        .pane(pane_id)
+        .expect("tracked jelly pane exists");
+    (tab_index, pane.clone())
+}
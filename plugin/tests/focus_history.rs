@@ -0,0 +1,102 @@
+mod common;
+
+use common::{pipe_message, ready_state, request, sync, toggle};
+use jelly_j::fake_host::{FakeHost, HostCall};
+use jelly_j::State;
+use serde_json::{json, Value};
+
+fn shell(state: &State<FakeHost>, tab: usize) -> u32 {
+    state.host().panes[&tab][0].id
+}
+
+fn history_panes(state: &mut State<FakeHost>) -> Vec<Value> {
+    let response = request(state, "h", json!({ "op": "get_focus_history" }));
+    assert_eq!(response["ok"], true, "{}", response);
+    response["result"]["panes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| entry["pane_id"].clone())
+        .collect()
+}
+
+#[test]
+fn history_is_most_recently_used_first() {
+    let mut state = ready_state(3);
+    for tab in [1, 2, 0, 1] {
+        state.host_mut().advance(1_000);
+        state.host_mut().focus_tab(tab);
+        sync(&mut state);
+    }
+
+    let response = request(&mut state, "h", json!({ "op": "get_focus_history" }));
+
+    let result = &response["result"];
+    let panes = result["panes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| (entry["pane_id"].clone(), entry["tab_position"].clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        panes,
+        [
+            (json!(shell(&state, 1)), json!(1)),
+            (json!(shell(&state, 0)), json!(0)),
+            (json!(shell(&state, 2)), json!(2)),
+        ]
+    );
+    let tabs = result["tabs"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| entry["position"].clone())
+        .collect::<Vec<_>>();
+    assert_eq!(tabs, [json!(1), json!(0), json!(2)]);
+}
+
+#[test]
+fn focus_back_and_jump_back_alternate_between_the_last_two_panes() {
+    let mut state = ready_state(3);
+    let (shell_0, shell_1) = (shell(&state, 0), shell(&state, 1));
+    state.host_mut().focus_tab(2);
+    sync(&mut state);
+    state.host_mut().focus_tab(1);
+    sync(&mut state);
+
+    let response = request(&mut state, "b", json!({ "op": "focus_back", "steps": 2 }));
+    assert_eq!(response["result"]["pane_id"], shell_0);
+    sync(&mut state);
+    state.pipe(pipe_message("jump_back", "j1", None));
+    sync(&mut state);
+
+    let focused = state
+        .host()
+        .calls
+        .iter()
+        .filter_map(|call| match call {
+            HostCall::FocusTerminalPane(pane_id) => Some(*pane_id),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(focused, [shell_0, shell_1]);
+    assert!(state.host().tabs[1].active);
+}
+
+#[test]
+fn jelly_and_closed_panes_are_left_out() {
+    let mut state = ready_state(1);
+    let extra = state.host_mut().add_terminal_pane(0, "htop", Some("htop"));
+    let shell_0 = shell(&state, 0);
+    state.host_mut().pane_mut(shell_0).unwrap().is_focused = false;
+    state.host_mut().pane_mut(extra).unwrap().is_focused = true;
+    sync(&mut state);
+    toggle(&mut state, "t1");
+    state.host_mut().remove_pane(extra);
+    state.host_mut().pane_mut(shell_0).unwrap().is_focused = true;
+    sync(&mut state);
+
+    assert_eq!(history_panes(&mut state), [json!(shell_0)]);
+    let response = request(&mut state, "b", json!({ "op": "focus_back" }));
+    assert_eq!(response["code"], "no_focus_history");
+}
@@ -0,0 +1,138 @@
+mod common;
+
+use common::{ready_state, ready_state_with, request};
+use jelly_j::fake_host::HostCall;
+use serde_json::json;
+use std::path::PathBuf;
+use zellij_tile::prelude::*;
+
+fn paths(paths: &[&str]) -> Vec<(PathBuf, Option<FileMetadata>)> {
+    paths
+        .iter()
+        .map(|path| (PathBuf::from(path), None))
+        .collect()
+}
+
+#[test]
+fn watching_is_opt_in() {
+    let mut state = ready_state(1);
+    let response = request(&mut state, "f", json!({ "op": "get_fs_changes" }));
+    assert_eq!(response["result"]["watching"], false, "{}", response);
+
+    let mut state = ready_state_with(1, &[("fs_watch", "on")]);
+    let response = request(&mut state, "f", json!({ "op": "get_fs_changes" }));
+    assert_eq!(response["result"]["watching"], true, "{}", response);
+}
+
+#[test]
+fn bursts_are_debounced_merged_and_filtered() {
+    let mut state = ready_state_with(
+        1,
+        &[("fs_watch", "on"), ("fs_watch_include", "src/**,*.toml")],
+    );
+    state.update(Event::FileSystemCreate(paths(&[
+        "/host/src/new.rs",
+        "/host/src/.new.rs.swp",
+    ])));
+    state.host_mut().advance(100);
+    state.update(Event::FileSystemUpdate(paths(&[
+        "/host/src/new.rs",
+        "/host/crates/api/Cargo.toml",
+        "/host/README.md",
+    ])));
+    state.update(Event::FileSystemDelete(paths(&[
+        "/host/src/.new.rs.swp",
+        "/host/src/lib.rs",
+        "/host/target/debug/src/x.rs",
+    ])));
+    state.update(Event::FileSystemCreate(paths(&["/host/src/lib.rs"])));
+
+    let early = request(&mut state, "f1", json!({ "op": "get_fs_changes" }));
+    assert_eq!(early["result"]["summaries"], json!([]));
+
+    state.host_mut().advance(1_000);
+    let response = request(&mut state, "f2", json!({ "op": "get_fs_changes" }));
+
+    let summary = &response["result"]["summaries"][0];
+    assert_eq!(summary["seq"], 1);
+    assert_eq!(summary["created"], json!(["src/new.rs"]));
+    assert_eq!(
+        summary["updated"],
+        json!(["crates/api/Cargo.toml", "src/lib.rs"])
+    );
+    assert_eq!(summary["deleted"], json!([]));
+    assert_eq!(
+        summary["last_epoch_ms"].as_u64().unwrap() - summary["first_epoch_ms"].as_u64().unwrap(),
+        100
+    );
+}
+
+#[test]
+fn since_returns_only_newer_summaries() {
+    let mut state = ready_state_with(1, &[("fs_watch", "on"), ("fs_watch_debounce_ms", "200")]);
+    state.update(Event::FileSystemUpdate(paths(&["/host/a.txt"])));
+    state.host_mut().advance(500);
+    state.update(Event::FileSystemDelete(paths(&["/host/b.txt"])));
+    state.host_mut().advance(500);
+
+    let all = request(&mut state, "f1", json!({ "op": "get_fs_changes" }));
+    assert_eq!(all["result"]["summaries"].as_array().unwrap().len(), 2);
+    let newer = request(
+        &mut state,
+        "f2",
+        json!({ "op": "get_fs_changes", "since": 1 }),
+    );
+    assert_eq!(newer["result"]["summaries"][0]["deleted"], json!(["b.txt"]));
+    assert_eq!(newer["result"]["summaries"].as_array().unwrap().len(), 1);
+    let trace = request(
+        &mut state,
+        "t",
+        json!({ "op": "get_trace", "category": "fs", "format": "json" }),
+    );
+    assert_eq!(
+        trace["result"]["entries"].as_array().unwrap().len(),
+        2,
+        "{}",
+        trace
+    );
+}
+
+#[test]
+fn a_timer_cuts_the_burst_once_it_goes_quiet() {
+    let mut state = ready_state_with(1, &[("fs_watch", "on"), ("fs_watch_debounce_ms", "200")]);
+    state.update(Event::FileSystemUpdate(paths(&["/host/a.txt"])));
+    state.host_mut().advance(150);
+    state.update(Event::FileSystemUpdate(paths(&["/host/b.txt"])));
+    let calls = state.host_mut().take_calls();
+    assert_eq!(
+        calls
+            .iter()
+            .filter(|call| matches!(call, HostCall::SetTimeout(_)))
+            .collect::<Vec<_>>(),
+        [&HostCall::SetTimeout(200)]
+    );
+
+    // The later change kept the burst going, so the first timer re-arms for the rest.
+    state.host_mut().advance(50);
+    state.update(Event::Timer(0.2));
+    assert!(state
+        .host_mut()
+        .take_calls()
+        .contains(&HostCall::SetTimeout(150)));
+
+    state.host_mut().advance(150);
+    state.update(Event::Timer(0.15));
+    let trace = request(
+        &mut state,
+        "t",
+        json!({ "op": "get_trace", "category": "fs", "format": "json" }),
+    );
+    let entries = trace["result"]["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 1, "{}", trace);
+    assert_eq!(entries[0]["fields"]["changes"], "2");
+    assert!(!state
+        .host_mut()
+        .take_calls()
+        .iter()
+        .any(|call| matches!(call, HostCall::SetTimeout(_))));
+}
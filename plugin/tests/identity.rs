@@ -0,0 +1,136 @@
+mod common;
+
+use common::{jelly_pane, ready_state, request, sync, toggle};
+use jelly_j::fake_host::{FakeHost, HostCall};
+use jelly_j::{State, PANE_NAME, UI_TOKEN_ENV};
+use serde_json::json;
+use std::collections::BTreeMap;
+use zellij_tile::prelude::*;
+
+/// The token in the environment of the most recently launched Jelly pane.
+fn launch_token(state: &State<FakeHost>) -> String {
+    let args = state
+        .host()
+        .calls
+        .iter()
+        .rev()
+        .find_map(|call| match call {
+            HostCall::OpenCommandPaneFloating { args, .. } => Some(args.clone()),
+            _ => None,
+        })
+        .expect("a Jelly pane was launched");
+    let assignment = args
+        .iter()
+        .find_map(|word| word.strip_prefix(&format!("{}=", UI_TOKEN_ENV)))
+        .expect("launch carries a token");
+    assignment.to_owned()
+}
+
+#[test]
+fn panes_that_merely_mention_jelly_are_left_alone() {
+    let mut host = FakeHost::with_tabs(1);
+    let notes = host.add_terminal_pane(0, "notes", Some("vim jelly-j.md"));
+    let mut state = State::new(host);
+    state.load(BTreeMap::new());
+    state.update(Event::PermissionRequestResult(PermissionStatus::Granted));
+    sync(&mut state);
+
+    toggle(&mut state, "t1");
+
+    assert_ne!(jelly_pane(&state).1.id, notes);
+    let calls = &state.host().calls;
+    assert!(!calls.contains(&HostCall::CloseTerminalPane(notes)));
+    assert!(!calls.contains(&HostCall::HidePane(PaneId::Terminal(notes))));
+}
+
+#[test]
+fn heuristic_matches_are_not_closed_unless_title_and_program_agree() {
+    let mut host = FakeHost::with_tabs(2);
+    let renamed = host.add_terminal_pane(1, PANE_NAME, Some("htop"));
+    let current = host.add_terminal_pane(0, PANE_NAME, None);
+    let mut state = State::new(host);
+    state.load(BTreeMap::new());
+    state.update(Event::PermissionRequestResult(PermissionStatus::Granted));
+    sync(&mut state);
+
+    toggle(&mut state, "t1");
+
+    assert_eq!(state.jelly_pane_id(), Some(current));
+    assert!(!state
+        .host()
+        .calls
+        .contains(&HostCall::CloseTerminalPane(renamed)));
+    assert!(state.host().pane(renamed).is_some());
+}
+
+#[test]
+fn renamed_plain_shell_survives_toggle() {
+    let mut state = ready_state(2);
+    toggle(&mut state, "t1");
+    let launched = jelly_pane(&state).1.id;
+    let shell = state.host_mut().add_terminal_pane(1, PANE_NAME, None);
+    sync(&mut state);
+
+    toggle(&mut state, "t2");
+    toggle(&mut state, "t3");
+
+    assert_eq!(state.jelly_pane_id(), Some(launched));
+    assert!(!state
+        .host()
+        .calls
+        .contains(&HostCall::CloseTerminalPane(shell)));
+    assert!(state.host().pane(shell).is_some());
+}
+
+#[test]
+fn registered_ui_pane_stays_jelly_after_a_rename() {
+    let mut state = ready_state(1);
+    toggle(&mut state, "t1");
+    let pane_id = jelly_pane(&state).1.id;
+    let token = launch_token(&state);
+
+    let response = request(
+        &mut state,
+        "r1",
+        json!({ "op": "register_ui_pane", "pane_id": pane_id, "token": token }),
+    );
+    assert_eq!(response["ok"], true);
+    let spent = request(
+        &mut state,
+        "r1b",
+        json!({ "op": "register_ui_pane", "pane_id": pane_id, "token": token }),
+    );
+    assert_eq!(spent["code"], "unknown_token", "tokens are single-use");
+    state.host_mut().pane_mut(pane_id).unwrap().title = "~/src".to_owned();
+    sync(&mut state);
+
+    let response = request(&mut state, "r2", json!({ "op": "get_state" }));
+    assert_eq!(
+        response["result"]["butler"]["registered_jelly_panes"],
+        json!([pane_id])
+    );
+    let panes = response["result"]["panes"].as_array().unwrap().clone();
+    assert!(panes.iter().any(|pane| pane["id"] == pane_id));
+}
+
+#[test]
+fn registration_rejects_foreign_tokens() {
+    let mut state = ready_state(1);
+    toggle(&mut state, "t1");
+    let pane_id = jelly_pane(&state).1.id;
+    let token = launch_token(&state);
+
+    let unknown = request(
+        &mut state,
+        "r1",
+        json!({ "op": "register_ui_pane", "pane_id": pane_id, "token": "forged" }),
+    );
+    assert_eq!(unknown["code"], "unknown_token");
+
+    let mismatch = request(
+        &mut state,
+        "r2",
+        json!({ "op": "register_ui_pane", "pane_id": pane_id + 1, "token": token }),
+    );
+    assert_eq!(mismatch["code"], "token_mismatch");
+}
@@ -0,0 +1,196 @@
+mod common;
+
+use common::{ready_state_with, request, sync, toggle};
+use jelly_j::fake_host::{FakeHost, HostCall};
+use jelly_j::{State, UI_TOKEN_ENV};
+use serde_json::json;
+
+/// Program, arguments and cwd of the last launch.
+fn last_launch(state: &State<FakeHost>) -> (String, Vec<String>, Option<String>) {
+    state
+        .host()
+        .calls
+        .iter()
+        .rev()
+        .find_map(|call| match call {
+            HostCall::OpenCommandPaneFloating { path, args, cwd } => {
+                Some((path.clone(), args.clone(), cwd.clone()))
+            }
+            _ => None,
+        })
+        .expect("a Jelly pane was launched")
+}
+
+#[test]
+fn focused_cwd_follows_the_pane_jelly_is_opened_from() {
+    let mut state = ready_state_with(2, &[]);
+    let shell_0 = state.host().panes[&0][0].id;
+    let shell_1 = state.host().panes[&1][0].id;
+    let cwd_changed = state.host().cwd_changed(shell_0, "/home/me/notes");
+    state.update(cwd_changed);
+    let cwd_changed = state.host().cwd_changed(shell_1, "/home/me/project");
+    state.update(cwd_changed);
+    state.host_mut().focus_tab(1);
+    sync(&mut state);
+
+    toggle(&mut state, "t1");
+
+    assert_eq!(last_launch(&state).2.as_deref(), Some("/home/me/project"));
+}
+
+#[test]
+fn focused_cwd_is_left_to_zellij_until_reported() {
+    let mut state = ready_state_with(1, &[]);
+
+    toggle(&mut state, "t1");
+
+    assert_eq!(last_launch(&state).2, None);
+}
+
+#[test]
+fn fixed_cwd_and_launch_env_are_passed_as_shell_arguments() {
+    let mut state = ready_state_with(
+        1,
+        &[
+            ("launch_cwd", "fixed:/srv/notes"),
+            ("launch_env.JELLY_J_MODEL", "fast"),
+            ("launch_env.NO_COLOR", "1"),
+            ("launch_command", "/opt/jelly/bin/jelly-j ui --compact"),
+        ],
+    );
+
+    toggle(&mut state, "t1");
+
+    let (path, args, cwd) = last_launch(&state);
+    assert_eq!(path, "sh");
+    assert_eq!(cwd.as_deref(), Some("/srv/notes"));
+    assert_eq!(
+        args[..3],
+        [
+            "-c",
+            "export \"$@\" && exec /opt/jelly/bin/jelly-j ui --compact",
+            "jelly-j"
+        ]
+    );
+    assert_eq!(args[3..5], ["JELLY_J_MODEL=fast", "NO_COLOR=1"]);
+    assert!(args[5].starts_with(&format!("{}=", UI_TOKEN_ENV)));
+
+    let response = request(&mut state, "p1", json!({ "op": "get_state" }));
+    let butler = &response["result"]["butler"];
+    assert_eq!(butler["launch_cwd"], "fixed:/srv/notes");
+    assert_eq!(butler["launch_env"], json!(["JELLY_J_MODEL", "NO_COLOR"]));
+}
+
+#[test]
+fn launch_command_is_parsed_by_the_shell() {
+    let mut state = ready_state_with(
+        1,
+        &[("launch_command", "~/bin/jelly-j ui --title 'two words'")],
+    );
+
+    toggle(&mut state, "t1");
+
+    let (_, args, _) = last_launch(&state);
+    assert_eq!(
+        args[1],
+        "export \"$@\" && exec ~/bin/jelly-j ui --title 'two words'"
+    );
+}
+
+#[test]
+fn launch_env_values_are_not_reported_recorded_or_traced() {
+    let mut state = ready_state_with(
+        1,
+        &[
+            ("launch_env.API_KEY", "sk-secret"),
+            ("record_events", "true"),
+        ],
+    );
+    request(&mut state, "r0", json!({ "op": "start_recording" }));
+    toggle(&mut state, "t1");
+    sync(&mut state);
+
+    for (pipe_id, op) in [
+        ("r1", json!({ "op": "get_state" })),
+        ("r2", json!({ "op": "get_recording" })),
+        ("r3", json!({ "op": "get_trace", "format": "json" })),
+    ] {
+        let response = request(&mut state, pipe_id, op);
+        assert_eq!(response["ok"], true, "{}", response);
+        let text = response.to_string();
+        assert!(!text.contains("sk-secret"), "{}", text);
+    }
+    let response = request(&mut state, "r4", json!({ "op": "get_state" }));
+    assert!(response.to_string().contains("API_KEY=<redacted>"));
+}
+
+#[test]
+fn launch_tokens_are_random_hex_and_never_reported_or_recorded() {
+    let mut state = ready_state_with(1, &[("record_events", "true")]);
+    request(&mut state, "r0", json!({ "op": "start_recording" }));
+    toggle(&mut state, "t1");
+    sync(&mut state);
+    let token = last_launch(&state).1.last().unwrap()[UI_TOKEN_ENV.len() + 1..].to_owned();
+    assert_eq!(token.len(), 32, "{}", token);
+    assert!(token.chars().all(|c| c.is_ascii_hexdigit()));
+    let pane_id = state.jelly_pane_id().unwrap();
+    let registered = request(
+        &mut state,
+        "r1",
+        json!({ "op": "register_ui_pane", "pane_id": pane_id, "token": token }),
+    );
+    assert_eq!(registered["ok"], true, "{}", registered);
+
+    for (pipe_id, op) in [
+        ("r2", json!({ "op": "get_state" })),
+        ("r3", json!({ "op": "get_recording" })),
+        ("r4", json!({ "op": "get_trace", "format": "json" })),
+    ] {
+        let response = request(&mut state, pipe_id, op);
+        assert_eq!(response["ok"], true, "{}", response);
+        let text = response.to_string();
+        assert!(!text.contains(&token), "{}", text);
+    }
+    let response = request(&mut state, "r5", json!({ "op": "get_state" }));
+    assert!(response
+        .to_string()
+        .contains(&format!("{}=<redacted>", UI_TOKEN_ENV)));
+}
+
+#[test]
+fn home_cwd_changes_directory_without_a_login_shell() {
+    let mut state = ready_state_with(1, &[("launch_cwd", "home")]);
+
+    toggle(&mut state, "t1");
+
+    let (path, args, cwd) = last_launch(&state);
+    assert_eq!(path, "sh");
+    assert_eq!(cwd, None);
+    assert_eq!(
+        args[..3],
+        ["-c", "export \"$@\" && cd && exec jelly-j ui", "jelly-j"]
+    );
+}
+
+#[test]
+fn unknown_launch_cwd_falls_back_to_focused() {
+    let mut state = ready_state_with(1, &[("launch_cwd", "fixed:"), ("launch_env.BAD NAME", "x")]);
+
+    let response = request(&mut state, "p1", json!({ "op": "get_state" }));
+    let butler = &response["result"]["butler"];
+    assert_eq!(butler["launch_cwd"], "focused");
+    assert_eq!(butler["launch_env"], json!([]));
+    let trace = request(
+        &mut state,
+        "p2",
+        json!({ "op": "get_trace", "format": "json" }),
+    );
+    let messages = trace["result"]["entries"]
+        .as_array()
+        .expect("trace entries")
+        .iter()
+        .filter_map(|entry| entry["message"].as_str())
+        .collect::<Vec<_>>();
+    assert!(messages.contains(&"unknown launch_cwd, using focused"));
+    assert!(messages.contains(&"invalid launch_env name, ignoring"));
+}
@@ -0,0 +1,134 @@
+mod common;
+
+use common::{ready_state, request, sync};
+use jelly_j::fake_host::HostCall;
+use serde_json::json;
+use zellij_tile::prelude::*;
+
+#[test]
+fn open_file_starts_an_editor_at_the_line_in_the_focused_panes_cwd() {
+    let mut state = ready_state(1);
+    let shell = state.host().panes[&0][0].id;
+    let event = state.host().cwd_changed(shell, "/home/dev/api");
+    state.update(event);
+
+    let response = request(
+        &mut state,
+        "o",
+        json!({ "op": "open_file", "path": "src/main.rs", "line": 42 }),
+    );
+
+    assert_eq!(response["ok"], true, "{}", response);
+    let pane_id = response["result"]["pane_id"].as_u64().unwrap() as u32;
+    assert!(state.host().calls.contains(&HostCall::OpenFile {
+        path: "src/main.rs".to_owned(),
+        line_number: Some(42),
+        cwd: Some("/home/dev/api".to_owned()),
+        floating: false,
+        in_place_of: None,
+    }));
+    sync(&mut state);
+    let info = request(
+        &mut state,
+        "i",
+        json!({ "op": "get_pane_info", "pane_id": pane_id }),
+    );
+    assert_eq!(info["result"]["is_floating"], false, "{}", info);
+}
+
+#[test]
+fn open_file_can_float_or_replace_a_pane() {
+    let mut state = ready_state(2);
+    let logs = state
+        .host_mut()
+        .add_terminal_pane(1, "logs", Some("tail -f app.log"));
+    sync(&mut state);
+
+    let floating = request(
+        &mut state,
+        "f",
+        json!({ "op": "open_file", "path": "/etc/hosts", "floating": true }),
+    );
+    let floating_id = floating["result"]["pane_id"].as_u64().unwrap() as u32;
+    assert!(state.host().pane(floating_id).unwrap().1.is_floating);
+
+    let replaced = request(
+        &mut state,
+        "r",
+        json!({ "op": "open_file", "path": "app.log", "in_place_of_pane_id": logs }),
+    );
+
+    let editor = replaced["result"]["pane_id"].as_u64().unwrap() as u32;
+    assert_eq!(state.host().pane(editor).unwrap().0, 1);
+    assert!(state.host().pane(logs).unwrap().1.is_suppressed);
+    assert!(state.host().calls.contains(&HostCall::OpenFile {
+        path: "app.log".to_owned(),
+        line_number: None,
+        cwd: None,
+        floating: false,
+        in_place_of: Some(PaneId::Terminal(logs)),
+    }));
+}
+
+#[test]
+fn open_file_rejects_bad_requests_and_reports_failures() {
+    let mut state = ready_state(1);
+    let shell = state.host().panes[&0][0].id;
+
+    let cases = [
+        (json!({ "op": "open_file", "path": "" }), "invalid_request"),
+        (
+            json!({ "op": "open_file", "path": "a.rs", "line": 0 }),
+            "invalid_request",
+        ),
+        (
+            json!({ "op": "open_file", "path": "a.rs", "floating": true, "in_place_of_pane_id": shell }),
+            "invalid_request",
+        ),
+        (
+            json!({ "op": "open_file", "path": "a.rs", "in_place_of_pane_id": 999 }),
+            "pane_not_found",
+        ),
+    ];
+    for (payload, code) in cases {
+        let response = request(&mut state, "x", payload.clone());
+        assert_eq!(response["code"], code, "{}", payload);
+    }
+    state.host_mut().launch_error = Some("no editor".to_owned());
+    let failed = request(
+        &mut state,
+        "x",
+        json!({ "op": "open_file", "path": "a.rs" }),
+    );
+    assert_eq!(failed["code"], "open_file_failed");
+}
+
+#[test]
+fn open_file_replaces_the_one_pane_a_selector_matches() {
+    let mut state = ready_state(1);
+    let editor = state.host_mut().add_terminal_pane(0, "vim", Some("vim"));
+    let other = state.host_mut().add_terminal_pane(0, "vim", Some("vim"));
+    sync(&mut state);
+    let tagged = request(
+        &mut state,
+        "t",
+        json!({ "op": "tag_pane", "pane_id": editor, "tags": ["editor"] }),
+    );
+    assert_eq!(tagged["ok"], true, "{}", tagged);
+
+    let replaced = request(
+        &mut state,
+        "r",
+        json!({ "op": "open_file", "path": "a.rs", "in_place_of": "tag:editor" }),
+    );
+    assert_eq!(replaced["ok"], true, "{}", replaced);
+    assert!(state.host().pane(editor).unwrap().1.is_suppressed);
+    assert!(!state.host().pane(other).unwrap().1.is_suppressed);
+
+    let ambiguous = request(
+        &mut state,
+        "a",
+        json!({ "op": "open_file", "path": "a.rs", "in_place_of_pane_id": "command:^vim" }),
+    );
+    assert_eq!(ambiguous["code"], "ambiguous_selector", "{}", ambiguous);
+}
@@ -0,0 +1,169 @@
+mod common;
+
+use common::{
+    attach_clients, jelly_pane, pipe_message, ready_state_with, request, sync, toggle,
+    KEYPRESS_GAP_MS,
+};
+use jelly_j::fake_host::{FakeHost, HostCall};
+use jelly_j::{State, CRASH_LOOP_FREE_RESTARTS};
+use serde_json::json;
+use std::collections::BTreeMap;
+use zellij_tile::prelude::*;
+
+/// The Jelly UI crashes, Zellij reports the held command pane, and then whatever the
+/// butler did about it.
+fn crash(state: &mut State<FakeHost>, pane_id: u32) {
+    state.host_mut().advance(1_000);
+    state.host_mut().exit_pane(pane_id, Some(1));
+    sync(state);
+    sync(state);
+}
+
+#[test]
+fn exited_pane_is_rerun_in_place_by_default() {
+    let mut state = ready_state_with(1, &[("on_exit", "rerun")]);
+    toggle(&mut state, "t1");
+    let pane_id = jelly_pane(&state).1.id;
+
+    crash(&mut state, pane_id);
+
+    assert!(state
+        .host()
+        .calls
+        .contains(&HostCall::RerunCommandPane(pane_id)));
+    assert_eq!(state.jelly_pane_id(), Some(pane_id));
+    assert!(!state.host().pane(pane_id).unwrap().1.exited);
+    let metrics = request(&mut state, "m1", json!({ "op": "get_metrics" }));
+    assert_eq!(metrics["result"]["pane_exits"]["detected"], 1);
+    assert_eq!(metrics["result"]["pane_exits"]["reruns"], 1);
+}
+
+#[test]
+fn close_policy_replaces_a_visible_exited_pane() {
+    let mut state = ready_state_with(1, &[("on_exit", "close")]);
+    toggle(&mut state, "t1");
+    let crashed = jelly_pane(&state).1.id;
+
+    crash(&mut state, crashed);
+
+    assert!(state
+        .host()
+        .calls
+        .contains(&HostCall::CloseTerminalPane(crashed)));
+    let (_, replacement) = jelly_pane(&state);
+    assert_ne!(replacement.id, crashed);
+    assert!(!replacement.is_suppressed);
+}
+
+#[test]
+fn keep_policy_leaves_the_corpse_and_launches_fresh_on_toggle() {
+    let mut state = ready_state_with(1, &[("on_exit", "keep")]);
+    toggle(&mut state, "t1");
+    let crashed = jelly_pane(&state).1.id;
+
+    crash(&mut state, crashed);
+    assert_eq!(state.jelly_pane_id(), None);
+    toggle(&mut state, "t2");
+
+    assert!(state.host().pane(crashed).unwrap().1.exited);
+    assert_ne!(jelly_pane(&state).1.id, crashed);
+    assert!(!state.host().calls.iter().any(|call| matches!(
+        call,
+        HostCall::RerunCommandPane(_) | HostCall::CloseTerminalPane(_)
+    )));
+}
+
+#[test]
+fn crash_loops_back_off_until_the_timer_fires() {
+    let mut state = ready_state_with(1, &[("on_exit", "rerun")]);
+    toggle(&mut state, "t1");
+    let pane_id = jelly_pane(&state).1.id;
+    for _ in 0..CRASH_LOOP_FREE_RESTARTS {
+        crash(&mut state, pane_id);
+    }
+    state.host_mut().take_calls();
+
+    crash(&mut state, pane_id);
+
+    let calls = state.host_mut().take_calls();
+    assert!(calls.contains(&HostCall::SetTimeout(1_000)));
+    assert!(!calls.contains(&HostCall::RerunCommandPane(pane_id)));
+    let response = request(&mut state, "s1", json!({ "op": "get_state" }));
+    assert_eq!(
+        response["result"]["butler"]["deferred_restarts"],
+        json!([pane_id])
+    );
+
+    state.host_mut().advance(1_000);
+    state.update(Event::Timer(1.0));
+
+    assert!(state
+        .host()
+        .calls
+        .contains(&HostCall::RerunCommandPane(pane_id)));
+    let metrics = request(&mut state, "m1", json!({ "op": "get_metrics" }));
+    assert_eq!(metrics["result"]["pane_exits"]["backoffs"], 1);
+    assert_eq!(
+        metrics["result"]["pane_exits"]["reruns"],
+        CRASH_LOOP_FREE_RESTARTS + 1
+    );
+}
+
+#[test]
+fn timers_rerun_only_the_deferred_restarts_that_are_due() {
+    // Two clients, each with its own Jelly pane.
+    let mut state = ready_state_with(2, &[("pane_policy", "per_client")]);
+    attach_clients(&mut state, &[(1, 0), (2, 1)]);
+    for (pipe_id, client_id) in [("t1", "1"), ("t2", "2")] {
+        state.host_mut().advance(KEYPRESS_GAP_MS);
+        let mut message = pipe_message("toggle", pipe_id, Some("toggle"));
+        message.args = BTreeMap::from([("client_id".to_owned(), client_id.to_owned())]);
+        state.pipe(message);
+        sync(&mut state);
+    }
+    let response = request(&mut state, "s0", json!({ "op": "get_state" }));
+    let client_panes = &response["result"]["butler"]["client_panes"];
+    let first = client_panes[0]["pane_id"].as_u64().unwrap() as u32;
+    let second = client_panes[1]["pane_id"].as_u64().unwrap() as u32;
+    for _ in 0..CRASH_LOOP_FREE_RESTARTS {
+        crash(&mut state, first);
+    }
+
+    // `first` backs off 1s; `second` exits 900ms later at the same 1s level.
+    crash(&mut state, first);
+    state.host_mut().advance(900);
+    state.host_mut().exit_pane(second, Some(1));
+    sync(&mut state);
+    state.host_mut().take_calls();
+
+    // At 1s only `first` is due; its rerun raises the next backoff to 2s.
+    state.host_mut().advance(100);
+    state.update(Event::Timer(1.0));
+    let calls = state.host_mut().take_calls();
+    assert!(calls.contains(&HostCall::RerunCommandPane(first)));
+    assert!(!calls.contains(&HostCall::RerunCommandPane(second)));
+    assert!(calls.contains(&HostCall::SetTimeout(900)));
+    sync(&mut state);
+    state.host_mut().exit_pane(first, Some(1));
+    sync(&mut state);
+    assert!(state
+        .host_mut()
+        .take_calls()
+        .contains(&HostCall::SetTimeout(2_000)));
+
+    state.host_mut().advance(900);
+    state.update(Event::Timer(0.9));
+    let calls = state.host_mut().take_calls();
+    assert!(calls.contains(&HostCall::RerunCommandPane(second)));
+    assert!(!calls.contains(&HostCall::RerunCommandPane(first)));
+    assert!(calls.contains(&HostCall::SetTimeout(1_100)));
+
+    state.host_mut().advance(1_100);
+    state.update(Event::Timer(1.1));
+    assert!(state
+        .host()
+        .calls
+        .contains(&HostCall::RerunCommandPane(first)));
+    let response = request(&mut state, "s1", json!({ "op": "get_state" }));
+    assert_eq!(response["result"]["butler"]["deferred_restarts"], json!([]));
+}
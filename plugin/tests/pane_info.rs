@@ -0,0 +1,70 @@
+mod common;
+
+use common::{ready_state, request, toggle};
+use serde_json::json;
+
+#[test]
+fn state_reports_cwd_and_foreground_command_per_pane() {
+    let mut state = ready_state(2);
+    let shell_0 = state.host().panes[&0][0].id;
+    let shell_1 = state.host().panes[&1][0].id;
+    state.host_mut().add_client(1, shell_0);
+    state.host_mut().clients[0].running_command = "cargo watch -x test".to_owned();
+    let clients_update = state.host().clients_update();
+    state.update(clients_update);
+    let cwd_changed = state.host().cwd_changed(shell_1, "/home/me/api");
+    state.update(cwd_changed);
+
+    let response = request(&mut state, "p1", json!({ "op": "get_state" }));
+
+    let panes = response["result"]["panes"].as_array().unwrap();
+    let pane = |id: u32| {
+        panes
+            .iter()
+            .find(|pane| pane["id"] == id)
+            .expect("pane is listed")
+    };
+    assert_eq!(pane(shell_0)["foreground_command"], "cargo watch -x test");
+    assert_eq!(pane(shell_0)["cwd"], json!(null));
+    assert_eq!(pane(shell_1)["cwd"], "/home/me/api");
+    // Without a client report, a plain shell pane falls back to its start command.
+    assert_eq!(pane(shell_1)["foreground_command"], "zsh");
+}
+
+#[test]
+fn get_pane_info_returns_detail_for_one_pane() {
+    let mut state = ready_state(1);
+    toggle(&mut state, "t1");
+    let jelly = state.jelly_pane_id().unwrap();
+    let cwd_changed = state.host().cwd_changed(jelly, "/home/me");
+    state.update(cwd_changed);
+
+    let response = request(
+        &mut state,
+        "p1",
+        json!({ "op": "get_pane_info", "pane_id": jelly }),
+    );
+
+    assert_eq!(response["ok"], true);
+    let info = &response["result"];
+    assert_eq!(info["id"], jelly);
+    assert_eq!(info["cwd"], "/home/me");
+    assert_eq!(info["is_jelly"], true);
+    assert_eq!(info["registered_ui"], false);
+    assert_eq!(info["is_floating"], true);
+    assert!(info["geometry"]["columns"].is_number());
+}
+
+#[test]
+fn get_pane_info_rejects_unknown_panes() {
+    let mut state = ready_state(1);
+
+    let response = request(
+        &mut state,
+        "p1",
+        json!({ "op": "get_pane_info", "pane_id": 999 }),
+    );
+
+    assert_eq!(response["ok"], false);
+    assert_eq!(response["code"], "pane_not_found");
+}
@@ -0,0 +1,87 @@
+mod common;
+
+use common::{ready_state_with, request, sync, toggle};
+use jelly_j::fake_host::{FakeHost, HostCall};
+use jelly_j::State;
+use serde_json::json;
+use zellij_tile::prelude::*;
+
+const PER_TAB: &[(&str, &str)] = &[("pane_policy", "per_tab")];
+
+/// `(tab_index, pane_id)` pairs from `get_state`.
+fn tab_panes(state: &mut State<FakeHost>, pipe_id: &str) -> Vec<(u64, u32)> {
+    let response = request(state, pipe_id, json!({ "op": "get_state" }));
+    response["result"]["butler"]["tab_panes"]
+        .as_array()
+        .expect("tab_panes is an array")
+        .iter()
+        .map(|entry| {
+            (
+                entry["tab_index"].as_u64().unwrap(),
+                entry["pane_id"].as_u64().unwrap() as u32,
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn each_tab_gets_its_own_pinned_pane() {
+    let mut state = ready_state_with(2, PER_TAB);
+    toggle(&mut state, "t1");
+    state.host_mut().focus_tab(1);
+    sync(&mut state);
+    toggle(&mut state, "t2");
+
+    let pinned = tab_panes(&mut state, "p1");
+    assert_eq!(pinned.len(), 2);
+    for (tab_index, pane_id) in &pinned {
+        assert_eq!(state.host().pane(*pane_id).unwrap().0 as u64, *tab_index);
+    }
+    let calls = state.host_mut().take_calls();
+    assert!(!calls.iter().any(|call| matches!(
+        call,
+        HostCall::CloseTerminalPane(_) | HostCall::BreakPanesToTab { .. }
+    )));
+}
+
+#[test]
+fn toggling_hides_only_the_current_tabs_pane() {
+    let mut state = ready_state_with(2, PER_TAB);
+    toggle(&mut state, "t1");
+    state.host_mut().focus_tab(1);
+    sync(&mut state);
+    toggle(&mut state, "t2");
+    let pinned = tab_panes(&mut state, "p1");
+
+    toggle(&mut state, "t3");
+
+    let (tab0_pane, tab1_pane) = (pinned[0].1, pinned[1].1);
+    assert!(state.host().pane(tab1_pane).unwrap().1.is_suppressed);
+    assert!(!state.host().pane(tab0_pane).unwrap().1.is_suppressed);
+}
+
+#[test]
+fn closing_a_tab_remaps_later_tabs_and_forgets_its_pane() {
+    let mut state = ready_state_with(3, PER_TAB);
+    toggle(&mut state, "t1");
+    state.host_mut().focus_tab(2);
+    sync(&mut state);
+    toggle(&mut state, "t2");
+    let pinned = tab_panes(&mut state, "p1");
+    assert_eq!(
+        pinned.iter().map(|(tab, _)| *tab).collect::<Vec<_>>(),
+        [0, 2]
+    );
+    let last_tab_pane = pinned[1].1;
+
+    state.host_mut().close_tab(0);
+    sync(&mut state);
+
+    assert_eq!(tab_panes(&mut state, "p2"), [(1, last_tab_pane)]);
+    state.host_mut().take_calls();
+    toggle(&mut state, "t3");
+    assert!(state
+        .host()
+        .calls
+        .contains(&HostCall::HidePane(PaneId::Terminal(last_tab_pane))));
+}
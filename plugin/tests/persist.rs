@@ -0,0 +1,83 @@
+mod common;
+
+use common::{jelly_pane, ready_state, request, sync, toggle};
+use jelly_j::fake_host::{FakeHost, HostCall};
+use jelly_j::State;
+use serde_json::json;
+use std::collections::BTreeMap;
+use zellij_tile::prelude::*;
+
+const STATE_PATH: &str = "/data/butler-state.json";
+
+/// Loads a new butler on the same host, as a wasm reload or upgrade would.
+fn reload(state: &mut State<FakeHost>) -> State<FakeHost> {
+    let host = std::mem::take(state.host_mut());
+    let mut reloaded = State::new(host);
+    reloaded.load(BTreeMap::new());
+    reloaded.update(Event::PermissionRequestResult(PermissionStatus::Granted));
+    sync(&mut reloaded);
+    reloaded.host_mut().take_calls();
+    reloaded
+}
+
+#[test]
+fn reload_restores_the_tracked_jelly_pane_instead_of_launching_another() {
+    let mut state = ready_state(1);
+    toggle(&mut state, "t1");
+    let (_, launched) = jelly_pane(&state);
+
+    let mut reloaded = reload(&mut state);
+
+    assert_eq!(reloaded.jelly_pane_id(), Some(launched.id));
+    toggle(&mut reloaded, "t2");
+    let calls = reloaded.host().calls.clone();
+    assert!(calls.contains(&HostCall::HidePane(PaneId::Terminal(launched.id))));
+    assert!(!calls.iter().any(|call| matches!(
+        call,
+        HostCall::OpenCommandPaneFloating { .. } | HostCall::CloseTerminalPane(_)
+    )));
+
+    let response = request(&mut reloaded, "p1", json!({ "op": "get_state" }));
+    let butler = &response["result"]["butler"];
+    assert_eq!(butler["restored_jelly_pane"], true);
+    assert_eq!(butler["lifetime"]["loads"], 2);
+    assert_eq!(butler["lifetime"]["launches"], 1);
+    assert_eq!(butler["lifetime"]["toggles"], 2);
+}
+
+#[test]
+fn persisted_pane_is_discarded_when_it_no_longer_looks_like_jelly() {
+    let mut state = ready_state(1);
+    toggle(&mut state, "t1");
+    let (_, launched) = jelly_pane(&state);
+    // The session was recreated and the id now belongs to a plain shell.
+    state.host_mut().remove_pane(launched.id);
+    let shell_id = state.host().pane(0).map(|(_, pane)| pane.id).unwrap();
+    let persisted = state.host().files[STATE_PATH].replace(
+        &format!("\"jelly_pane_id\":{}", launched.id),
+        &format!("\"jelly_pane_id\":{}", shell_id),
+    );
+    state
+        .host_mut()
+        .files
+        .insert(STATE_PATH.to_owned(), persisted);
+
+    let reloaded = reload(&mut state);
+
+    assert_eq!(reloaded.jelly_pane_id(), None);
+    assert!(reloaded.host().files[STATE_PATH].contains("\"jelly_pane_id\":null"));
+}
+
+#[test]
+fn persistence_can_be_disabled() {
+    let mut state = State::new(FakeHost::with_tabs(1));
+    state.load(BTreeMap::from([(
+        "persist_state".to_owned(),
+        "false".to_owned(),
+    )]));
+    state.update(Event::PermissionRequestResult(PermissionStatus::Granted));
+    sync(&mut state);
+    toggle(&mut state, "t1");
+
+    assert!(!state.host().files.contains_key(STATE_PATH));
+}
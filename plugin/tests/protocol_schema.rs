@@ -0,0 +1,52 @@
+//! Generator and drift check for the committed protocol artifacts.
+//!
+//! `UPDATE_SCHEMA=1 cargo test --test protocol_schema` rewrites
+//! `schema/butler-protocol.json` and `../src/butlerProtocol.generated.ts` from
+//! `src/protocol.rs`; without it the test fails if either file is stale.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn artifacts() -> Vec<(PathBuf, String)> {
+    let pretty = serde_json::to_string_pretty(&jelly_j::protocol_schema())
+        .expect("serialize protocol schema");
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut artifacts = vec![(
+        manifest_dir.join("schema/butler-protocol.json"),
+        format!("{}\n", pretty),
+    )];
+    // The TS artifact only exists inside the full repository checkout.
+    let ts_dir = manifest_dir.join("../src");
+    if ts_dir.is_dir() {
+        artifacts.push((
+            ts_dir.join("butlerProtocol.generated.ts"),
+            format!(
+                "// @generated by plugin/tests/protocol_schema.rs from plugin/src/protocol.rs. \
+                 Do not edit.\nexport const butlerProtocolSchema = {} as const;\n",
+                pretty
+            ),
+        ));
+    }
+    artifacts
+}
+
+#[test]
+fn committed_protocol_artifacts_match_the_schema() {
+    let update = std::env::var_os("UPDATE_SCHEMA").is_some_and(|value| value == "1");
+    for (path, contents) in artifacts() {
+        let existing = fs::read_to_string(&path).ok();
+        if existing.as_deref() == Some(contents.as_str()) {
+            continue;
+        }
+        if update {
+            fs::create_dir_all(path.parent().unwrap()).expect("create artifact directory");
+            fs::write(&path, contents)
+                .unwrap_or_else(|err| panic!("write {}: {}", path.display(), err));
+        } else {
+            panic!(
+                "{} is stale; run `UPDATE_SCHEMA=1 cargo test --test protocol_schema`",
+                path.display()
+            );
+        }
+    }
+}
@@ -0,0 +1,240 @@
+mod common;
+
+use common::{pipe_message, ready_state_with, request, sync, toggle};
+use jelly_j::fake_host::{FakeHost, HostCall};
+use jelly_j::recording::parse_recording;
+use jelly_j::replay::replay;
+use jelly_j::State;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use zellij_tile::prelude::*;
+
+fn recording_state(tab_count: usize) -> State<FakeHost> {
+    let mut state = State::new(FakeHost::with_tabs(tab_count));
+    state.load(BTreeMap::from([(
+        "record_events".to_owned(),
+        "true".to_owned(),
+    )]));
+    state.update(Event::PermissionRequestResult(PermissionStatus::Granted));
+    sync(&mut state);
+    state
+}
+
+/// Host calls that change the workspace, ignoring pipe plumbing.
+fn workspace_calls(calls: &[HostCall]) -> Vec<HostCall> {
+    calls
+        .iter()
+        .filter(|call| {
+            !matches!(
+                call,
+                HostCall::CliPipeOutput { .. } | HostCall::UnblockCliPipeInput(_)
+            )
+        })
+        .cloned()
+        .collect()
+}
+
+#[test]
+fn replaying_a_recording_reproduces_the_host_calls() {
+    let mut state = recording_state(2);
+    toggle(&mut state, "p1");
+    state.host_mut().focus_tab(1);
+    sync(&mut state);
+    toggle(&mut state, "p2");
+    toggle(&mut state, "p3");
+    let original = workspace_calls(&state.host().calls);
+
+    let recording = request(&mut state, "dump", json!({ "op": "get_recording" }));
+    let entries = parse_recording(&recording.to_string()).unwrap();
+    let replayed = replay(&entries);
+
+    assert_eq!(workspace_calls(&replayed.host().calls), original);
+    assert_eq!(replayed.jelly_pane_id(), state.jelly_pane_id());
+}
+
+#[test]
+fn recording_started_mid_session_is_seeded_with_current_caches() {
+    let mut state = common::ready_state(2);
+    toggle(&mut state, "p1");
+    request(&mut state, "start", json!({ "op": "start_recording" }));
+    state.host_mut().take_calls();
+    state.host_mut().focus_tab(1);
+    sync(&mut state);
+    toggle(&mut state, "p2");
+    let original = workspace_calls(&state.host().calls);
+
+    let recording = request(&mut state, "dump", json!({ "op": "stop_recording" }));
+    let entries = parse_recording(&recording.to_string()).unwrap();
+    let replayed = replay(&entries);
+
+    // The seed replays `load`, which re-subscribes; everything after must match.
+    let replayed_calls = workspace_calls(&replayed.host().calls);
+    assert!(replayed_calls.ends_with(&original));
+    assert_eq!(
+        request(&mut state, "after", json!({ "op": "get_recording" }))["result"]["recording"],
+        false
+    );
+}
+
+#[test]
+fn field_trace_cross_tab_toggle_moves_jelly_floating() {
+    let entries = parse_recording(include_str!("traces/cross-tab-toggle.json")).unwrap();
+
+    let state = replay(&entries);
+
+    let jelly = PaneId::Terminal(2);
+    let calls = workspace_calls(&state.host().calls);
+    let break_at = calls
+        .iter()
+        .position(|call| {
+            call == &HostCall::BreakPanesToTab {
+                pane_ids: vec![jelly],
+                tab_index: 1,
+            }
+        })
+        .expect("jelly pane broken into the active tab");
+    assert_eq!(calls[break_at - 1], HostCall::HidePane(jelly));
+    assert_eq!(calls[break_at + 1], HostCall::TogglePaneEmbedOrEject(jelly));
+    assert_eq!(
+        calls[break_at + 2],
+        HostCall::ShowPane {
+            pane_id: jelly,
+            should_float_if_hidden: true,
+            should_focus_pane: true,
+        }
+    );
+}
+
+#[test]
+fn events_are_not_recorded_unless_enabled() {
+    let mut state = common::ready_state(1);
+    state.pipe(pipe_message("toggle", "p1", Some("toggle")));
+
+    let response = request(&mut state, "dump", json!({ "op": "get_recording" }));
+
+    assert_eq!(response["result"]["recording"], false);
+    assert_eq!(response["result"]["entries"], json!([]));
+}
+
+#[test]
+fn replaying_scratch_launches_keeps_jelly_launches_in_step() {
+    let mut state = State::new(FakeHost::with_tabs(1));
+    state.load(BTreeMap::from([
+        ("record_events".to_owned(), "true".to_owned()),
+        ("scratch.top.command".to_owned(), "htop".to_owned()),
+    ]));
+    state.update(Event::PermissionRequestResult(PermissionStatus::Granted));
+    sync(&mut state);
+    request(
+        &mut state,
+        "s1",
+        json!({ "op": "toggle_scratch", "name": "top" }),
+    );
+    sync(&mut state);
+    toggle(&mut state, "p1");
+    let original = workspace_calls(&state.host().calls);
+
+    let recording = request(&mut state, "dump", json!({ "op": "get_recording" }));
+    let entries = parse_recording(&recording.to_string()).unwrap();
+    let replayed = replay(&entries);
+
+    assert_eq!(workspace_calls(&replayed.host().calls), original);
+    assert_eq!(replayed.jelly_pane_id(), state.jelly_pane_id());
+}
+
+#[test]
+fn recorded_panes_and_clients_round_trip_every_field() {
+    let mut state = recording_state(1);
+    let pane_id = state.host_mut().add_terminal_pane(0, "htop", Some("htop"));
+    {
+        let pane = state.host_mut().pane_mut(pane_id).unwrap();
+        pane.is_held = true;
+        pane.is_fullscreen = true;
+        pane.pane_x = 3;
+        pane.pane_y = 4;
+        pane.pane_columns = 80;
+        pane.pane_rows = 24;
+        pane.pane_content_x = 4;
+        pane.pane_content_y = 5;
+        pane.pane_content_columns = 78;
+        pane.pane_content_rows = 22;
+        pane.cursor_coordinates_in_pane = Some((1, 2));
+    }
+    state.host_mut().add_client(1, pane_id);
+    let manifest = state.host().manifest();
+    let Event::ListClients(mut clients) = state.host().clients_update() else {
+        unreachable!()
+    };
+    clients[0].running_command = "htop".to_owned();
+    state.update(Event::PaneUpdate(manifest.clone()));
+    state.update(Event::ListClients(clients.clone()));
+
+    let recording = request(&mut state, "dump", json!({ "op": "get_recording" }));
+    let events = parse_recording(&recording.to_string())
+        .unwrap()
+        .iter()
+        .filter_map(|entry| entry.event.to_event())
+        .collect::<Vec<_>>();
+
+    assert!(events
+        .iter()
+        .any(|event| matches!(event, Event::PaneUpdate(replayed) if *replayed == manifest)));
+    assert!(events
+        .iter()
+        .any(|event| matches!(event, Event::ListClients(replayed) if *replayed == clients)));
+}
+
+#[test]
+fn recorded_tabs_keep_focused_clients_and_floating_visibility() {
+    let mut state = recording_state(2);
+    let Event::TabUpdate(mut tabs) = state.host().tab_update() else {
+        unreachable!()
+    };
+    tabs[1].other_focused_clients = vec![2, 3];
+    tabs[1].are_floating_panes_visible = true;
+    state.update(Event::TabUpdate(tabs.clone()));
+
+    let recording = request(&mut state, "dump", json!({ "op": "get_recording" }));
+    let entries = parse_recording(&recording.to_string()).unwrap();
+
+    assert!(entries.iter().any(|entry| matches!(
+        entry.event.to_event(),
+        Some(Event::TabUpdate(replayed)) if replayed == tabs
+    )));
+}
+
+#[test]
+fn recorded_filesystem_changes_replay_into_the_same_summaries() {
+    let mut state = ready_state_with(1, &[("record_events", "true"), ("fs_watch", "on")]);
+    state.update(Event::FileSystemCreate(vec![(
+        PathBuf::from("/host/src/new.rs"),
+        None,
+    )]));
+    state.update(Event::FileSystemDelete(vec![
+        (PathBuf::from("/host/src/old.rs"), None),
+        (PathBuf::from("/host/README.md"), None),
+    ]));
+    state.host_mut().advance(1_000);
+    let original = request(&mut state, "f", json!({ "op": "get_fs_changes" }));
+
+    let recording = request(&mut state, "dump", json!({ "op": "get_recording" }));
+    let entries = parse_recording(&recording.to_string()).unwrap();
+    assert!(entries.iter().any(|entry| matches!(
+        entry.event.to_event(),
+        Some(Event::FileSystemDelete(paths)) if paths == [
+            (PathBuf::from("/host/src/old.rs"), None),
+            (PathBuf::from("/host/README.md"), None),
+        ]
+    )));
+    let mut replayed = replay(&entries);
+    let summaries = request(&mut replayed, "f", json!({ "op": "get_fs_changes" }));
+    assert_eq!(
+        summaries["result"]["summaries"],
+        original["result"]["summaries"]
+    );
+    assert_eq!(
+        original["result"]["summaries"][0]["deleted"],
+        json!(["README.md", "src/old.rs"])
+    );
+}
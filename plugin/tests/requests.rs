@@ -0,0 +1,192 @@
+mod common;
+
+use common::{pipe_message, ready_state, request, sync};
+use jelly_j::fake_host::{FakeHost, HostCall};
+use jelly_j::State;
+use serde_json::json;
+use std::collections::BTreeMap;
+use zellij_tile::prelude::*;
+
+#[test]
+fn responses_echo_the_request_id() {
+    let mut state = ready_state(1);
+
+    let response = request(&mut state, "p1", json!({ "op": "ping", "id": "abc-1" }));
+
+    assert_eq!(response["ok"], true);
+    assert_eq!(response["id"], "abc-1");
+}
+
+#[test]
+fn hello_reports_protocol_version_and_ops() {
+    let mut state = ready_state(1);
+
+    let response = request(&mut state, "p1", json!({ "op": "hello" }));
+
+    let result = &response["result"];
+    assert!(result["protocol_version"].as_u64().is_some());
+    assert_eq!(result["permission_status"], "granted");
+    assert!(result["supported_ops"]
+        .as_array()
+        .unwrap()
+        .contains(&json!("batch")));
+}
+
+#[test]
+fn unknown_ops_return_unsupported_op() {
+    let mut state = ready_state(1);
+
+    let response = request(&mut state, "p1", json!({ "op": "teleport", "id": 7 }));
+
+    assert_eq!(response["ok"], false);
+    assert_eq!(response["code"], "unsupported_op");
+    assert_eq!(response["id"], 7);
+}
+
+#[test]
+fn stateful_requests_wait_for_readiness() {
+    let mut state = State::new(FakeHost::with_tabs(1));
+    state.load(BTreeMap::new());
+
+    let response = request(&mut state, "p1", json!({ "op": "get_state" }));
+
+    assert_eq!(response["code"], "not_ready");
+}
+
+#[test]
+fn atomic_batch_rejects_bad_references_before_any_side_effect() {
+    let mut state = ready_state(2);
+
+    let response = request(
+        &mut state,
+        "p1",
+        json!({
+            "op": "batch",
+            "atomic": true,
+            "requests": [
+                { "op": "rename_tab", "position": 0, "name": "api" },
+                { "op": "rename_tab", "position": 9, "name": "nope" },
+            ],
+        }),
+    );
+
+    assert_eq!(response["code"], "validation_failed");
+    assert_eq!(response["failures"][0]["index"], 1);
+    assert!(!state
+        .host()
+        .calls
+        .iter()
+        .any(|call| matches!(call, HostCall::RenameTab { .. })));
+}
+
+#[test]
+fn batch_runs_steps_in_order_and_stops_on_error() {
+    let mut state = ready_state(2);
+
+    let response = request(
+        &mut state,
+        "p1",
+        json!({
+            "op": "batch",
+            "stop_on_error": true,
+            "requests": [
+                { "op": "rename_tab", "position": 0, "name": "api" },
+                { "op": "hide_pane", "pane_id": 999 },
+                { "op": "rename_tab", "position": 1, "name": "web" },
+            ],
+        }),
+    );
+
+    let result = &response["result"];
+    assert_eq!(result["completed"], 2);
+    assert_eq!(result["stopped_early"], true);
+    assert_eq!(result["steps"][1]["code"], "pane_not_found");
+    assert_eq!(state.host().tabs[0].name, "api");
+    assert_eq!(state.host().tabs[1].name, "Tab #2");
+}
+
+#[test]
+fn get_trace_filters_structured_entries() {
+    let mut state = ready_state(1);
+    request(
+        &mut state,
+        "p1",
+        json!({ "op": "rename_tab", "position": 0, "name": "api" }),
+    );
+
+    let response = request(
+        &mut state,
+        "p2",
+        json!({ "op": "get_trace", "category": "request", "format": "json" }),
+    );
+
+    let entries = response["result"]["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0]["message"], "rename_tab");
+    assert_eq!(entries[0]["level"], "info");
+    assert_eq!(entries[0]["fields"]["name"], "api");
+
+    let since_seq = entries[0]["seq"].as_u64().unwrap();
+    let response = request(
+        &mut state,
+        "p3",
+        json!({ "op": "get_trace", "since_seq": since_seq }),
+    );
+    let lines = response["result"]["entries"].as_array().unwrap();
+    assert!(lines.iter().all(|line| line.is_string()));
+    assert!(lines
+        .iter()
+        .any(|line| line.as_str().unwrap().ends_with("pipe request")));
+
+    let response = request(
+        &mut state,
+        "p4",
+        json!({ "op": "get_trace", "level": "warn" }),
+    );
+    assert_eq!(response["result"]["entries"], json!([]));
+}
+
+#[test]
+fn get_metrics_counts_requests_errors_and_toggle_latency() {
+    let mut state = State::new(FakeHost::with_tabs(1));
+    state.load(BTreeMap::new());
+    // Toggle before permissions arrive; it runs once the butler becomes ready.
+    state.pipe(pipe_message("toggle", "t1", Some("toggle")));
+    state.pipe(pipe_message("toggle", "t1", Some("toggle")));
+    state.host_mut().advance(30);
+    state.update(Event::PermissionRequestResult(PermissionStatus::Granted));
+    sync(&mut state);
+    request(&mut state, "p1", json!({ "op": "ping" }));
+    request(
+        &mut state,
+        "p2",
+        json!({ "op": "hide_pane", "pane_id": 999 }),
+    );
+
+    let response = request(&mut state, "p3", json!({ "op": "get_metrics" }));
+
+    let metrics = &response["result"];
+    assert_eq!(metrics["requests_by_op"]["ping"], 1);
+    assert_eq!(metrics["requests_by_op"]["hide_pane"], 1);
+    assert_eq!(metrics["errors_by_op"]["hide_pane"], 1);
+    assert_eq!(metrics["errors_by_code"]["pane_not_found"], 1);
+    assert_eq!(metrics["toggles"]["received"], 2);
+    assert_eq!(metrics["toggles"]["dedup_window_ignored"], 1);
+    assert_eq!(metrics["toggles"]["executed"], 1);
+    let launch = &metrics["toggle_latency_ms"]["launch"];
+    assert_eq!(launch["count"], 1);
+    assert_eq!(launch["max_ms"], 30);
+    let bounds = metrics["latency_bucket_bounds_ms"].as_array().unwrap();
+    let bucket = bounds
+        .iter()
+        .position(|bound| bound.as_u64().unwrap() >= 30)
+        .unwrap();
+    assert_eq!(launch["counts"][bucket], 1);
+    assert_eq!(metrics["events"]["pipe_toggle"]["count"], 2);
+    assert!(
+        metrics["events"]["pane_update"]["per_minute"]
+            .as_f64()
+            .unwrap()
+            > 0.0
+    );
+}
@@ -0,0 +1,294 @@
+mod common;
+
+use common::{ready_state, ready_state_with, request, sync};
+use jelly_j::fake_host::{FakeHost, HostCall};
+use jelly_j::State;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use zellij_tile::prelude::*;
+
+const RULES_PATH: &str = "/data/butler-rules.json";
+
+fn trace_messages(state: &mut State<FakeHost>) -> Vec<Value> {
+    let response = request(
+        state,
+        "trace",
+        json!({ "op": "get_trace", "category": "rule", "format": "json" }),
+    );
+    response["result"]["entries"].as_array().unwrap().clone()
+}
+
+#[test]
+fn added_rules_fire_on_pane_exit_and_are_saved() {
+    let mut state = ready_state(1);
+    let watcher = state
+        .host_mut()
+        .add_terminal_pane(0, "cargo", Some("cargo watch -x test"));
+    let quiet = state.host_mut().add_terminal_pane(0, "ls", Some("ls"));
+    sync(&mut state);
+    let rule = json!({
+        "id": "watch-failed",
+        "when": { "event": "pane_exited", "command": "cargo watch", "nonzero_only": true },
+        "actions": [{ "op": "rename_pane", "pane_id": "$pane_id", "name": "tests failed" }],
+        "trace_message": "cargo watch failed",
+    });
+
+    let response = request(&mut state, "p1", json!({ "op": "add_rule", "rule": rule }));
+    assert_eq!(response["ok"], true, "{}", response);
+    let saved: Value = serde_json::from_str(&state.host().files[RULES_PATH]).unwrap();
+    assert_eq!(saved[0]["id"], "watch-failed");
+
+    state.host_mut().exit_pane(quiet, Some(1));
+    state.host_mut().exit_pane(watcher, Some(101));
+    state.host_mut().take_calls();
+    sync(&mut state);
+
+    assert_eq!(
+        state
+            .host()
+            .calls
+            .iter()
+            .filter(|call| matches!(call, HostCall::RenamePane { .. }))
+            .collect::<Vec<_>>(),
+        [&HostCall::RenamePane {
+            pane_id: PaneId::Terminal(watcher),
+            name: "tests failed".to_owned(),
+        }]
+    );
+    let traced = trace_messages(&mut state)
+        .into_iter()
+        .find(|entry| entry["message"] == "rule_trace_message")
+        .expect("rule traced its message");
+    assert_eq!(traced["level"], "warn");
+    assert_eq!(traced["fields"]["message"], "cargo watch failed");
+    let listed = request(&mut state, "p2", json!({ "op": "list_rules" }));
+    assert_eq!(listed["result"]["rules"][0]["source"], "file");
+    assert_eq!(listed["result"]["rules"][0]["fired"], 1);
+}
+
+#[test]
+fn config_rules_fire_when_a_named_tab_appears() {
+    let rules = json!([{
+        "id": "logs-tab",
+        "when": { "event": "tab_appeared", "name": "logs" },
+        "actions": [{ "op": "rename_tab", "position": "$tab_position", "name": "logs (watched)" }],
+    }]);
+    let mut state = State::new(FakeHost::with_tabs(2));
+    state.load(BTreeMap::from([("rules".to_owned(), rules.to_string())]));
+    state.update(Event::PermissionRequestResult(PermissionStatus::Granted));
+    sync(&mut state);
+    state.host_mut().take_calls();
+
+    state.host_mut().tabs[1].name = "logs".to_owned();
+    sync(&mut state);
+    sync(&mut state);
+
+    let renames = state
+        .host()
+        .calls
+        .iter()
+        .filter(|call| matches!(call, HostCall::RenameTab { .. }))
+        .cloned()
+        .collect::<Vec<_>>();
+    assert_eq!(
+        renames,
+        [HostCall::RenameTab {
+            tab_position: 2,
+            name: "logs (watched)".to_owned(),
+        }]
+    );
+}
+
+#[test]
+fn rule_edits_are_validated() {
+    // `notify` is the old name of `trace_message`.
+    let rules = json!([{
+        "id": "pinned",
+        "when": { "event": "pane_opened" },
+        "notify": "new pane",
+    }]);
+    let mut state = State::new(FakeHost::with_tabs(1));
+    state.load(BTreeMap::from([("rules".to_owned(), rules.to_string())]));
+    state.update(Event::PermissionRequestResult(PermissionStatus::Granted));
+    sync(&mut state);
+
+    let nested = json!({
+        "id": "recursive",
+        "when": { "event": "pane_opened" },
+        "actions": [{ "op": "add_rule", "rule": {} }],
+    });
+    let response = request(
+        &mut state,
+        "p1",
+        json!({ "op": "add_rule", "rule": nested }),
+    );
+    assert_eq!(response["code"], "invalid_rule");
+
+    let duplicate = json!({ "id": "pinned", "when": { "event": "pane_opened" }, "notify": "x" });
+    let response = request(
+        &mut state,
+        "p2",
+        json!({ "op": "add_rule", "rule": duplicate }),
+    );
+    assert_eq!(response["code"], "rule_exists");
+
+    let response = request(
+        &mut state,
+        "p3",
+        json!({ "op": "remove_rule", "id": "pinned" }),
+    );
+    assert_eq!(response["code"], "rule_readonly");
+    let response = request(
+        &mut state,
+        "p4",
+        json!({ "op": "remove_rule", "id": "nope" }),
+    );
+    assert_eq!(response["code"], "rule_not_found");
+}
+
+#[test]
+fn tab_rules_may_not_use_the_pane_placeholder() {
+    let mut state = common::ready_state(1);
+    let rule = json!({
+        "id": "logs",
+        "when": { "event": "tab_appeared", "name": "logs" },
+        "actions": [{ "op": "rename_pane", "pane_id": "$pane_id", "name": "tail" }],
+    });
+    let response = request(&mut state, "p1", json!({ "op": "add_rule", "rule": rule }));
+    assert_eq!(response["code"], "invalid_rule");
+    assert!(response["error"]
+        .as_str()
+        .unwrap()
+        .contains("tab_appeared has no pane"));
+
+    let rule = json!({
+        "id": "logs",
+        "when": { "event": "tab_appeared", "name": "logs" },
+        "actions": [{ "op": "rename_tab", "position": "$tab_position", "name": "logs*" }],
+    });
+    let response = request(&mut state, "p2", json!({ "op": "add_rule", "rule": rule }));
+    assert_eq!(response["ok"], true, "{}", response);
+}
+
+fn count_calls(state: &State<FakeHost>, matching: impl Fn(&HostCall) -> bool) -> usize {
+    state
+        .host()
+        .calls
+        .iter()
+        .filter(|call| matching(call))
+        .count()
+}
+
+#[test]
+fn panes_opened_by_rule_actions_do_not_fire_rules_again() {
+    let rules = json!([{
+        "id": "notes-for-every-pane",
+        "when": { "event": "pane_opened" },
+        "actions": [{ "op": "open_file", "path": "/host/NOTES.md", "floating": true }],
+    }]);
+    let mut state = ready_state_with(1, &[("rules", &rules.to_string())]);
+
+    state.host_mut().add_terminal_pane(0, "htop", Some("htop"));
+    for _ in 0..5 {
+        state.host_mut().advance(10_000);
+        sync(&mut state);
+    }
+
+    assert_eq!(
+        count_calls(&state, |call| matches!(call, HostCall::OpenFile { .. })),
+        1
+    );
+    assert!(trace_messages(&mut state)
+        .iter()
+        .any(|entry| entry["message"] == "rule_skipped_rule_pane"));
+}
+
+#[test]
+fn rules_wait_out_their_cooldown_between_updates() {
+    let rules = json!([{
+        "id": "rename-new",
+        "when": { "event": "pane_opened" },
+        "actions": [{ "op": "rename_pane", "pane_id": "$pane_id", "name": "new" }],
+        "cooldown_ms": 5000,
+    }]);
+    let mut state = ready_state_with(1, &[("rules", &rules.to_string())]);
+    let renames = |state: &State<FakeHost>| {
+        count_calls(state, |call| matches!(call, HostCall::RenamePane { .. }))
+    };
+
+    // Two panes in one update both fire.
+    state.host_mut().add_terminal_pane(0, "a", Some("a"));
+    state.host_mut().add_terminal_pane(0, "b", Some("b"));
+    sync(&mut state);
+    assert_eq!(renames(&state), 2);
+
+    state.host_mut().advance(1_000);
+    state.host_mut().add_terminal_pane(0, "c", Some("c"));
+    sync(&mut state);
+    assert_eq!(renames(&state), 2, "still cooling down");
+
+    state.host_mut().advance(5_000);
+    state.host_mut().add_terminal_pane(0, "d", Some("d"));
+    sync(&mut state);
+    assert_eq!(renames(&state), 3);
+}
+
+#[test]
+fn a_tab_rule_floats_the_new_tabs_panes() {
+    let rules = json!([{
+        "id": "float-logs",
+        "when": { "event": "tab_appeared", "name": "logs" },
+        "actions": [{ "op": "float_panes", "selector": "tab:$tab_position" }],
+    }]);
+    let mut state = ready_state_with(2, &[("rules", &rules.to_string())]);
+    let tail = state
+        .host_mut()
+        .add_terminal_pane(1, "tail", Some("tail -f app.log"));
+    sync(&mut state);
+
+    state.host_mut().tabs[1].name = "logs".to_owned();
+    sync(&mut state);
+    sync(&mut state);
+
+    for pane in &state.host().panes[&1] {
+        assert!(pane.is_floating, "pane {} floats", pane.id);
+    }
+    assert!(state.host().pane(tail).unwrap().1.is_floating);
+    assert_eq!(
+        state.host().panes[&0]
+            .iter()
+            .filter(|pane| pane.is_floating)
+            .count(),
+        0
+    );
+}
+
+#[test]
+fn rule_renamed_tabs_do_not_fire_tab_rules_again() {
+    let rules = json!([
+        {
+            "id": "a-to-b",
+            "when": { "event": "tab_appeared", "name": "a" },
+            "actions": [{ "op": "rename_tab", "position": "$tab_position", "name": "b" }],
+            "cooldown_ms": 0,
+        },
+        {
+            "id": "b-to-a",
+            "when": { "event": "tab_appeared", "name": "b" },
+            "actions": [{ "op": "rename_tab", "position": "$tab_position", "name": "a" }],
+            "cooldown_ms": 0,
+        },
+    ]);
+    let mut state = ready_state_with(1, &[("rules", &rules.to_string())]);
+
+    state.host_mut().tabs[0].name = "a".to_owned();
+    for _ in 0..4 {
+        sync(&mut state);
+    }
+
+    assert_eq!(
+        count_calls(&state, |call| matches!(call, HostCall::RenameTab { .. })),
+        1
+    );
+    assert_eq!(state.host().tabs[0].name, "b");
+}
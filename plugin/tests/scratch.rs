@@ -0,0 +1,105 @@
+mod common;
+
+use common::{pipe_message, ready_state_with, request, sync};
+use jelly_j::fake_host::{FakeHost, HostCall};
+use jelly_j::State;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use zellij_tile::prelude::*;
+
+const SCRATCHPADS: &[(&str, &str)] = &[
+    ("scratch.top.command", "htop -d 10"),
+    ("scratch.notes.command", "nvim notes.md"),
+];
+
+fn toggle_scratch(state: &mut State<FakeHost>, pipe_id: &str, name: &str) -> Value {
+    let response = request(
+        state,
+        pipe_id,
+        json!({ "op": "toggle_scratch", "name": name }),
+    );
+    sync(state);
+    response
+}
+
+#[test]
+fn scratch_panes_launch_hide_show_and_follow_the_active_tab() {
+    let mut state = ready_state_with(2, SCRATCHPADS);
+
+    let launched = toggle_scratch(&mut state, "s1", "top");
+    assert_eq!(launched["result"]["action"], "launch", "{}", launched);
+    let pane_id = launched["result"]["pane_id"].as_u64().unwrap() as u32;
+    assert!(state
+        .host()
+        .calls
+        .contains(&HostCall::OpenCommandPaneFloating {
+            path: "sh".to_owned(),
+            args: vec!["-c".to_owned(), "htop -d 10".to_owned()],
+            cwd: None,
+        }));
+    let (_, pane) = state.host().pane(pane_id).unwrap();
+    assert_eq!(pane.title, "scratch:top");
+
+    let actions = ["s2", "s3"]
+        .map(|pipe_id| toggle_scratch(&mut state, pipe_id, "top")["result"]["action"].clone());
+    assert_eq!(actions, [json!("hide"), json!("show")]);
+
+    state.host_mut().focus_tab(1);
+    sync(&mut state);
+    let moved = toggle_scratch(&mut state, "s4", "top");
+    assert_eq!(
+        moved["result"],
+        json!({ "name": "top", "pane_id": pane_id, "action": "move" })
+    );
+    assert_eq!(state.host().pane(pane_id).unwrap().0, 1);
+    assert_eq!(state.jelly_pane_id(), None);
+}
+
+#[test]
+fn scratch_pipe_relaunches_exited_panes_and_rejects_unknown_names() {
+    let mut state = ready_state_with(1, SCRATCHPADS);
+    state.pipe(pipe_message("scratch:notes", "p1", None));
+    sync(&mut state);
+    let first = state.host().last_cli_output("p1").unwrap()["result"]["pane_id"].clone();
+    let first = first.as_u64().unwrap() as u32;
+    state.host_mut().exit_pane(first, Some(0));
+    sync(&mut state);
+
+    state.pipe(pipe_message("scratch:notes", "p2", None));
+    sync(&mut state);
+
+    let relaunched = state.host().last_cli_output("p2").unwrap();
+    assert_eq!(relaunched["result"]["action"], "launch");
+    assert_ne!(relaunched["result"]["pane_id"], first);
+    assert!(state.host().pane(first).is_none());
+    let butler = &request(&mut state, "g", json!({ "op": "get_state" }))["result"]["butler"];
+    assert_eq!(
+        butler["scratch_panes"],
+        json!([
+            { "name": "notes", "command": "nvim notes.md", "pane_id": relaunched["result"]["pane_id"] },
+            { "name": "top", "command": "htop -d 10", "pane_id": null },
+        ])
+    );
+    state.pipe(pipe_message("scratch:shell", "p3", None));
+    assert_eq!(
+        state.host().last_cli_output("p3").unwrap()["code"],
+        "scratch_not_found"
+    );
+}
+
+#[test]
+fn scratch_panes_are_reused_after_a_reload() {
+    let mut state = ready_state_with(1, SCRATCHPADS);
+    let launched = toggle_scratch(&mut state, "s1", "top");
+    let pane_id = launched["result"]["pane_id"].clone();
+
+    let configuration = BTreeMap::from([("scratch.top.command".to_owned(), "htop".to_owned())]);
+    let mut reloaded = State::new(std::mem::take(state.host_mut()));
+    reloaded.load(configuration);
+    reloaded.update(Event::PermissionRequestResult(PermissionStatus::Granted));
+    sync(&mut reloaded);
+
+    let toggled = toggle_scratch(&mut reloaded, "s2", "top");
+    assert_eq!(toggled["result"]["pane_id"], pane_id);
+    assert_eq!(toggled["result"]["action"], "hide");
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use common::{ready_state_with, request, sync};
+use jelly_j::fake_host::{FakeHost, HostCall};
+use jelly_j::State;
+use serde_json::{json, Value};
+
+fn shell(state: &State<FakeHost>, tab: usize) -> u32 {
+    state.host().panes[&tab][0].id
+}
+
+fn cd(state: &mut State<FakeHost>, pane_id: u32, cwd: &str) {
+    let event = state.host().cwd_changed(pane_id, cwd);
+    state.update(event);
+}
+
+fn suggestions(state: &mut State<FakeHost>) -> Vec<Value> {
+    let response = request(state, "s", json!({ "op": "get_tab_name_suggestions" }));
+    assert_eq!(response["ok"], true, "{}", response);
+    response["result"]["suggestions"]
+        .as_array()
+        .unwrap()
+        .clone()
+}
+
+#[test]
+fn suggestions_prefer_repo_then_directory_then_program() {
+    let mut state = ready_state_with(4, &[("auto_name_tabs", "suggest")]);
+    let (tab_0, tab_1, tab_3) = (shell(&state, 0), shell(&state, 1), shell(&state, 3));
+    cd(&mut state, tab_0, "/home/me/src/github.com/acme/api/cmd");
+    cd(&mut state, tab_1, "/var/log/nginx");
+    let htop = state.host_mut().add_terminal_pane(2, "htop", Some("htop"));
+    let shell_2 = shell(&state, 2);
+    state.host_mut().pane_mut(shell_2).unwrap().is_focused = false;
+    state.host_mut().pane_mut(htop).unwrap().is_focused = true;
+    cd(&mut state, tab_3, "/home/me/code/api");
+    sync(&mut state);
+
+    let suggestions = suggestions(&mut state);
+
+    let named = suggestions
+        .iter()
+        .map(|s| {
+            (
+                s["position"].as_u64().unwrap(),
+                s["name"].as_str().unwrap(),
+                s["reason"].as_str().unwrap(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        named,
+        [
+            (0, "api", "repo"),
+            (1, "nginx", "cwd"),
+            (2, "htop", "command"),
+            (3, "api 2", "repo"),
+        ]
+    );
+    assert!(state
+        .host()
+        .calls
+        .iter()
+        .all(|call| !matches!(call, HostCall::RenameTab { .. })));
+}
+
+#[test]
+fn apply_renames_default_named_tabs_once() {
+    let mut state = ready_state_with(2, &[("auto_name_tabs", "apply")]);
+    let tab_0 = shell(&state, 0);
+    cd(&mut state, tab_0, "/home/me/projects/jelly-j");
+    state.host_mut().tabs[1].name = "mine".to_owned();
+
+    let tab_update = state.host().tab_update();
+    state.update(tab_update.clone());
+    state.update(tab_update);
+
+    let renames = state
+        .host()
+        .calls
+        .iter()
+        .filter(|call| matches!(call, HostCall::RenameTab { .. }))
+        .cloned()
+        .collect::<Vec<_>>();
+    assert_eq!(
+        renames,
+        [HostCall::RenameTab {
+            tab_position: 1,
+            name: "jelly-j".to_owned(),
+        }]
+    );
+    sync(&mut state);
+    assert!(suggestions(&mut state).is_empty());
+}
+
+#[test]
+fn suggestions_are_refused_when_auto_naming_is_off() {
+    let mut state = ready_state_with(1, &[("auto_name_tabs", "off")]);
+
+    let response = request(&mut state, "s", json!({ "op": "get_tab_name_suggestions" }));
+
+    assert_eq!(response["ok"], false);
+    assert_eq!(response["code"], "auto_name_tabs_off");
+}
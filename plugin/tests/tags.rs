@@ -0,0 +1,146 @@
+mod common;
+
+use common::{ready_state, request, sync};
+use jelly_j::fake_host::{FakeHost, HostCall};
+use jelly_j::State;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use zellij_tile::prelude::*;
+
+fn pane_tags(state: &mut State<FakeHost>, pane_id: u32) -> Value {
+    let response = request(state, "s", json!({ "op": "get_state" }));
+    response["result"]["panes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|pane| pane["id"] == pane_id)
+        .expect("pane is listed")["tags"]
+        .clone()
+}
+
+#[test]
+fn tags_survive_renames_and_can_be_listed_and_removed() {
+    let mut state = ready_state(1);
+    let shell = state.host().panes[&0][0].id;
+    let logs = state
+        .host_mut()
+        .add_terminal_pane(0, "tail", Some("tail -f app.log"));
+    sync(&mut state);
+
+    let tagged = request(
+        &mut state,
+        "t1",
+        json!({ "op": "tag_pane", "pane_id": logs, "tags": ["role:logs", "project:api"] }),
+    );
+    assert_eq!(tagged["ok"], true, "{}", tagged);
+    request(
+        &mut state,
+        "t2",
+        json!({ "op": "tag_pane", "pane_id": shell, "tags": ["project:api"] }),
+    );
+    request(
+        &mut state,
+        "r",
+        json!({ "op": "rename_pane", "pane_id": logs, "name": "app logs" }),
+    );
+    state.host_mut().pane_mut(logs).unwrap().title = "app logs".to_owned();
+    sync(&mut state);
+
+    assert_eq!(
+        pane_tags(&mut state, logs),
+        json!(["project:api", "role:logs"])
+    );
+    let listed = request(&mut state, "l", json!({ "op": "list_tags" }));
+    assert_eq!(
+        listed["result"]["tags"],
+        json!([
+            { "tag": "project:api", "pane_ids": [shell, logs] },
+            { "tag": "role:logs", "pane_ids": [logs] },
+        ])
+    );
+
+    request(
+        &mut state,
+        "u",
+        json!({ "op": "untag_pane", "pane_id": "tag:project:api", "tags": ["project:api"] }),
+    );
+    assert_eq!(pane_tags(&mut state, logs), json!(["role:logs"]));
+    assert_eq!(pane_tags(&mut state, shell), json!([]));
+}
+
+#[test]
+fn selectors_stand_in_for_pane_ids() {
+    let mut state = ready_state(2);
+    let first = state
+        .host_mut()
+        .add_terminal_pane(0, "tail", Some("tail -f a"));
+    let second = state
+        .host_mut()
+        .add_terminal_pane(1, "tail", Some("tail -f b"));
+    sync(&mut state);
+    for pane_id in [first, second] {
+        request(
+            &mut state,
+            "t",
+            json!({ "op": "tag_pane", "pane_id": pane_id, "tags": ["role:logs"] }),
+        );
+    }
+    state.host_mut().take_calls();
+
+    let hidden = request(
+        &mut state,
+        "h",
+        json!({ "op": "hide_pane", "pane_id": "tag:role:logs" }),
+    );
+
+    assert_eq!(hidden["ok"], true, "{}", hidden);
+    assert_eq!(
+        state
+            .host()
+            .calls
+            .iter()
+            .filter(|call| matches!(call, HostCall::HidePane(_)))
+            .cloned()
+            .collect::<Vec<_>>(),
+        [
+            HostCall::HidePane(PaneId::Terminal(first)),
+            HostCall::HidePane(PaneId::Terminal(second)),
+        ]
+    );
+    let info = request(
+        &mut state,
+        "i",
+        json!({ "op": "get_pane_info", "pane_id": "tag:role:logs" }),
+    );
+    assert_eq!(info["code"], "ambiguous_selector");
+    let unknown = request(
+        &mut state,
+        "i",
+        json!({ "op": "hide_pane", "pane_id": "tag:role:db" }),
+    );
+    assert_eq!(unknown["code"], "pane_not_found");
+    let invalid = request(
+        &mut state,
+        "i",
+        json!({ "op": "hide_pane", "pane_id": "role:logs" }),
+    );
+    assert_eq!(invalid["code"], "invalid_selector");
+}
+
+#[test]
+fn tags_are_restored_after_a_reload() {
+    let mut state = ready_state(1);
+    let shell = state.host().panes[&0][0].id;
+    request(
+        &mut state,
+        "t",
+        json!({ "op": "tag_pane", "pane_id": shell, "tags": ["project:api"] }),
+    );
+
+    let mut reloaded = State::new(std::mem::take(state.host_mut()));
+    reloaded.load(BTreeMap::new());
+    reloaded.update(Event::PermissionRequestResult(PermissionStatus::Granted));
+    sync(&mut reloaded);
+
+    assert_eq!(pane_tags(&mut reloaded, shell), json!(["project:api"]));
+}
@@ -0,0 +1,269 @@
+mod common;
+
+use common::{jelly_pane, pipe_message, ready_state, sync, toggle};
+use jelly_j::fake_host::{FakeHost, HostCall};
+use jelly_j::{State, PANE_NAME, TRACKED_PANE_MISSING_GRACE_MS, UI_TOKEN_ENV};
+use std::collections::BTreeMap;
+use zellij_tile::prelude::*;
+
+#[test]
+fn first_toggle_launches_a_floating_jelly_pane() {
+    let mut state = ready_state(1);
+
+    toggle(&mut state, "p1");
+
+    let (tab_index, pane) = jelly_pane(&state);
+    assert_eq!(tab_index, 0);
+    assert_eq!(pane.title, PANE_NAME);
+    assert!(pane.is_floating);
+    assert!(pane.is_focused);
+    let (path, args) = state
+        .host()
+        .calls
+        .iter()
+        .find_map(|call| match call {
+            HostCall::OpenCommandPaneFloating { path, args, .. } => {
+                Some((path.clone(), args.clone()))
+            }
+            _ => None,
+        })
+        .expect("launched a floating Jelly pane");
+    assert_eq!(path, "sh");
+    assert_eq!(
+        args[..3],
+        ["-c", "export \"$@\" && exec jelly-j ui", "jelly-j"]
+    );
+    assert!(args[3].starts_with(&format!("{}=", UI_TOKEN_ENV)));
+    assert!(state.host().calls.contains(&HostCall::RenamePane {
+        pane_id: PaneId::Terminal(pane.id),
+        name: PANE_NAME.to_owned(),
+    }));
+}
+
+#[test]
+fn repeated_toggles_hide_then_show_the_same_pane() {
+    let mut state = ready_state(1);
+    toggle(&mut state, "p1");
+    let (_, launched) = jelly_pane(&state);
+
+    toggle(&mut state, "p2");
+    let (_, hidden) = jelly_pane(&state);
+    assert_eq!(hidden.id, launched.id);
+    assert!(hidden.is_suppressed);
+
+    state.host_mut().take_calls();
+    toggle(&mut state, "p3");
+    let (_, shown) = jelly_pane(&state);
+    assert_eq!(shown.id, launched.id);
+    assert!(!shown.is_suppressed);
+    assert!(shown.is_floating);
+    assert!(!state
+        .host()
+        .calls
+        .iter()
+        .any(|call| matches!(call, HostCall::OpenCommandPaneFloating { .. })));
+}
+
+#[test]
+fn hiding_a_focused_jelly_pane_hands_focus_back_to_the_tab() {
+    let mut state = ready_state(1);
+    toggle(&mut state, "p1");
+    let shell_id = state.host().panes[&0][0].id;
+
+    toggle(&mut state, "p2");
+
+    let calls = &state.host().calls;
+    let focus = calls
+        .iter()
+        .position(|call| call == &HostCall::FocusTerminalPane(shell_id))
+        .expect("focus moved to the shell pane");
+    let hide = calls
+        .iter()
+        .position(|call| matches!(call, HostCall::HidePane(_)))
+        .expect("jelly pane hidden");
+    assert!(focus < hide);
+}
+
+#[test]
+fn toggle_from_another_tab_moves_jelly_there_floating() {
+    let mut state = ready_state(2);
+    toggle(&mut state, "p1");
+    let (_, launched) = jelly_pane(&state);
+
+    state.host_mut().focus_tab(1);
+    sync(&mut state);
+    state.host_mut().take_calls();
+    toggle(&mut state, "p2");
+
+    let jelly = PaneId::Terminal(launched.id);
+    assert_eq!(
+        state.host().calls[..4],
+        [
+            HostCall::HidePane(jelly),
+            HostCall::BreakPanesToTab {
+                pane_ids: vec![jelly],
+                tab_index: 1,
+            },
+            HostCall::TogglePaneEmbedOrEject(jelly),
+            HostCall::ShowPane {
+                pane_id: jelly,
+                should_float_if_hidden: true,
+                should_focus_pane: true,
+            },
+        ]
+    );
+    let (tab_index, pane) = jelly_pane(&state);
+    assert_eq!(tab_index, 1);
+    assert!(pane.is_floating);
+    assert!(!pane.is_suppressed);
+}
+
+#[test]
+fn active_tab_falls_back_to_pane_focus_without_tab_updates() {
+    let mut host = FakeHost::with_tabs(2);
+    let jelly_id = host.add_terminal_pane(0, PANE_NAME, None);
+    host.pane_mut(jelly_id).unwrap().is_suppressed = true;
+    let tab0_shell = host.panes[&0][0].id;
+    host.pane_mut(tab0_shell).unwrap().is_focused = false;
+
+    let mut state = State::new(host);
+    state.load(BTreeMap::new());
+    // No permission event and no TabUpdate: the cached grant is inferred from PaneUpdate.
+    let pane_update = state.host().pane_update();
+    state.update(pane_update);
+    state.host_mut().advance(common::KEYPRESS_GAP_MS);
+    state.pipe(pipe_message("toggle", "p1", Some("toggle")));
+
+    assert!(state.host().calls.contains(&HostCall::BreakPanesToTab {
+        pane_ids: vec![PaneId::Terminal(jelly_id)],
+        tab_index: 1,
+    }));
+}
+
+#[test]
+fn extra_jelly_panes_are_closed_keeping_the_current_tab_one() {
+    let mut host = FakeHost::with_tabs(2);
+    let stale = host.add_terminal_pane(1, PANE_NAME, Some("jelly-j ui"));
+    let current = host.add_terminal_pane(0, PANE_NAME, None);
+    let mut state = State::new(host);
+    state.load(BTreeMap::new());
+    state.update(Event::PermissionRequestResult(PermissionStatus::Granted));
+    sync(&mut state);
+
+    toggle(&mut state, "p1");
+
+    assert!(state
+        .host()
+        .calls
+        .contains(&HostCall::CloseTerminalPane(stale)));
+    assert_eq!(state.jelly_pane_id(), Some(current));
+    assert!(state.host().pane(stale).is_none());
+}
+
+#[test]
+fn toggle_before_permission_grant_runs_once_granted() {
+    let mut state = State::new(FakeHost::with_tabs(1));
+    state.load(BTreeMap::new());
+    state.pipe(pipe_message("toggle", "p1", Some("toggle")));
+    assert!(state.jelly_pane_id().is_none());
+
+    state.update(Event::PermissionRequestResult(PermissionStatus::Granted));
+    sync(&mut state);
+
+    assert!(state.jelly_pane_id().is_some());
+}
+
+#[test]
+fn toggles_inside_the_dedup_window_are_ignored() {
+    let mut state = ready_state(1);
+    toggle(&mut state, "p1");
+    state.host_mut().take_calls();
+
+    state.pipe(pipe_message("toggle", "p2", Some("toggle")));
+
+    let response = state.host().last_cli_output("p2").unwrap();
+    assert_eq!(response["result"]["dedup_window"], true);
+    assert!(!jelly_pane(&state).1.is_suppressed);
+}
+
+#[test]
+fn repeated_cli_pipe_ids_are_ignored() {
+    let mut state = ready_state(1);
+    toggle(&mut state, "p1");
+    state.host_mut().take_calls();
+
+    toggle(&mut state, "p1");
+
+    let response = state.host().last_cli_output("p1").unwrap();
+    assert_eq!(response["result"]["duplicate"], true);
+    assert!(!jelly_pane(&state).1.is_suppressed);
+}
+
+#[test]
+fn tracked_pane_survives_transient_absence_within_grace_period() {
+    let mut state = ready_state(1);
+    toggle(&mut state, "p1");
+    let (tab_index, pane) = jelly_pane(&state);
+
+    let removed = state.host_mut().remove_pane(pane.id).unwrap();
+    sync(&mut state);
+    state.host_mut().advance(TRACKED_PANE_MISSING_GRACE_MS / 2);
+    sync(&mut state);
+    assert_eq!(state.jelly_pane_id(), Some(pane.id));
+
+    state
+        .host_mut()
+        .panes
+        .entry(tab_index)
+        .or_default()
+        .push(removed.1);
+    sync(&mut state);
+    state.host_mut().advance(TRACKED_PANE_MISSING_GRACE_MS * 2);
+    sync(&mut state);
+    assert_eq!(state.jelly_pane_id(), Some(pane.id));
+}
+
+#[test]
+fn tracked_pane_is_forgotten_after_grace_period() {
+    let mut state = ready_state(1);
+    toggle(&mut state, "p1");
+    let (_, pane) = jelly_pane(&state);
+
+    state.host_mut().remove_pane(pane.id);
+    sync(&mut state);
+    state.host_mut().advance(TRACKED_PANE_MISSING_GRACE_MS + 1);
+    sync(&mut state);
+    assert_eq!(state.jelly_pane_id(), None);
+
+    toggle(&mut state, "p2");
+    assert!(state.jelly_pane_id().is_some_and(|id| id != pane.id));
+}
+
+#[test]
+fn launch_is_skipped_while_tracked_pane_is_pending() {
+    let mut state = ready_state(1);
+    toggle(&mut state, "p1");
+    let (_, pane) = jelly_pane(&state);
+    state.host_mut().remove_pane(pane.id);
+    sync(&mut state);
+    state.host_mut().take_calls();
+
+    toggle(&mut state, "p2");
+
+    assert!(!state
+        .host()
+        .calls
+        .iter()
+        .any(|call| matches!(call, HostCall::OpenCommandPaneFloating { .. })));
+    assert_eq!(state.jelly_pane_id(), Some(pane.id));
+}
+
+#[test]
+fn launch_failure_leaves_no_tracked_pane() {
+    let mut state = ready_state(1);
+    state.host_mut().launch_error = Some("no pty".to_owned());
+
+    toggle(&mut state, "p1");
+
+    assert_eq!(state.jelly_pane_id(), None);
+}
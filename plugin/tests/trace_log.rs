@@ -0,0 +1,190 @@
+mod common;
+
+use common::{ready_state_with, request};
+use jelly_j::fake_host::FakeHost;
+use jelly_j::State;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+const LOG_PATH: &str = "/data/butler-trace.jsonl";
+
+fn log_lines(host: &FakeHost, path: &str) -> Vec<Value> {
+    host.files
+        .get(path)
+        .map(|contents| {
+            contents
+                .lines()
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[test]
+fn trace_log_appends_structured_entries_with_fields() {
+    let mut state = ready_state_with(
+        1,
+        &[("trace_log", "data"), ("trace_log_max_bytes", "1000000")],
+    );
+
+    request(
+        &mut state,
+        "p1",
+        json!({ "op": "rename_tab", "position": 0, "name": "api" }),
+    );
+
+    let lines = log_lines(state.host(), LOG_PATH);
+    assert_eq!(lines[0]["message"], "load");
+    assert_eq!(lines[0]["fields"]["trace_log"], LOG_PATH);
+    let rename = lines
+        .iter()
+        .find(|line| line["message"] == "rename_tab")
+        .expect("rename_tab was persisted");
+    assert_eq!(rename["fields"]["name"], "api");
+    let seqs = lines
+        .iter()
+        .map(|line| line["seq"].as_u64().unwrap())
+        .collect::<Vec<_>>();
+    assert!(seqs.windows(2).all(|pair| pair[0] < pair[1]));
+}
+
+#[test]
+fn trace_log_rotates_when_it_would_exceed_the_cap() {
+    let mut state = ready_state_with(1, &[("trace_log", "data"), ("trace_log_max_bytes", "2000")]);
+
+    for index in 0..20 {
+        request(&mut state, &format!("p{}", index), json!({ "op": "ping" }));
+    }
+
+    let host = state.host();
+    assert!(host.files[LOG_PATH].len() <= 2000);
+    assert!(host.files.contains_key(&format!("{}.1", LOG_PATH)));
+}
+
+#[test]
+fn export_trace_concatenates_rotated_and_current_logs() {
+    let mut state = ready_state_with(1, &[("trace_log", "data"), ("trace_log_max_bytes", "2000")]);
+    for index in 0..20 {
+        request(&mut state, &format!("p{}", index), json!({ "op": "ping" }));
+    }
+
+    let response = request(
+        &mut state,
+        "export",
+        json!({ "op": "export_trace", "path": "/tmp/export.jsonl" }),
+    );
+
+    assert_eq!(response["result"]["persisted"], true);
+    let exported = log_lines(state.host(), "/tmp/export.jsonl");
+    assert_eq!(
+        response["result"]["entries"].as_u64().unwrap(),
+        exported.len() as u64
+    );
+    assert!(exported.len() > 20);
+    assert_eq!(
+        exported.last().unwrap()["message"],
+        "pipe request",
+        "the export request itself is flushed before reading"
+    );
+}
+
+#[test]
+fn export_trace_without_a_log_uses_the_ring_and_reports_io_errors() {
+    let mut state = State::new(FakeHost::with_tabs(1));
+    state.load(BTreeMap::new());
+
+    let response = request(
+        &mut state,
+        "p1",
+        json!({ "op": "export_trace", "path": "/tmp/export.jsonl" }),
+    );
+    assert_eq!(response["result"]["persisted"], false);
+    assert!(!log_lines(state.host(), "/tmp/export.jsonl").is_empty());
+
+    state.host_mut().file_error = Some("read-only file system".to_owned());
+    let response = request(
+        &mut state,
+        "p2",
+        json!({ "op": "export_trace", "path": "/tmp/export.jsonl" }),
+    );
+    assert_eq!(response["code"], "io_error");
+}
+
+#[test]
+fn entries_the_ring_drops_within_one_callback_still_reach_the_log() {
+    let mut state = ready_state_with(
+        1,
+        &[("trace_log", "data"), ("trace_log_max_bytes", "1000000")],
+    );
+    let steps = (0..300)
+        .map(|index| json!({ "op": "rename_tab", "position": 0, "name": format!("t{}", index) }))
+        .collect::<Vec<_>>();
+
+    let response = request(
+        &mut state,
+        "p1",
+        json!({ "op": "batch", "requests": steps }),
+    );
+    assert_eq!(response["ok"], true, "{}", response);
+
+    let seqs = log_lines(state.host(), LOG_PATH)
+        .iter()
+        .map(|line| line["seq"].as_u64().unwrap())
+        .collect::<Vec<_>>();
+    assert!(seqs.len() > 300);
+    assert_eq!(seqs, (1..=seqs.len() as u64).collect::<Vec<_>>());
+}
+
+#[test]
+fn export_trace_only_writes_under_data_or_tmp() {
+    let mut state = State::new(FakeHost::with_tabs(1));
+    state.load(BTreeMap::new());
+
+    for path in [
+        "/host/trace.jsonl",
+        "/tmp/../host/x",
+        "/data/",
+        "/database/trace.jsonl",
+        "relative.jsonl",
+    ] {
+        let response = request(
+            &mut state,
+            "p",
+            json!({ "op": "export_trace", "path": path }),
+        );
+        assert_eq!(response["code"], "invalid_path", "{}", path);
+        assert!(!state.host().files.contains_key(path));
+    }
+    let response = request(
+        &mut state,
+        "p",
+        json!({ "op": "export_trace", "path": "/data/trace.jsonl" }),
+    );
+    assert_eq!(response["ok"], true, "{}", response);
+}
+
+#[test]
+fn trace_log_outside_data_or_tmp_is_rejected_with_a_warning() {
+    for dir in ["/host/logs", "/tmp/../host", "/database", "logs"] {
+        let mut state = State::new(FakeHost::with_tabs(1));
+        state.load(BTreeMap::from([("trace_log".to_owned(), dir.to_owned())]));
+
+        assert!(state.host().files.is_empty(), "{}", dir);
+        let trace = request(
+            &mut state,
+            "t",
+            json!({ "op": "get_trace", "level": "warn", "format": "json" }),
+        );
+        let warning = &trace["result"]["entries"][0];
+        assert_eq!(warning["message"], "trace_log_rejected", "{}", dir);
+        assert_eq!(warning["fields"]["dir"], dir);
+    }
+
+    let mut state = State::new(FakeHost::with_tabs(1));
+    state.load(BTreeMap::from([(
+        "trace_log".to_owned(),
+        "/tmp/jelly/".to_owned(),
+    )]));
+    request(&mut state, "p", json!({ "op": "ping" }));
+    assert!(!log_lines(state.host(), "/tmp/jelly/butler-trace.jsonl").is_empty());
+}
@@ -0,0 +1,145 @@
+mod common;
+
+use common::{jelly_pane, ready_state, request, toggle};
+use jelly_j::fake_host::{FakeHost, HostCall};
+use jelly_j::{State, UI_HEARTBEAT_TIMEOUT_MS, UI_STALE_GRACE_MS};
+use serde_json::{json, Value};
+
+fn register_ui(state: &mut State<FakeHost>, pane_id: u32) {
+    let response = request(
+        state,
+        "register",
+        json!({ "op": "register_ui", "pane_id": pane_id, "client_id": "ui-42-abc", "pid": 42 }),
+    );
+    assert_eq!(response["ok"], true);
+}
+
+/// Lets `ms` pass while other pipes keep the plugin busy, so it counts as activity.
+fn stay_busy(state: &mut State<FakeHost>, ms: u128) {
+    for step in 0..ms.div_ceil(5_000) {
+        state.host_mut().advance(5_000.min(ms - step * 5_000));
+        request(state, "busy", json!({ "op": "ping" }));
+    }
+}
+
+fn ui_clients(state: &mut State<FakeHost>, pipe_id: &str) -> Value {
+    let response = request(state, pipe_id, json!({ "op": "get_state" }));
+    response["result"]["butler"]["ui_clients"].clone()
+}
+
+#[test]
+fn heartbeats_keep_a_registered_ui_alive() {
+    let mut state = ready_state(1);
+    toggle(&mut state, "t1");
+    let pane_id = jelly_pane(&state).1.id;
+    register_ui(&mut state, pane_id);
+
+    state.host_mut().advance(UI_HEARTBEAT_TIMEOUT_MS - 1);
+    let heartbeat = request(
+        &mut state,
+        "h1",
+        json!({ "op": "ui_heartbeat", "pane_id": pane_id }),
+    );
+    assert_eq!(heartbeat["ok"], true);
+    state.host_mut().advance(UI_HEARTBEAT_TIMEOUT_MS - 1);
+
+    let clients = ui_clients(&mut state, "s1");
+    assert_eq!(clients[0]["pane_id"], pane_id);
+    assert_eq!(clients[0]["client_id"], "ui-42-abc");
+    assert_eq!(clients[0]["alive"], true);
+}
+
+#[test]
+fn heartbeat_from_an_unregistered_pane_asks_for_registration() {
+    let mut state = ready_state(1);
+
+    let response = request(
+        &mut state,
+        "h1",
+        json!({ "op": "ui_heartbeat", "pane_id": 7 }),
+    );
+
+    assert_eq!(response["code"], "ui_not_registered");
+}
+
+#[test]
+fn toggle_shows_a_silent_ui_and_marks_it_stale_instead_of_closing_it() {
+    let mut state = ready_state(1);
+    toggle(&mut state, "t1");
+    let pane_id = jelly_pane(&state).1.id;
+    register_ui(&mut state, pane_id);
+    toggle(&mut state, "t2");
+    stay_busy(&mut state, UI_HEARTBEAT_TIMEOUT_MS + 1);
+    assert_eq!(ui_clients(&mut state, "s1")[0]["alive"], false);
+    state.host_mut().take_calls();
+
+    toggle(&mut state, "t3");
+
+    let calls = state.host().calls.clone();
+    assert!(!calls.contains(&HostCall::CloseTerminalPane(pane_id)));
+    let (_, pane) = jelly_pane(&state);
+    assert_eq!(pane.id, pane_id);
+    assert!(!pane.is_suppressed);
+    assert_eq!(ui_clients(&mut state, "s2")[0]["stale"], true);
+
+    let heartbeat = request(
+        &mut state,
+        "h1",
+        json!({ "op": "ui_heartbeat", "pane_id": pane_id }),
+    );
+    assert_eq!(heartbeat["ok"], true);
+    let clients = ui_clients(&mut state, "s3");
+    assert_eq!(clients[0]["alive"], true);
+    assert_eq!(clients[0]["stale"], false);
+}
+
+#[test]
+fn a_suspended_session_does_not_count_as_ui_silence() {
+    let mut state = ready_state(1);
+    toggle(&mut state, "t1");
+    let pane_id = jelly_pane(&state).1.id;
+    register_ui(&mut state, pane_id);
+
+    state.host_mut().advance(10 * UI_STALE_GRACE_MS);
+
+    assert_eq!(ui_clients(&mut state, "s1")[0]["alive"], true);
+}
+
+#[test]
+fn toggle_replaces_a_pane_whose_ui_stayed_silent_through_the_grace_period() {
+    let mut state = ready_state(1);
+    toggle(&mut state, "t1");
+    let zombie = jelly_pane(&state).1.id;
+    register_ui(&mut state, zombie);
+    stay_busy(&mut state, UI_HEARTBEAT_TIMEOUT_MS + 1);
+    toggle(&mut state, "t2");
+    toggle(&mut state, "t3");
+    stay_busy(&mut state, UI_STALE_GRACE_MS);
+    state.host_mut().take_calls();
+
+    toggle(&mut state, "t4");
+
+    let calls = state.host().calls.clone();
+    assert!(calls.contains(&HostCall::CloseTerminalPane(zombie)));
+    assert!(calls
+        .iter()
+        .any(|call| matches!(call, HostCall::OpenCommandPaneFloating { .. })));
+    let (_, replacement) = jelly_pane(&state);
+    assert_ne!(replacement.id, zombie);
+    assert!(!replacement.is_suppressed);
+    let metrics = request(&mut state, "m1", json!({ "op": "get_metrics" }));
+    assert_eq!(metrics["result"]["toggles"]["zombies_replaced"], 1);
+}
+
+#[test]
+fn register_ui_needs_an_existing_pane() {
+    let mut state = ready_state(1);
+
+    let response = request(
+        &mut state,
+        "r1",
+        json!({ "op": "register_ui", "pane_id": 999, "client_id": "ui-1", "pid": 1 }),
+    );
+
+    assert_eq!(response["code"], "pane_not_found", "{}", response);
+}
@@ -3,11 +3,15 @@ import { readFile, unlink } from "node:fs/promises";
 import os from "node:os";
 import path from "node:path";
 import { promisify } from "node:util";
+import { exportButlerTrace } from "../src/zellijPipe.js";
 
 const execFileAsync = promisify(execFile);
 const LOCK_PATH = path.join(os.homedir(), ".jelly-j", "agent.lock.json");
 const TERM_WAIT_MS = 2_000;
 const CMD_TIMEOUT_MS = 5_000;
+// Plugin-side path: `/tmp` and `/host` are the butler's sandbox folders, not the host's.
+const TRACE_EXPORT_PATH =
+  process.env.JELLY_J_TRACE_EXPORT_PATH?.trim() || "/tmp/jelly-j-butler-trace.jsonl";
 
 type LockOwner = {
   pid?: number;
@@ -86,7 +90,21 @@ async function relaunchJellyJPane(): Promise<void> {
   );
 }
 
+async function exportTraceForDiagnostics(): Promise<void> {
+  try {
+    const exported = await exportButlerTrace(TRACE_EXPORT_PATH);
+    const source = exported.persisted ? "persisted log" : "in-memory ring";
+    process.stdout.write(
+      `Exported ${exported.entries} butler trace entries (${source}) to plugin path ${exported.path}.\n`
+    );
+  } catch (error) {
+    const message = error instanceof Error ? error.message : String(error);
+    process.stderr.write(`butler trace export skipped: ${message}\n`);
+  }
+}
+
 async function main(): Promise<void> {
+  await exportTraceForDiagnostics();
   const stopResult = await stopExistingAgentFromLock();
   await relaunchJellyJPane();
 
@@ -0,0 +1,3961 @@
+// @generated by plugin/tests/protocol_schema.rs from plugin/src/protocol.rs. Do not edit.
+export const butlerProtocolSchema = {
+  "error": {
+    "$schema": "http://json-schema.org/draft-07/schema#",
+    "properties": {
+      "code": {
+        "type": "string"
+      },
+      "error": {
+        "type": "string"
+      },
+      "ok": {
+        "type": "boolean"
+      }
+    },
+    "required": [
+      "code",
+      "error",
+      "ok"
+    ],
+    "title": "ErrorResponse",
+    "type": "object"
+  },
+  "protocol_version": 1,
+  "request": {
+    "$schema": "http://json-schema.org/draft-07/schema#",
+    "definitions": {
+      "ButlerRequest": {
+        "oneOf": [
+          {
+            "properties": {
+              "op": {
+                "enum": [
+                  "hello"
+                ],
+                "type": "string"
+              }
+            },
+            "required": [
+              "op"
+            ],
+            "type": "object"
+          },
+          {
+            "properties": {
+              "op": {
+                "enum": [
+                  "get_schema"
+                ],
+                "type": "string"
+              }
+            },
+            "required": [
+              "op"
+            ],
+            "type": "object"
+          },
+          {
+            "properties": {
+              "op": {
+                "enum": [
+                  "ping"
+                ],
+                "type": "string"
+              }
+            },
+            "required": [
+              "op"
+            ],
+            "type": "object"
+          },
+          {
+            "properties": {
+              "op": {
+                "enum": [
+                  "get_state"
+                ],
+                "type": "string"
+              }
+            },
+            "required": [
+              "op"
+            ],
+            "type": "object"
+          },
+          {
+            "properties": {
+              "op": {
+                "enum": [
+                  "get_metrics"
+                ],
+                "type": "string"
+              }
+            },
+            "required": [
+              "op"
+            ],
+            "type": "object"
+          },
+          {
+            "properties": {
+              "category": {
+                "anyOf": [
+                  {
+                    "$ref": "#/definitions/TraceCategory"
+                  },
+                  {
+                    "type": "null"
+                  }
+                ]
+              },
+              "format": {
+                "anyOf": [
+                  {
+                    "$ref": "#/definitions/TraceFormat"
+                  },
+                  {
+                    "type": "null"
+                  }
+                ],
+                "description": "`text` (default) keeps the legacy `\"0001 +12ms message k=v\"` lines."
+              },
+              "level": {
+                "anyOf": [
+                  {
+                    "$ref": "#/definitions/TraceLevel"
+                  },
+                  {
+                    "type": "null"
+                  }
+                ],
+                "description": "Minimum level to include."
+              },
+              "limit": {
+                "format": "uint",
+                "minimum": 0.0,
+                "type": [
+                  "integer",
+                  "null"
+                ]
+              },
+              "op": {
+                "enum": [
+                  "get_trace"
+                ],
+                "type": "string"
+              },
+              "since_seq": {
+                "description": "Only entries with a greater `seq`.",
+                "format": "uint64",
+                "minimum": 0.0,
+                "type": [
+                  "integer",
+                  "null"
+                ]
+              }
+            },
+            "required": [
+              "op"
+            ],
+            "type": "object"
+          },
+          {
+            "properties": {
+              "op": {
+                "enum": [
+                  "clear_trace"
+                ],
+                "type": "string"
+              }
+            },
+            "required": [
+              "op"
+            ],
+            "type": "object"
+          },
+          {
+            "description": "Writes the trace as JSON lines to a file under the plugin's `/data` or `/tmp` folder; any other path fails with `invalid_path`. Includes the persisted log files when `trace_log` is configured.",
+            "properties": {
+              "op": {
+                "enum": [
+                  "export_trace"
+                ],
+                "type": "string"
+              },
+              "path": {
+                "type": "string"
+              }
+            },
+            "required": [
+              "op",
+              "path"
+            ],
+            "type": "object"
+          },
+          {
+            "properties": {
+              "name": {
+                "type": "string"
+              },
+              "op": {
+                "enum": [
+                  "rename_tab"
+                ],
+                "type": "string"
+              },
+              "position": {
+                "format": "uint",
+                "minimum": 0.0,
+                "type": "integer"
+              }
+            },
+            "required": [
+              "name",
+              "op",
+              "position"
+            ],
+            "type": "object"
+          },
+          {
+            "properties": {
+              "name": {
+                "type": "string"
+              },
+              "op": {
+                "enum": [
+                  "rename_pane"
+                ],
+                "type": "string"
+              },
+              "pane_id": {
+                "$ref": "#/definitions/PaneTarget"
+              }
+            },
+            "required": [
+              "name",
+              "op",
+              "pane_id"
+            ],
+            "type": "object"
+          },
+          {
+            "properties": {
+              "op": {
+                "enum": [
+                  "hide_pane"
+                ],
+                "type": "string"
+              },
+              "pane_id": {
+                "$ref": "#/definitions/PaneTarget"
+              }
+            },
+            "required": [
+              "op",
+              "pane_id"
+            ],
+            "type": "object"
+          },
+          {
+            "properties": {
+              "op": {
+                "enum": [
+                  "show_pane"
+                ],
+                "type": "string"
+              },
+              "pane_id": {
+                "$ref": "#/definitions/PaneTarget"
+              },
+              "should_float_if_hidden": {
+                "type": [
+                  "boolean",
+                  "null"
+                ]
+              },
+              "should_focus_pane": {
+                "type": [
+                  "boolean",
+                  "null"
+                ]
+              }
+            },
+            "required": [
+              "op",
+              "pane_id"
+            ],
+            "type": "object"
+          },
+          {
+            "properties": {
+              "atomic": {
+                "default": false,
+                "type": "boolean"
+              },
+              "op": {
+                "enum": [
+                  "batch"
+                ],
+                "type": "string"
+              },
+              "requests": {
+                "items": {
+                  "$ref": "#/definitions/ButlerRequest"
+                },
+                "type": "array"
+              },
+              "stop_on_error": {
+                "default": false,
+                "type": "boolean"
+              }
+            },
+            "required": [
+              "op",
+              "requests"
+            ],
+            "type": "object"
+          },
+          {
+            "description": "Starts (or restarts) recording raw inbound events for replay.",
+            "properties": {
+              "limit": {
+                "format": "uint",
+                "minimum": 0.0,
+                "type": [
+                  "integer",
+                  "null"
+                ]
+              },
+              "op": {
+                "enum": [
+                  "start_recording"
+                ],
+                "type": "string"
+              }
+            },
+            "required": [
+              "op"
+            ],
+            "type": "object"
+          },
+          {
+            "description": "Stops recording and returns what was captured.",
+            "properties": {
+              "op": {
+                "enum": [
+                  "stop_recording"
+                ],
+                "type": "string"
+              }
+            },
+            "required": [
+              "op"
+            ],
+            "type": "object"
+          },
+          {
+            "properties": {
+              "op": {
+                "enum": [
+                  "get_recording"
+                ],
+                "type": "string"
+              },
+              "since_seq": {
+                "format": "uint64",
+                "minimum": 0.0,
+                "type": [
+                  "integer",
+                  "null"
+                ]
+              }
+            },
+            "required": [
+              "op"
+            ],
+            "type": "object"
+          },
+          {
+            "description": "Sent by `jelly-j ui` at startup with the token the butler put in its environment (`JELLY_J_BUTLER_TOKEN`), proving the pane runs Jelly J.",
+            "properties": {
+              "op": {
+                "enum": [
+                  "register_ui_pane"
+                ],
+                "type": "string"
+              },
+              "pane_id": {
+                "format": "uint32",
+                "minimum": 0.0,
+                "type": "integer"
+              },
+              "token": {
+                "type": "string"
+              }
+            },
+            "required": [
+              "op",
+              "pane_id",
+              "token"
+            ],
+            "type": "object"
+          },
+          {
+            "description": "Sent by `jelly-j ui` once it is connected to the daemon; starts liveness tracking for its pane.",
+            "properties": {
+              "client_id": {
+                "description": "The id the UI registered with at the daemon.",
+                "type": "string"
+              },
+              "op": {
+                "enum": [
+                  "register_ui"
+                ],
+                "type": "string"
+              },
+              "pane_id": {
+                "format": "uint32",
+                "minimum": 0.0,
+                "type": "integer"
+              },
+              "pid": {
+                "format": "uint32",
+                "minimum": 0.0,
+                "type": "integer"
+              }
+            },
+            "required": [
+              "client_id",
+              "op",
+              "pane_id",
+              "pid"
+            ],
+            "type": "object"
+          },
+          {
+            "description": "Periodic liveness ping from a registered UI. Fails with `ui_not_registered` after a plugin reload, telling the UI to send `register_ui` again.",
+            "properties": {
+              "op": {
+                "enum": [
+                  "ui_heartbeat"
+                ],
+                "type": "string"
+              },
+              "pane_id": {
+                "format": "uint32",
+                "minimum": 0.0,
+                "type": "integer"
+              }
+            },
+            "required": [
+              "op",
+              "pane_id"
+            ],
+            "type": "object"
+          },
+          {
+            "description": "Everything the butler knows about one terminal pane, exited panes included. A selector must match exactly one pane (`ambiguous_selector` otherwise).",
+            "properties": {
+              "op": {
+                "enum": [
+                  "get_pane_info"
+                ],
+                "type": "string"
+              },
+              "pane_id": {
+                "$ref": "#/definitions/PaneTarget"
+              }
+            },
+            "required": [
+              "op",
+              "pane_id"
+            ],
+            "type": "object"
+          },
+          {
+            "description": "Names the `auto_name_tabs` rules would give default-named (`Tab #N`) tabs. Fails with `auto_name_tabs_off` unless the config is `suggest` or `apply`.",
+            "properties": {
+              "op": {
+                "enum": [
+                  "get_tab_name_suggestions"
+                ],
+                "type": "string"
+              }
+            },
+            "required": [
+              "op"
+            ],
+            "type": "object"
+          },
+          {
+            "properties": {
+              "op": {
+                "enum": [
+                  "list_rules"
+                ],
+                "type": "string"
+              }
+            },
+            "required": [
+              "op"
+            ],
+            "type": "object"
+          },
+          {
+            "description": "Adds a rule to the rules file. Fails with `rule_exists` if the id is taken.",
+            "properties": {
+              "op": {
+                "enum": [
+                  "add_rule"
+                ],
+                "type": "string"
+              },
+              "rule": {
+                "$ref": "#/definitions/WorkspaceRule"
+              }
+            },
+            "required": [
+              "op",
+              "rule"
+            ],
+            "type": "object"
+          },
+          {
+            "description": "Removes a rule from the rules file; rules from the plugin config are read-only.",
+            "properties": {
+              "id": {
+                "type": "string"
+              },
+              "op": {
+                "enum": [
+                  "remove_rule"
+                ],
+                "type": "string"
+              }
+            },
+            "required": [
+              "id",
+              "op"
+            ],
+            "type": "object"
+          },
+          {
+            "description": "Tabs and terminal panes with their activity, stalest first.",
+            "properties": {
+              "op": {
+                "enum": [
+                  "get_activity"
+                ],
+                "type": "string"
+              }
+            },
+            "required": [
+              "op"
+            ],
+            "type": "object"
+          },
+          {
+            "description": "Focuses the pane `steps` (default 1) back in the focus history, skipping the pane focused now. Fails with `no_focus_history` if the history is shorter.",
+            "properties": {
+              "op": {
+                "enum": [
+                  "focus_back"
+                ],
+                "type": "string"
+              },
+              "steps": {
+                "format": "uint",
+                "minimum": 0.0,
+                "type": [
+                  "integer",
+                  "null"
+                ]
+              }
+            },
+            "required": [
+              "op"
+            ],
+            "type": "object"
+          },
+          {
+            "properties": {
+              "op": {
+                "enum": [
+                  "get_focus_history"
+                ],
+                "type": "string"
+              }
+            },
+            "required": [
+              "op"
+            ],
+            "type": "object"
+          },
+          {
+            "description": "Adds labels such as `project:api` to panes; they survive renames and reloads.",
+            "properties": {
+              "op": {
+                "enum": [
+                  "tag_pane"
+                ],
+                "type": "string"
+              },
+              "pane_id": {
+                "$ref": "#/definitions/PaneTarget"
+              },
+              "tags": {
+                "items": {
+                  "type": "string"
+                },
+                "type": "array"
+              }
+            },
+            "required": [
+              "op",
+              "pane_id",
+              "tags"
+            ],
+            "type": "object"
+          },
+          {
+            "description": "Removes `tags`, or every tag when omitted.",
+            "properties": {
+              "op": {
+                "enum": [
+                  "untag_pane"
+                ],
+                "type": "string"
+              },
+              "pane_id": {
+                "$ref": "#/definitions/PaneTarget"
+              },
+              "tags": {
+                "items": {
+                  "type": "string"
+                },
+                "type": [
+                  "array",
+                  "null"
+                ]
+              }
+            },
+            "required": [
+              "op",
+              "pane_id"
+            ],
+            "type": "object"
+          },
+          {
+            "properties": {
+              "op": {
+                "enum": [
+                  "list_tags"
+                ],
+                "type": "string"
+              }
+            },
+            "required": [
+              "op"
+            ],
+            "type": "object"
+          },
+          {
+            "description": "Hides every matching pane that is not hidden yet. Jelly panes are never matched by the bulk ops; toggling them is the butler's job.",
+            "properties": {
+              "op": {
+                "enum": [
+                  "hide_panes"
+                ],
+                "type": "string"
+              },
+              "selector": {
+                "type": "string"
+              }
+            },
+            "required": [
+              "op",
+              "selector"
+            ],
+            "type": "object"
+          },
+          {
+            "description": "Shows every matching hidden pane, without focusing them unless asked.",
+            "properties": {
+              "op": {
+                "enum": [
+                  "show_panes"
+                ],
+                "type": "string"
+              },
+              "selector": {
+                "type": "string"
+              },
+              "should_float_if_hidden": {
+                "type": [
+                  "boolean",
+                  "null"
+                ]
+              },
+              "should_focus_pane": {
+                "type": [
+                  "boolean",
+                  "null"
+                ]
+              }
+            },
+            "required": [
+              "op",
+              "selector"
+            ],
+            "type": "object"
+          },
+          {
+            "description": "Floats every matching visible tiled pane.",
+            "properties": {
+              "op": {
+                "enum": [
+                  "float_panes"
+                ],
+                "type": "string"
+              },
+              "selector": {
+                "type": "string"
+              }
+            },
+            "required": [
+              "op",
+              "selector"
+            ],
+            "type": "object"
+          },
+          {
+            "description": "Closes every matching pane; `dry_run` only reports which ones would close.",
+            "properties": {
+              "dry_run": {
+                "default": false,
+                "type": "boolean"
+              },
+              "op": {
+                "enum": [
+                  "close_panes"
+                ],
+                "type": "string"
+              },
+              "selector": {
+                "type": "string"
+              }
+            },
+            "required": [
+              "op",
+              "selector"
+            ],
+            "type": "object"
+          },
+          {
+            "description": "Hides, shows, moves or launches the `scratch.<name>.command` pane, like the Jelly toggle. `client_id` picks whose tab it lands in (default: the active tab).",
+            "properties": {
+              "client_id": {
+                "format": "uint16",
+                "minimum": 0.0,
+                "type": [
+                  "integer",
+                  "null"
+                ]
+              },
+              "name": {
+                "type": "string"
+              },
+              "op": {
+                "enum": [
+                  "toggle_scratch"
+                ],
+                "type": "string"
+              }
+            },
+            "required": [
+              "name",
+              "op"
+            ],
+            "type": "object"
+          },
+          {
+            "description": "Opens `path` in the user's `$EDITOR` at `line` (1-based): as a new tiled pane, floating, or in place of `in_place_of_pane_id` (an id, or a selector matching one pane), which comes back when the editor exits. Relative paths resolve against the focused pane's cwd.",
+            "properties": {
+              "floating": {
+                "default": false,
+                "type": "boolean"
+              },
+              "in_place_of_pane_id": {
+                "anyOf": [
+                  {
+                    "$ref": "#/definitions/PaneTarget"
+                  },
+                  {
+                    "type": "null"
+                  }
+                ]
+              },
+              "line": {
+                "format": "uint",
+                "minimum": 0.0,
+                "type": [
+                  "integer",
+                  "null"
+                ]
+              },
+              "op": {
+                "enum": [
+                  "open_file"
+                ],
+                "type": "string"
+              },
+              "path": {
+                "type": "string"
+              }
+            },
+            "required": [
+              "op",
+              "path"
+            ],
+            "type": "object"
+          },
+          {
+            "description": "Filesystem change summaries with a `seq` above `since`, oldest first. Empty unless the `fs_watch` config is on.",
+            "properties": {
+              "op": {
+                "enum": [
+                  "get_fs_changes"
+                ],
+                "type": "string"
+              },
+              "since": {
+                "format": "uint64",
+                "minimum": 0.0,
+                "type": [
+                  "integer",
+                  "null"
+                ]
+              }
+            },
+            "required": [
+              "op"
+            ],
+            "type": "object"
+          }
+        ]
+      },
+      "PaneTarget": {
+        "anyOf": [
+          {
+            "format": "uint32",
+            "minimum": 0.0,
+            "type": "integer"
+          },
+          {
+            "type": "string"
+          }
+        ],
+        "description": "A terminal pane id, or a selector such as `\"tag:role:logs\"` that may match several panes (see `plugin/src/tags.rs`)."
+      },
+      "RuleTrigger": {
+        "oneOf": [
+          {
+            "description": "A terminal pane's command exited.",
+            "properties": {
+              "command": {
+                "description": "Substring of the pane's command.",
+                "type": [
+                  "string",
+                  "null"
+                ]
+              },
+              "event": {
+                "enum": [
+                  "pane_exited"
+                ],
+                "type": "string"
+              },
+              "nonzero_only": {
+                "default": false,
+                "description": "Only fire for a non-zero exit status.",
+                "type": "boolean"
+              }
+            },
+            "required": [
+              "event"
+            ],
+            "type": "object"
+          },
+          {
+            "description": "A terminal pane appeared.",
+            "properties": {
+              "command": {
+                "type": [
+                  "string",
+                  "null"
+                ]
+              },
+              "event": {
+                "enum": [
+                  "pane_opened"
+                ],
+                "type": "string"
+              },
+              "title": {
+                "description": "Substring of the pane title.",
+                "type": [
+                  "string",
+                  "null"
+                ]
+              }
+            },
+            "required": [
+              "event"
+            ],
+            "type": "object"
+          },
+          {
+            "description": "A tab with this exact name appeared (created or renamed).",
+            "properties": {
+              "event": {
+                "enum": [
+                  "tab_appeared"
+                ],
+                "type": "string"
+              },
+              "name": {
+                "type": "string"
+              }
+            },
+            "required": [
+              "event",
+              "name"
+            ],
+            "type": "object"
+          }
+        ]
+      },
+      "TraceCategory": {
+        "oneOf": [
+          {
+            "enum": [
+              "permission",
+              "pipe",
+              "toggle",
+              "request"
+            ],
+            "type": "string"
+          },
+          {
+            "description": "Plugin load, subscriptions and state events.",
+            "enum": [
+              "lifecycle"
+            ],
+            "type": "string"
+          },
+          {
+            "description": "Workspace rules firing.",
+            "enum": [
+              "rule"
+            ],
+            "type": "string"
+          },
+          {
+            "description": "Filesystem change summaries.",
+            "enum": [
+              "fs"
+            ],
+            "type": "string"
+          }
+        ]
+      },
+      "TraceFormat": {
+        "enum": [
+          "text",
+          "json"
+        ],
+        "type": "string"
+      },
+      "TraceLevel": {
+        "enum": [
+          "debug",
+          "info",
+          "warn",
+          "error"
+        ],
+        "type": "string"
+      },
+      "WorkspaceRule": {
+        "description": "\"When `when` happens, run `actions`\": see `plugin/src/rules.rs`.",
+        "properties": {
+          "actions": {
+            "default": [],
+            "description": "Request objects as sent to the `request` pipe. `$pane_id` and `$tab_position` in any string are replaced with the pane and tab that fired the rule (a string that is only the placeholder becomes a number), e.g. `\"tab:$tab_position\"`; `tab_appeared` rules have no pane, so they may not use `$pane_id`.",
+            "items": true,
+            "type": "array"
+          },
+          "cooldown_ms": {
+            "description": "Minimum time between two firings, default 1000. Matches from the same update all run.",
+            "format": "uint64",
+            "minimum": 0.0,
+            "type": [
+              "integer",
+              "null"
+            ]
+          },
+          "id": {
+            "type": "string"
+          },
+          "trace_message": {
+            "description": "Logged as a `rule` trace entry at `warn` when the rule fires; nothing is shown in the UI. Read it back with `get_trace { category: \"rule\" }`. Formerly `notify`.",
+            "type": [
+              "string",
+              "null"
+            ]
+          },
+          "when": {
+            "$ref": "#/definitions/RuleTrigger"
+          }
+        },
+        "required": [
+          "id",
+          "when"
+        ],
+        "type": "object"
+      }
+    },
+    "oneOf": [
+      {
+        "properties": {
+          "op": {
+            "enum": [
+              "hello"
+            ],
+            "type": "string"
+          }
+        },
+        "required": [
+          "op"
+        ],
+        "type": "object"
+      },
+      {
+        "properties": {
+          "op": {
+            "enum": [
+              "get_schema"
+            ],
+            "type": "string"
+          }
+        },
+        "required": [
+          "op"
+        ],
+        "type": "object"
+      },
+      {
+        "properties": {
+          "op": {
+            "enum": [
+              "ping"
+            ],
+            "type": "string"
+          }
+        },
+        "required": [
+          "op"
+        ],
+        "type": "object"
+      },
+      {
+        "properties": {
+          "op": {
+            "enum": [
+              "get_state"
+            ],
+            "type": "string"
+          }
+        },
+        "required": [
+          "op"
+        ],
+        "type": "object"
+      },
+      {
+        "properties": {
+          "op": {
+            "enum": [
+              "get_metrics"
+            ],
+            "type": "string"
+          }
+        },
+        "required": [
+          "op"
+        ],
+        "type": "object"
+      },
+      {
+        "properties": {
+          "category": {
+            "anyOf": [
+              {
+                "$ref": "#/definitions/TraceCategory"
+              },
+              {
+                "type": "null"
+              }
+            ]
+          },
+          "format": {
+            "anyOf": [
+              {
+                "$ref": "#/definitions/TraceFormat"
+              },
+              {
+                "type": "null"
+              }
+            ],
+            "description": "`text` (default) keeps the legacy `\"0001 +12ms message k=v\"` lines."
+          },
+          "level": {
+            "anyOf": [
+              {
+                "$ref": "#/definitions/TraceLevel"
+              },
+              {
+                "type": "null"
+              }
+            ],
+            "description": "Minimum level to include."
+          },
+          "limit": {
+            "format": "uint",
+            "minimum": 0.0,
+            "type": [
+              "integer",
+              "null"
+            ]
+          },
+          "op": {
+            "enum": [
+              "get_trace"
+            ],
+            "type": "string"
+          },
+          "since_seq": {
+            "description": "Only entries with a greater `seq`.",
+            "format": "uint64",
+            "minimum": 0.0,
+            "type": [
+              "integer",
+              "null"
+            ]
+          }
+        },
+        "required": [
+          "op"
+        ],
+        "type": "object"
+      },
+      {
+        "properties": {
+          "op": {
+            "enum": [
+              "clear_trace"
+            ],
+            "type": "string"
+          }
+        },
+        "required": [
+          "op"
+        ],
+        "type": "object"
+      },
+      {
+        "description": "Writes the trace as JSON lines to a file under the plugin's `/data` or `/tmp` folder; any other path fails with `invalid_path`. Includes the persisted log files when `trace_log` is configured.",
+        "properties": {
+          "op": {
+            "enum": [
+              "export_trace"
+            ],
+            "type": "string"
+          },
+          "path": {
+            "type": "string"
+          }
+        },
+        "required": [
+          "op",
+          "path"
+        ],
+        "type": "object"
+      },
+      {
+        "properties": {
+          "name": {
+            "type": "string"
+          },
+          "op": {
+            "enum": [
+              "rename_tab"
+            ],
+            "type": "string"
+          },
+          "position": {
+            "format": "uint",
+            "minimum": 0.0,
+            "type": "integer"
+          }
+        },
+        "required": [
+          "name",
+          "op",
+          "position"
+        ],
+        "type": "object"
+      },
+      {
+        "properties": {
+          "name": {
+            "type": "string"
+          },
+          "op": {
+            "enum": [
+              "rename_pane"
+            ],
+            "type": "string"
+          },
+          "pane_id": {
+            "$ref": "#/definitions/PaneTarget"
+          }
+        },
+        "required": [
+          "name",
+          "op",
+          "pane_id"
+        ],
+        "type": "object"
+      },
+      {
+        "properties": {
+          "op": {
+            "enum": [
+              "hide_pane"
+            ],
+            "type": "string"
+          },
+          "pane_id": {
+            "$ref": "#/definitions/PaneTarget"
+          }
+        },
+        "required": [
+          "op",
+          "pane_id"
+        ],
+        "type": "object"
+      },
+      {
+        "properties": {
+          "op": {
+            "enum": [
+              "show_pane"
+            ],
+            "type": "string"
+          },
+          "pane_id": {
+            "$ref": "#/definitions/PaneTarget"
+          },
+          "should_float_if_hidden": {
+            "type": [
+              "boolean",
+              "null"
+            ]
+          },
+          "should_focus_pane": {
+            "type": [
+              "boolean",
+              "null"
+            ]
+          }
+        },
+        "required": [
+          "op",
+          "pane_id"
+        ],
+        "type": "object"
+      },
+      {
+        "properties": {
+          "atomic": {
+            "default": false,
+            "type": "boolean"
+          },
+          "op": {
+            "enum": [
+              "batch"
+            ],
+            "type": "string"
+          },
+          "requests": {
+            "items": {
+              "$ref": "#/definitions/ButlerRequest"
+            },
+            "type": "array"
+          },
+          "stop_on_error": {
+            "default": false,
+            "type": "boolean"
+          }
+        },
+        "required": [
+          "op",
+          "requests"
+        ],
+        "type": "object"
+      },
+      {
+        "description": "Starts (or restarts) recording raw inbound events for replay.",
+        "properties": {
+          "limit": {
+            "format": "uint",
+            "minimum": 0.0,
+            "type": [
+              "integer",
+              "null"
+            ]
+          },
+          "op": {
+            "enum": [
+              "start_recording"
+            ],
+            "type": "string"
+          }
+        },
+        "required": [
+          "op"
+        ],
+        "type": "object"
+      },
+      {
+        "description": "Stops recording and returns what was captured.",
+        "properties": {
+          "op": {
+            "enum": [
+              "stop_recording"
+            ],
+            "type": "string"
+          }
+        },
+        "required": [
+          "op"
+        ],
+        "type": "object"
+      },
+      {
+        "properties": {
+          "op": {
+            "enum": [
+              "get_recording"
+            ],
+            "type": "string"
+          },
+          "since_seq": {
+            "format": "uint64",
+            "minimum": 0.0,
+            "type": [
+              "integer",
+              "null"
+            ]
+          }
+        },
+        "required": [
+          "op"
+        ],
+        "type": "object"
+      },
+      {
+        "description": "Sent by `jelly-j ui` at startup with the token the butler put in its environment (`JELLY_J_BUTLER_TOKEN`), proving the pane runs Jelly J.",
+        "properties": {
+          "op": {
+            "enum": [
+              "register_ui_pane"
+            ],
+            "type": "string"
+          },
+          "pane_id": {
+            "format": "uint32",
+            "minimum": 0.0,
+            "type": "integer"
+          },
+          "token": {
+            "type": "string"
+          }
+        },
+        "required": [
+          "op",
+          "pane_id",
+          "token"
+        ],
+        "type": "object"
+      },
+      {
+        "description": "Sent by `jelly-j ui` once it is connected to the daemon; starts liveness tracking for its pane.",
+        "properties": {
+          "client_id": {
+            "description": "The id the UI registered with at the daemon.",
+            "type": "string"
+          },
+          "op": {
+            "enum": [
+              "register_ui"
+            ],
+            "type": "string"
+          },
+          "pane_id": {
+            "format": "uint32",
+            "minimum": 0.0,
+            "type": "integer"
+          },
+          "pid": {
+            "format": "uint32",
+            "minimum": 0.0,
+            "type": "integer"
+          }
+        },
+        "required": [
+          "client_id",
+          "op",
+          "pane_id",
+          "pid"
+        ],
+        "type": "object"
+      },
+      {
+        "description": "Periodic liveness ping from a registered UI. Fails with `ui_not_registered` after a plugin reload, telling the UI to send `register_ui` again.",
+        "properties": {
+          "op": {
+            "enum": [
+              "ui_heartbeat"
+            ],
+            "type": "string"
+          },
+          "pane_id": {
+            "format": "uint32",
+            "minimum": 0.0,
+            "type": "integer"
+          }
+        },
+        "required": [
+          "op",
+          "pane_id"
+        ],
+        "type": "object"
+      },
+      {
+        "description": "Everything the butler knows about one terminal pane, exited panes included. A selector must match exactly one pane (`ambiguous_selector` otherwise).",
+        "properties": {
+          "op": {
+            "enum": [
+              "get_pane_info"
+            ],
+            "type": "string"
+          },
+          "pane_id": {
+            "$ref": "#/definitions/PaneTarget"
+          }
+        },
+        "required": [
+          "op",
+          "pane_id"
+        ],
+        "type": "object"
+      },
+      {
+        "description": "Names the `auto_name_tabs` rules would give default-named (`Tab #N`) tabs. Fails with `auto_name_tabs_off` unless the config is `suggest` or `apply`.",
+        "properties": {
+          "op": {
+            "enum": [
+              "get_tab_name_suggestions"
+            ],
+            "type": "string"
+          }
+        },
+        "required": [
+          "op"
+        ],
+        "type": "object"
+      },
+      {
+        "properties": {
+          "op": {
+            "enum": [
+              "list_rules"
+            ],
+            "type": "string"
+          }
+        },
+        "required": [
+          "op"
+        ],
+        "type": "object"
+      },
+      {
+        "description": "Adds a rule to the rules file. Fails with `rule_exists` if the id is taken.",
+        "properties": {
+          "op": {
+            "enum": [
+              "add_rule"
+            ],
+            "type": "string"
+          },
+          "rule": {
+            "$ref": "#/definitions/WorkspaceRule"
+          }
+        },
+        "required": [
+          "op",
+          "rule"
+        ],
+        "type": "object"
+      },
+      {
+        "description": "Removes a rule from the rules file; rules from the plugin config are read-only.",
+        "properties": {
+          "id": {
+            "type": "string"
+          },
+          "op": {
+            "enum": [
+              "remove_rule"
+            ],
+            "type": "string"
+          }
+        },
+        "required": [
+          "id",
+          "op"
+        ],
+        "type": "object"
+      },
+      {
+        "description": "Tabs and terminal panes with their activity, stalest first.",
+        "properties": {
+          "op": {
+            "enum": [
+              "get_activity"
+            ],
+            "type": "string"
+          }
+        },
+        "required": [
+          "op"
+        ],
+        "type": "object"
+      },
+      {
+        "description": "Focuses the pane `steps` (default 1) back in the focus history, skipping the pane focused now. Fails with `no_focus_history` if the history is shorter.",
+        "properties": {
+          "op": {
+            "enum": [
+              "focus_back"
+            ],
+            "type": "string"
+          },
+          "steps": {
+            "format": "uint",
+            "minimum": 0.0,
+            "type": [
+              "integer",
+              "null"
+            ]
+          }
+        },
+        "required": [
+          "op"
+        ],
+        "type": "object"
+      },
+      {
+        "properties": {
+          "op": {
+            "enum": [
+              "get_focus_history"
+            ],
+            "type": "string"
+          }
+        },
+        "required": [
+          "op"
+        ],
+        "type": "object"
+      },
+      {
+        "description": "Adds labels such as `project:api` to panes; they survive renames and reloads.",
+        "properties": {
+          "op": {
+            "enum": [
+              "tag_pane"
+            ],
+            "type": "string"
+          },
+          "pane_id": {
+            "$ref": "#/definitions/PaneTarget"
+          },
+          "tags": {
+            "items": {
+              "type": "string"
+            },
+            "type": "array"
+          }
+        },
+        "required": [
+          "op",
+          "pane_id",
+          "tags"
+        ],
+        "type": "object"
+      },
+      {
+        "description": "Removes `tags`, or every tag when omitted.",
+        "properties": {
+          "op": {
+            "enum": [
+              "untag_pane"
+            ],
+            "type": "string"
+          },
+          "pane_id": {
+            "$ref": "#/definitions/PaneTarget"
+          },
+          "tags": {
+            "items": {
+              "type": "string"
+            },
+            "type": [
+              "array",
+              "null"
+            ]
+          }
+        },
+        "required": [
+          "op",
+          "pane_id"
+        ],
+        "type": "object"
+      },
+      {
+        "properties": {
+          "op": {
+            "enum": [
+              "list_tags"
+            ],
+            "type": "string"
+          }
+        },
+        "required": [
+          "op"
+        ],
+        "type": "object"
+      },
+      {
+        "description": "Hides every matching pane that is not hidden yet. Jelly panes are never matched by the bulk ops; toggling them is the butler's job.",
+        "properties": {
+          "op": {
+            "enum": [
+              "hide_panes"
+            ],
+            "type": "string"
+          },
+          "selector": {
+            "type": "string"
+          }
+        },
+        "required": [
+          "op",
+          "selector"
+        ],
+        "type": "object"
+      },
+      {
+        "description": "Shows every matching hidden pane, without focusing them unless asked.",
+        "properties": {
+          "op": {
+            "enum": [
+              "show_panes"
+            ],
+            "type": "string"
+          },
+          "selector": {
+            "type": "string"
+          },
+          "should_float_if_hidden": {
+            "type": [
+              "boolean",
+              "null"
+            ]
+          },
+          "should_focus_pane": {
+            "type": [
+              "boolean",
+              "null"
+            ]
+          }
+        },
+        "required": [
+          "op",
+          "selector"
+        ],
+        "type": "object"
+      },
+      {
+        "description": "Floats every matching visible tiled pane.",
+        "properties": {
+          "op": {
+            "enum": [
+              "float_panes"
+            ],
+            "type": "string"
+          },
+          "selector": {
+            "type": "string"
+          }
+        },
+        "required": [
+          "op",
+          "selector"
+        ],
+        "type": "object"
+      },
+      {
+        "description": "Closes every matching pane; `dry_run` only reports which ones would close.",
+        "properties": {
+          "dry_run": {
+            "default": false,
+            "type": "boolean"
+          },
+          "op": {
+            "enum": [
+              "close_panes"
+            ],
+            "type": "string"
+          },
+          "selector": {
+            "type": "string"
+          }
+        },
+        "required": [
+          "op",
+          "selector"
+        ],
+        "type": "object"
+      },
+      {
+        "description": "Hides, shows, moves or launches the `scratch.<name>.command` pane, like the Jelly toggle. `client_id` picks whose tab it lands in (default: the active tab).",
+        "properties": {
+          "client_id": {
+            "format": "uint16",
+            "minimum": 0.0,
+            "type": [
+              "integer",
+              "null"
+            ]
+          },
+          "name": {
+            "type": "string"
+          },
+          "op": {
+            "enum": [
+              "toggle_scratch"
+            ],
+            "type": "string"
+          }
+        },
+        "required": [
+          "name",
+          "op"
+        ],
+        "type": "object"
+      },
+      {
+        "description": "Opens `path` in the user's `$EDITOR` at `line` (1-based): as a new tiled pane, floating, or in place of `in_place_of_pane_id` (an id, or a selector matching one pane), which comes back when the editor exits. Relative paths resolve against the focused pane's cwd.",
+        "properties": {
+          "floating": {
+            "default": false,
+            "type": "boolean"
+          },
+          "in_place_of_pane_id": {
+            "anyOf": [
+              {
+                "$ref": "#/definitions/PaneTarget"
+              },
+              {
+                "type": "null"
+              }
+            ]
+          },
+          "line": {
+            "format": "uint",
+            "minimum": 0.0,
+            "type": [
+              "integer",
+              "null"
+            ]
+          },
+          "op": {
+            "enum": [
+              "open_file"
+            ],
+            "type": "string"
+          },
+          "path": {
+            "type": "string"
+          }
+        },
+        "required": [
+          "op",
+          "path"
+        ],
+        "type": "object"
+      },
+      {
+        "description": "Filesystem change summaries with a `seq` above `since`, oldest first. Empty unless the `fs_watch` config is on.",
+        "properties": {
+          "op": {
+            "enum": [
+              "get_fs_changes"
+            ],
+            "type": "string"
+          },
+          "since": {
+            "format": "uint64",
+            "minimum": 0.0,
+            "type": [
+              "integer",
+              "null"
+            ]
+          }
+        },
+        "required": [
+          "op"
+        ],
+        "type": "object"
+      }
+    ],
+    "title": "ButlerRequest"
+  },
+  "results": {
+    "add_rule": {
+      "$schema": "http://json-schema.org/draft-07/schema#",
+      "properties": {
+        "ok": {
+          "type": "boolean"
+        }
+      },
+      "required": [
+        "ok"
+      ],
+      "title": "AckResult",
+      "type": "object"
+    },
+    "batch": {
+      "$schema": "http://json-schema.org/draft-07/schema#",
+      "properties": {
+        "completed": {
+          "format": "uint",
+          "minimum": 0.0,
+          "type": "integer"
+        },
+        "steps": {
+          "description": "One response envelope per executed step.",
+          "items": true,
+          "type": "array"
+        },
+        "stopped_early": {
+          "type": "boolean"
+        }
+      },
+      "required": [
+        "completed",
+        "steps",
+        "stopped_early"
+      ],
+      "title": "BatchResult",
+      "type": "object"
+    },
+    "clear_trace": {
+      "$schema": "http://json-schema.org/draft-07/schema#",
+      "properties": {
+        "ok": {
+          "type": "boolean"
+        }
+      },
+      "required": [
+        "ok"
+      ],
+      "title": "AckResult",
+      "type": "object"
+    },
+    "close_panes": {
+      "$schema": "http://json-schema.org/draft-07/schema#",
+      "description": "Result of `hide_panes`, `show_panes`, `float_panes` and `close_panes`.",
+      "properties": {
+        "dry_run": {
+          "type": "boolean"
+        },
+        "pane_ids": {
+          "description": "The panes acted on (or, with `dry_run`, that would be).",
+          "items": {
+            "format": "uint32",
+            "minimum": 0.0,
+            "type": "integer"
+          },
+          "type": "array"
+        }
+      },
+      "required": [
+        "dry_run",
+        "pane_ids"
+      ],
+      "title": "PanesResult",
+      "type": "object"
+    },
+    "export_trace": {
+      "$schema": "http://json-schema.org/draft-07/schema#",
+      "properties": {
+        "bytes": {
+          "format": "uint64",
+          "minimum": 0.0,
+          "type": "integer"
+        },
+        "entries": {
+          "format": "uint",
+          "minimum": 0.0,
+          "type": "integer"
+        },
+        "path": {
+          "type": "string"
+        },
+        "persisted": {
+          "description": "Whether the export came from the persisted log rather than the in-memory ring.",
+          "type": "boolean"
+        }
+      },
+      "required": [
+        "bytes",
+        "entries",
+        "path",
+        "persisted"
+      ],
+      "title": "ExportTraceResult",
+      "type": "object"
+    },
+    "float_panes": {
+      "$schema": "http://json-schema.org/draft-07/schema#",
+      "description": "Result of `hide_panes`, `show_panes`, `float_panes` and `close_panes`.",
+      "properties": {
+        "dry_run": {
+          "type": "boolean"
+        },
+        "pane_ids": {
+          "description": "The panes acted on (or, with `dry_run`, that would be).",
+          "items": {
+            "format": "uint32",
+            "minimum": 0.0,
+            "type": "integer"
+          },
+          "type": "array"
+        }
+      },
+      "required": [
+        "dry_run",
+        "pane_ids"
+      ],
+      "title": "PanesResult",
+      "type": "object"
+    },
+    "focus_back": {
+      "$schema": "http://json-schema.org/draft-07/schema#",
+      "description": "Result of `focus_back`: the pane that was focused.",
+      "properties": {
+        "pane_id": {
+          "format": "uint32",
+          "minimum": 0.0,
+          "type": "integer"
+        },
+        "tab_position": {
+          "format": "uint",
+          "minimum": 0.0,
+          "type": "integer"
+        }
+      },
+      "required": [
+        "pane_id",
+        "tab_position"
+      ],
+      "title": "FocusBackResult",
+      "type": "object"
+    },
+    "get_activity": {
+      "$schema": "http://json-schema.org/draft-07/schema#",
+      "definitions": {
+        "PaneActivity": {
+          "description": "Focus and change times the butler observed since it loaded.",
+          "properties": {
+            "first_seen_epoch_ms": {
+              "description": "When the butler first saw it; earlier history is unknown.",
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "focus_ms": {
+              "description": "Total time focused.",
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "id": {
+              "format": "uint32",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "idle_ms": {
+              "description": "Time since it was last focused or changed; 0 while focused.",
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "is_jelly": {
+              "type": "boolean"
+            },
+            "last_changed_epoch_ms": {
+              "description": "Last title, command or exit change (for tabs: name or any of its panes).",
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "last_focused_epoch_ms": {
+              "description": "Now while focused; `None` if never focused since `first_seen_epoch_ms`.",
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": [
+                "integer",
+                "null"
+              ]
+            },
+            "tab_index": {
+              "format": "uint",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "title": {
+              "type": "string"
+            }
+          },
+          "required": [
+            "first_seen_epoch_ms",
+            "focus_ms",
+            "id",
+            "idle_ms",
+            "is_jelly",
+            "last_changed_epoch_ms",
+            "tab_index",
+            "title"
+          ],
+          "type": "object"
+        },
+        "TabActivity": {
+          "description": "Focus and change times the butler observed since it loaded.",
+          "properties": {
+            "first_seen_epoch_ms": {
+              "description": "When the butler first saw it; earlier history is unknown.",
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "focus_ms": {
+              "description": "Total time focused.",
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "idle_ms": {
+              "description": "Time since it was last focused or changed; 0 while focused.",
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "last_changed_epoch_ms": {
+              "description": "Last title, command or exit change (for tabs: name or any of its panes).",
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "last_focused_epoch_ms": {
+              "description": "Now while focused; `None` if never focused since `first_seen_epoch_ms`.",
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": [
+                "integer",
+                "null"
+              ]
+            },
+            "name": {
+              "type": "string"
+            },
+            "position": {
+              "format": "uint",
+              "minimum": 0.0,
+              "type": "integer"
+            }
+          },
+          "required": [
+            "first_seen_epoch_ms",
+            "focus_ms",
+            "idle_ms",
+            "last_changed_epoch_ms",
+            "name",
+            "position"
+          ],
+          "type": "object"
+        }
+      },
+      "description": "Result of `get_activity`: both lists sorted by `idle_ms`, stalest first.",
+      "properties": {
+        "now_epoch_ms": {
+          "format": "uint64",
+          "minimum": 0.0,
+          "type": "integer"
+        },
+        "panes": {
+          "items": {
+            "$ref": "#/definitions/PaneActivity"
+          },
+          "type": "array"
+        },
+        "tabs": {
+          "items": {
+            "$ref": "#/definitions/TabActivity"
+          },
+          "type": "array"
+        }
+      },
+      "required": [
+        "now_epoch_ms",
+        "panes",
+        "tabs"
+      ],
+      "title": "ActivityResult",
+      "type": "object"
+    },
+    "get_focus_history": {
+      "$schema": "http://json-schema.org/draft-07/schema#",
+      "definitions": {
+        "PaneFocusEntry": {
+          "properties": {
+            "focused_epoch_ms": {
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "pane_id": {
+              "format": "uint32",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "tab_position": {
+              "format": "uint",
+              "minimum": 0.0,
+              "type": "integer"
+            }
+          },
+          "required": [
+            "focused_epoch_ms",
+            "pane_id",
+            "tab_position"
+          ],
+          "type": "object"
+        },
+        "TabFocusEntry": {
+          "properties": {
+            "focused_epoch_ms": {
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "position": {
+              "format": "uint",
+              "minimum": 0.0,
+              "type": "integer"
+            }
+          },
+          "required": [
+            "focused_epoch_ms",
+            "position"
+          ],
+          "type": "object"
+        }
+      },
+      "description": "Result of `get_focus_history`: most recent first, the current focus included. Jelly panes are not recorded.",
+      "properties": {
+        "panes": {
+          "items": {
+            "$ref": "#/definitions/PaneFocusEntry"
+          },
+          "type": "array"
+        },
+        "tabs": {
+          "items": {
+            "$ref": "#/definitions/TabFocusEntry"
+          },
+          "type": "array"
+        }
+      },
+      "required": [
+        "panes",
+        "tabs"
+      ],
+      "title": "FocusHistoryResult",
+      "type": "object"
+    },
+    "get_fs_changes": {
+      "$schema": "http://json-schema.org/draft-07/schema#",
+      "definitions": {
+        "FsChangeSummary": {
+          "description": "One burst of filesystem changes, paths relative to the watched folder.",
+          "properties": {
+            "created": {
+              "items": {
+                "type": "string"
+              },
+              "type": "array"
+            },
+            "deleted": {
+              "items": {
+                "type": "string"
+              },
+              "type": "array"
+            },
+            "first_epoch_ms": {
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "last_epoch_ms": {
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "omitted": {
+              "description": "Changed paths left out of the lists above to keep the summary small.",
+              "format": "uint",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "seq": {
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "updated": {
+              "items": {
+                "type": "string"
+              },
+              "type": "array"
+            }
+          },
+          "required": [
+            "created",
+            "deleted",
+            "first_epoch_ms",
+            "last_epoch_ms",
+            "omitted",
+            "seq",
+            "updated"
+          ],
+          "type": "object"
+        }
+      },
+      "description": "Result of `get_fs_changes`.",
+      "properties": {
+        "exclude": {
+          "items": {
+            "type": "string"
+          },
+          "type": "array"
+        },
+        "include": {
+          "items": {
+            "type": "string"
+          },
+          "type": "array"
+        },
+        "summaries": {
+          "items": {
+            "$ref": "#/definitions/FsChangeSummary"
+          },
+          "type": "array"
+        },
+        "watching": {
+          "description": "Whether the `fs_watch` config is on.",
+          "type": "boolean"
+        }
+      },
+      "required": [
+        "exclude",
+        "include",
+        "summaries",
+        "watching"
+      ],
+      "title": "FsChangesResult",
+      "type": "object"
+    },
+    "get_metrics": {
+      "$schema": "http://json-schema.org/draft-07/schema#",
+      "definitions": {
+        "EventRate": {
+          "properties": {
+            "count": {
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "per_minute": {
+              "description": "Average over the plugin's uptime.",
+              "format": "double",
+              "type": "number"
+            }
+          },
+          "required": [
+            "count",
+            "per_minute"
+          ],
+          "type": "object"
+        },
+        "LatencyHistogram": {
+          "properties": {
+            "count": {
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "counts": {
+              "description": "One count per `latency_bucket_bounds_ms` entry, plus a final overflow bucket.",
+              "items": {
+                "format": "uint64",
+                "minimum": 0.0,
+                "type": "integer"
+              },
+              "type": "array"
+            },
+            "max_ms": {
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "sum_ms": {
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            }
+          },
+          "required": [
+            "count",
+            "counts",
+            "max_ms",
+            "sum_ms"
+          ],
+          "type": "object"
+        },
+        "PaneExitMetrics": {
+          "description": "Exits of tracked Jelly panes' commands and what `on_exit` did about them.",
+          "properties": {
+            "backoffs": {
+              "description": "Restarts delayed by the crash-loop backoff.",
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "closed": {
+              "description": "Exited panes closed (`on_exit \"close\"`), relaunched if they were on screen.",
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "detected": {
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "reruns": {
+              "description": "Commands restarted in place (`on_exit \"rerun\"`).",
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            }
+          },
+          "required": [
+            "backoffs",
+            "closed",
+            "detected",
+            "reruns"
+          ],
+          "type": "object"
+        },
+        "ToggleMetrics": {
+          "properties": {
+            "dedup_window_ignored": {
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "duplicate_ignored": {
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "executed": {
+              "description": "Toggles that reached a show/hide/move/launch decision.",
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "grace_period_timeouts": {
+              "description": "Tracked Jelly panes forgotten after the missing-pane grace period.",
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "launch_failures": {
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "received": {
+              "description": "Toggle pipe messages, including ignored ones.",
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "zombies_replaced": {
+              "description": "Jelly panes closed and relaunched because their UI stopped heartbeating.",
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            }
+          },
+          "required": [
+            "dedup_window_ignored",
+            "duplicate_ignored",
+            "executed",
+            "grace_period_timeouts",
+            "launch_failures",
+            "received",
+            "zombies_replaced"
+          ],
+          "type": "object"
+        }
+      },
+      "properties": {
+        "errors_by_code": {
+          "additionalProperties": {
+            "format": "uint64",
+            "minimum": 0.0,
+            "type": "integer"
+          },
+          "type": "object"
+        },
+        "errors_by_op": {
+          "additionalProperties": {
+            "format": "uint64",
+            "minimum": 0.0,
+            "type": "integer"
+          },
+          "type": "object"
+        },
+        "events": {
+          "additionalProperties": {
+            "$ref": "#/definitions/EventRate"
+          },
+          "description": "Inbound events by kind (`pane_update`, `tab_update`, `pipe_toggle`, ...).",
+          "type": "object"
+        },
+        "latency_bucket_bounds_ms": {
+          "items": {
+            "format": "uint64",
+            "minimum": 0.0,
+            "type": "integer"
+          },
+          "type": "array"
+        },
+        "pane_exits": {
+          "$ref": "#/definitions/PaneExitMetrics"
+        },
+        "requests_by_op": {
+          "additionalProperties": {
+            "format": "uint64",
+            "minimum": 0.0,
+            "type": "integer"
+          },
+          "description": "Top-level requests; batch steps count towards `batch`.",
+          "type": "object"
+        },
+        "toggle_latency_ms": {
+          "additionalProperties": {
+            "$ref": "#/definitions/LatencyHistogram"
+          },
+          "description": "Pipe receipt to the acting host call, keyed by action (`hide`, `show`, `move`, `launch`, ...).",
+          "type": "object"
+        },
+        "toggles": {
+          "$ref": "#/definitions/ToggleMetrics"
+        },
+        "uptime_ms": {
+          "format": "uint64",
+          "minimum": 0.0,
+          "type": "integer"
+        }
+      },
+      "required": [
+        "errors_by_code",
+        "errors_by_op",
+        "events",
+        "latency_bucket_bounds_ms",
+        "pane_exits",
+        "requests_by_op",
+        "toggle_latency_ms",
+        "toggles",
+        "uptime_ms"
+      ],
+      "title": "MetricsResult",
+      "type": "object"
+    },
+    "get_pane_info": {
+      "$schema": "http://json-schema.org/draft-07/schema#",
+      "definitions": {
+        "ButlerActivity": {
+          "description": "Focus and change times the butler observed since it loaded.",
+          "properties": {
+            "first_seen_epoch_ms": {
+              "description": "When the butler first saw it; earlier history is unknown.",
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "focus_ms": {
+              "description": "Total time focused.",
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "idle_ms": {
+              "description": "Time since it was last focused or changed; 0 while focused.",
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "last_changed_epoch_ms": {
+              "description": "Last title, command or exit change (for tabs: name or any of its panes).",
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "last_focused_epoch_ms": {
+              "description": "Now while focused; `None` if never focused since `first_seen_epoch_ms`.",
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": [
+                "integer",
+                "null"
+              ]
+            }
+          },
+          "required": [
+            "first_seen_epoch_ms",
+            "focus_ms",
+            "idle_ms",
+            "last_changed_epoch_ms"
+          ],
+          "type": "object"
+        },
+        "PaneGeometry": {
+          "description": "Floating position and size of a pane, in cells.",
+          "properties": {
+            "columns": {
+              "format": "uint",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "rows": {
+              "format": "uint",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "x": {
+              "format": "uint",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "y": {
+              "format": "uint",
+              "minimum": 0.0,
+              "type": "integer"
+            }
+          },
+          "required": [
+            "columns",
+            "rows",
+            "x",
+            "y"
+          ],
+          "type": "object"
+        }
+      },
+      "description": "Result of `get_pane_info`.",
+      "properties": {
+        "activity": {
+          "anyOf": [
+            {
+              "$ref": "#/definitions/ButlerActivity"
+            },
+            {
+              "type": "null"
+            }
+          ],
+          "description": "Terminal panes only; see `plugin/src/activity.rs`."
+        },
+        "cwd": {
+          "description": "Last directory reported by `CwdChanged`; terminal panes only.",
+          "type": [
+            "string",
+            "null"
+          ]
+        },
+        "exit_status": {
+          "format": "int32",
+          "type": [
+            "integer",
+            "null"
+          ]
+        },
+        "exited": {
+          "type": "boolean"
+        },
+        "foreground_command": {
+          "description": "What the pane is running in the foreground, as last reported by `ListClients` for a client focused on it, else the command a command pane was started with.",
+          "type": [
+            "string",
+            "null"
+          ]
+        },
+        "geometry": {
+          "$ref": "#/definitions/PaneGeometry"
+        },
+        "id": {
+          "format": "uint32",
+          "minimum": 0.0,
+          "type": "integer"
+        },
+        "is_floating": {
+          "type": "boolean"
+        },
+        "is_focused": {
+          "type": "boolean"
+        },
+        "is_fullscreen": {
+          "type": "boolean"
+        },
+        "is_held": {
+          "type": "boolean"
+        },
+        "is_jelly": {
+          "description": "Whether the butler treats this pane as a Jelly pane.",
+          "type": "boolean"
+        },
+        "is_plugin": {
+          "type": "boolean"
+        },
+        "is_suppressed": {
+          "type": "boolean"
+        },
+        "registered_ui": {
+          "description": "Whether a `jelly-j ui` in this pane proved itself with its launch token.",
+          "type": "boolean"
+        },
+        "tab_index": {
+          "format": "uint",
+          "minimum": 0.0,
+          "type": "integer"
+        },
+        "tags": {
+          "description": "Labels from `tag_pane`, sorted.",
+          "items": {
+            "type": "string"
+          },
+          "type": "array"
+        },
+        "terminal_command": {
+          "type": [
+            "string",
+            "null"
+          ]
+        },
+        "title": {
+          "type": "string"
+        }
+      },
+      "required": [
+        "exited",
+        "geometry",
+        "id",
+        "is_floating",
+        "is_focused",
+        "is_fullscreen",
+        "is_held",
+        "is_jelly",
+        "is_plugin",
+        "is_suppressed",
+        "registered_ui",
+        "tab_index",
+        "tags",
+        "title"
+      ],
+      "title": "ButlerPaneInfo",
+      "type": "object"
+    },
+    "get_recording": {
+      "$schema": "http://json-schema.org/draft-07/schema#",
+      "properties": {
+        "dropped": {
+          "description": "Entries evicted because the recording hit its limit.",
+          "format": "uint64",
+          "minimum": 0.0,
+          "type": "integer"
+        },
+        "entries": {
+          "description": "Recorded entries (`seq`, `epoch_ms`, `event`); see `plugin/src/recording.rs`.",
+          "items": true,
+          "type": "array"
+        },
+        "recording": {
+          "type": "boolean"
+        }
+      },
+      "required": [
+        "dropped",
+        "entries",
+        "recording"
+      ],
+      "title": "RecordingResult",
+      "type": "object"
+    },
+    "get_schema": {
+      "type": "object"
+    },
+    "get_state": {
+      "$schema": "http://json-schema.org/draft-07/schema#",
+      "definitions": {
+        "ButlerActivity": {
+          "description": "Focus and change times the butler observed since it loaded.",
+          "properties": {
+            "first_seen_epoch_ms": {
+              "description": "When the butler first saw it; earlier history is unknown.",
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "focus_ms": {
+              "description": "Total time focused.",
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "idle_ms": {
+              "description": "Time since it was last focused or changed; 0 while focused.",
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "last_changed_epoch_ms": {
+              "description": "Last title, command or exit change (for tabs: name or any of its panes).",
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "last_focused_epoch_ms": {
+              "description": "Now while focused; `None` if never focused since `first_seen_epoch_ms`.",
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": [
+                "integer",
+                "null"
+              ]
+            }
+          },
+          "required": [
+            "first_seen_epoch_ms",
+            "focus_ms",
+            "idle_ms",
+            "last_changed_epoch_ms"
+          ],
+          "type": "object"
+        },
+        "ButlerClientPane": {
+          "properties": {
+            "client_id": {
+              "format": "uint16",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "pane_id": {
+              "format": "uint32",
+              "minimum": 0.0,
+              "type": "integer"
+            }
+          },
+          "required": [
+            "client_id",
+            "pane_id"
+          ],
+          "type": "object"
+        },
+        "ButlerPaneState": {
+          "properties": {
+            "activity": {
+              "anyOf": [
+                {
+                  "$ref": "#/definitions/ButlerActivity"
+                },
+                {
+                  "type": "null"
+                }
+              ],
+              "description": "Terminal panes only; see `plugin/src/activity.rs`."
+            },
+            "cwd": {
+              "description": "Last directory reported by `CwdChanged`; terminal panes only.",
+              "type": [
+                "string",
+                "null"
+              ]
+            },
+            "exited": {
+              "type": "boolean"
+            },
+            "foreground_command": {
+              "description": "What the pane is running in the foreground, as last reported by `ListClients` for a client focused on it, else the command a command pane was started with.",
+              "type": [
+                "string",
+                "null"
+              ]
+            },
+            "id": {
+              "format": "uint32",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "is_floating": {
+              "type": "boolean"
+            },
+            "is_focused": {
+              "type": "boolean"
+            },
+            "is_plugin": {
+              "type": "boolean"
+            },
+            "is_suppressed": {
+              "type": "boolean"
+            },
+            "tab_index": {
+              "format": "uint",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "tags": {
+              "description": "Labels from `tag_pane`, sorted.",
+              "items": {
+                "type": "string"
+              },
+              "type": "array"
+            },
+            "terminal_command": {
+              "type": [
+                "string",
+                "null"
+              ]
+            },
+            "title": {
+              "type": "string"
+            }
+          },
+          "required": [
+            "exited",
+            "id",
+            "is_floating",
+            "is_focused",
+            "is_plugin",
+            "is_suppressed",
+            "tab_index",
+            "tags",
+            "title"
+          ],
+          "type": "object"
+        },
+        "ButlerRuntimeState": {
+          "properties": {
+            "auto_name_tabs": {
+              "description": "`off`, `suggest` or `apply`.",
+              "type": "string"
+            },
+            "client_panes": {
+              "items": {
+                "$ref": "#/definitions/ButlerClientPane"
+              },
+              "type": "array"
+            },
+            "deferred_restarts": {
+              "description": "Exited panes waiting out the crash-loop backoff.",
+              "items": {
+                "format": "uint32",
+                "minimum": 0.0,
+                "type": "integer"
+              },
+              "type": "array"
+            },
+            "jelly_geometry": {
+              "anyOf": [
+                {
+                  "$ref": "#/definitions/PaneGeometry"
+                },
+                {
+                  "type": "null"
+                }
+              ],
+              "description": "Last floating geometry seen for the tracked Jelly pane."
+            },
+            "jelly_pane_id": {
+              "format": "uint32",
+              "minimum": 0.0,
+              "type": [
+                "integer",
+                "null"
+              ]
+            },
+            "last_cli_toggle_pipe_id": {
+              "type": [
+                "string",
+                "null"
+              ]
+            },
+            "launch_command": {
+              "type": "string"
+            },
+            "launch_cwd": {
+              "description": "`focused`, `home` or `fixed:<path>`.",
+              "type": "string"
+            },
+            "launch_env": {
+              "description": "Names of the extra `launch_env` variables; values are not echoed.",
+              "items": {
+                "type": "string"
+              },
+              "type": "array"
+            },
+            "lifetime": {
+              "$ref": "#/definitions/LifetimeCounters"
+            },
+            "on_exit": {
+              "description": "`rerun`, `close` or `keep`.",
+              "type": "string"
+            },
+            "pane_policy": {
+              "description": "`single`, `per_client` or `per_tab`.",
+              "type": "string"
+            },
+            "pane_update_count": {
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "pending_toggle": {
+              "type": "boolean"
+            },
+            "permission_denied": {
+              "type": "boolean"
+            },
+            "permission_result_seen": {
+              "type": "boolean"
+            },
+            "ready": {
+              "type": "boolean"
+            },
+            "registered_jelly_panes": {
+              "description": "Panes whose UI registered with its launch token; only these (or panes that look unmistakably like Jelly) are ever closed.",
+              "items": {
+                "format": "uint32",
+                "minimum": 0.0,
+                "type": "integer"
+              },
+              "type": "array"
+            },
+            "restored_jelly_pane": {
+              "description": "Whether the tracked pane id was restored from a previous plugin instance.",
+              "type": "boolean"
+            },
+            "scratch_panes": {
+              "description": "Configured scratch panes and the pane each is using.",
+              "items": {
+                "$ref": "#/definitions/ButlerScratchPane"
+              },
+              "type": "array"
+            },
+            "state_path": {
+              "description": "Where toggle state is persisted, when enabled.",
+              "type": [
+                "string",
+                "null"
+              ]
+            },
+            "tab_panes": {
+              "items": {
+                "$ref": "#/definitions/ButlerTabPane"
+              },
+              "type": "array"
+            },
+            "tab_update_count": {
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "trace_len": {
+              "format": "uint",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "trace_log_path": {
+              "type": [
+                "string",
+                "null"
+              ]
+            },
+            "ui_clients": {
+              "description": "UIs that sent `register_ui`, with their liveness.",
+              "items": {
+                "$ref": "#/definitions/ButlerUiClient"
+              },
+              "type": "array"
+            }
+          },
+          "required": [
+            "auto_name_tabs",
+            "client_panes",
+            "deferred_restarts",
+            "launch_command",
+            "launch_cwd",
+            "launch_env",
+            "lifetime",
+            "on_exit",
+            "pane_policy",
+            "pane_update_count",
+            "pending_toggle",
+            "permission_denied",
+            "permission_result_seen",
+            "ready",
+            "registered_jelly_panes",
+            "restored_jelly_pane",
+            "scratch_panes",
+            "tab_panes",
+            "tab_update_count",
+            "trace_len",
+            "ui_clients"
+          ],
+          "type": "object"
+        },
+        "ButlerScratchPane": {
+          "properties": {
+            "command": {
+              "type": "string"
+            },
+            "name": {
+              "type": "string"
+            },
+            "pane_id": {
+              "format": "uint32",
+              "minimum": 0.0,
+              "type": [
+                "integer",
+                "null"
+              ]
+            }
+          },
+          "required": [
+            "command",
+            "name"
+          ],
+          "type": "object"
+        },
+        "ButlerTabPane": {
+          "properties": {
+            "pane_id": {
+              "format": "uint32",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "tab_index": {
+              "format": "uint",
+              "minimum": 0.0,
+              "type": "integer"
+            }
+          },
+          "required": [
+            "pane_id",
+            "tab_index"
+          ],
+          "type": "object"
+        },
+        "ButlerTabState": {
+          "properties": {
+            "active": {
+              "type": "boolean"
+            },
+            "activity": {
+              "anyOf": [
+                {
+                  "$ref": "#/definitions/ButlerActivity"
+                },
+                {
+                  "type": "null"
+                }
+              ]
+            },
+            "name": {
+              "type": "string"
+            },
+            "position": {
+              "format": "uint",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "selectable_floating_panes_count": {
+              "format": "uint",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "selectable_tiled_panes_count": {
+              "format": "uint",
+              "minimum": 0.0,
+              "type": "integer"
+            }
+          },
+          "required": [
+            "active",
+            "name",
+            "position",
+            "selectable_floating_panes_count",
+            "selectable_tiled_panes_count"
+          ],
+          "type": "object"
+        },
+        "ButlerUiClient": {
+          "properties": {
+            "alive": {
+              "description": "Heard from within the heartbeat timeout.",
+              "type": "boolean"
+            },
+            "client_id": {
+              "type": "string"
+            },
+            "last_heartbeat_epoch_ms": {
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "pane_id": {
+              "format": "uint32",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "pid": {
+              "format": "uint32",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "stale": {
+              "description": "A toggle found it silent; its pane is replaced if it stays silent through the grace period.",
+              "type": "boolean"
+            }
+          },
+          "required": [
+            "alive",
+            "client_id",
+            "last_heartbeat_epoch_ms",
+            "pane_id",
+            "pid",
+            "stale"
+          ],
+          "type": "object"
+        },
+        "LifetimeCounters": {
+          "description": "Counters that survive plugin reloads (see `plugin/src/persist.rs`).",
+          "properties": {
+            "launches": {
+              "default": 0,
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "loads": {
+              "default": 0,
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "toggles": {
+              "default": 0,
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            }
+          },
+          "type": "object"
+        },
+        "PaneGeometry": {
+          "description": "Floating position and size of a pane, in cells.",
+          "properties": {
+            "columns": {
+              "format": "uint",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "rows": {
+              "format": "uint",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "x": {
+              "format": "uint",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "y": {
+              "format": "uint",
+              "minimum": 0.0,
+              "type": "integer"
+            }
+          },
+          "required": [
+            "columns",
+            "rows",
+            "x",
+            "y"
+          ],
+          "type": "object"
+        }
+      },
+      "properties": {
+        "butler": {
+          "$ref": "#/definitions/ButlerRuntimeState"
+        },
+        "panes": {
+          "items": {
+            "$ref": "#/definitions/ButlerPaneState"
+          },
+          "type": "array"
+        },
+        "tabs": {
+          "items": {
+            "$ref": "#/definitions/ButlerTabState"
+          },
+          "type": "array"
+        }
+      },
+      "required": [
+        "butler",
+        "panes",
+        "tabs"
+      ],
+      "title": "ButlerWorkspaceState",
+      "type": "object"
+    },
+    "get_tab_name_suggestions": {
+      "$schema": "http://json-schema.org/draft-07/schema#",
+      "definitions": {
+        "TabNameSuggestion": {
+          "properties": {
+            "current_name": {
+              "type": "string"
+            },
+            "name": {
+              "type": "string"
+            },
+            "position": {
+              "format": "uint",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "reason": {
+              "description": "Which rule produced the name: `repo`, `cwd` or `command`.",
+              "type": "string"
+            }
+          },
+          "required": [
+            "current_name",
+            "name",
+            "position",
+            "reason"
+          ],
+          "type": "object"
+        }
+      },
+      "description": "Result of `get_tab_name_suggestions`.",
+      "properties": {
+        "mode": {
+          "description": "The `auto_name_tabs` config: `suggest` or `apply`.",
+          "type": "string"
+        },
+        "suggestions": {
+          "items": {
+            "$ref": "#/definitions/TabNameSuggestion"
+          },
+          "type": "array"
+        }
+      },
+      "required": [
+        "mode",
+        "suggestions"
+      ],
+      "title": "TabNameSuggestionsResult",
+      "type": "object"
+    },
+    "get_trace": {
+      "$schema": "http://json-schema.org/draft-07/schema#",
+      "definitions": {
+        "TraceCategory": {
+          "oneOf": [
+            {
+              "enum": [
+                "permission",
+                "pipe",
+                "toggle",
+                "request"
+              ],
+              "type": "string"
+            },
+            {
+              "description": "Plugin load, subscriptions and state events.",
+              "enum": [
+                "lifecycle"
+              ],
+              "type": "string"
+            },
+            {
+              "description": "Workspace rules firing.",
+              "enum": [
+                "rule"
+              ],
+              "type": "string"
+            },
+            {
+              "description": "Filesystem change summaries.",
+              "enum": [
+                "fs"
+              ],
+              "type": "string"
+            }
+          ]
+        },
+        "TraceEntries": {
+          "anyOf": [
+            {
+              "items": {
+                "type": "string"
+              },
+              "type": "array"
+            },
+            {
+              "items": {
+                "$ref": "#/definitions/TraceEntry"
+              },
+              "type": "array"
+            }
+          ]
+        },
+        "TraceEntry": {
+          "properties": {
+            "category": {
+              "$ref": "#/definitions/TraceCategory"
+            },
+            "delta_ms": {
+              "description": "Milliseconds since the first trace entry of this plugin instance.",
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "epoch_ms": {
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "fields": {
+              "additionalProperties": {
+                "type": "string"
+              },
+              "type": "object"
+            },
+            "level": {
+              "$ref": "#/definitions/TraceLevel"
+            },
+            "message": {
+              "type": "string"
+            },
+            "seq": {
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            }
+          },
+          "required": [
+            "category",
+            "delta_ms",
+            "epoch_ms",
+            "fields",
+            "level",
+            "message",
+            "seq"
+          ],
+          "type": "object"
+        },
+        "TraceLevel": {
+          "enum": [
+            "debug",
+            "info",
+            "warn",
+            "error"
+          ],
+          "type": "string"
+        }
+      },
+      "properties": {
+        "entries": {
+          "$ref": "#/definitions/TraceEntries"
+        }
+      },
+      "required": [
+        "entries"
+      ],
+      "title": "TraceResult",
+      "type": "object"
+    },
+    "hello": {
+      "$schema": "http://json-schema.org/draft-07/schema#",
+      "properties": {
+        "crate_version": {
+          "type": "string"
+        },
+        "granted_permissions": {
+          "items": {
+            "type": "string"
+          },
+          "type": "array"
+        },
+        "permission_status": {
+          "type": "string"
+        },
+        "protocol_version": {
+          "format": "uint32",
+          "minimum": 0.0,
+          "type": "integer"
+        },
+        "supported_ops": {
+          "items": {
+            "type": "string"
+          },
+          "type": "array"
+        }
+      },
+      "required": [
+        "crate_version",
+        "granted_permissions",
+        "permission_status",
+        "protocol_version",
+        "supported_ops"
+      ],
+      "title": "HelloResult",
+      "type": "object"
+    },
+    "hide_pane": {
+      "$schema": "http://json-schema.org/draft-07/schema#",
+      "properties": {
+        "ok": {
+          "type": "boolean"
+        }
+      },
+      "required": [
+        "ok"
+      ],
+      "title": "AckResult",
+      "type": "object"
+    },
+    "hide_panes": {
+      "$schema": "http://json-schema.org/draft-07/schema#",
+      "description": "Result of `hide_panes`, `show_panes`, `float_panes` and `close_panes`.",
+      "properties": {
+        "dry_run": {
+          "type": "boolean"
+        },
+        "pane_ids": {
+          "description": "The panes acted on (or, with `dry_run`, that would be).",
+          "items": {
+            "format": "uint32",
+            "minimum": 0.0,
+            "type": "integer"
+          },
+          "type": "array"
+        }
+      },
+      "required": [
+        "dry_run",
+        "pane_ids"
+      ],
+      "title": "PanesResult",
+      "type": "object"
+    },
+    "list_rules": {
+      "$schema": "http://json-schema.org/draft-07/schema#",
+      "definitions": {
+        "ListedRule": {
+          "description": "\"When `when` happens, run `actions`\": see `plugin/src/rules.rs`.",
+          "properties": {
+            "actions": {
+              "default": [],
+              "description": "Request objects as sent to the `request` pipe. `$pane_id` and `$tab_position` in any string are replaced with the pane and tab that fired the rule (a string that is only the placeholder becomes a number), e.g. `\"tab:$tab_position\"`; `tab_appeared` rules have no pane, so they may not use `$pane_id`.",
+              "items": true,
+              "type": "array"
+            },
+            "cooldown_ms": {
+              "description": "Minimum time between two firings, default 1000. Matches from the same update all run.",
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": [
+                "integer",
+                "null"
+              ]
+            },
+            "fired": {
+              "description": "Times fired since plugin load.",
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": "integer"
+            },
+            "id": {
+              "type": "string"
+            },
+            "last_fired_epoch_ms": {
+              "format": "uint64",
+              "minimum": 0.0,
+              "type": [
+                "integer",
+                "null"
+              ]
+            },
+            "source": {
+              "description": "`config` (read-only) or `file`.",
+              "type": "string"
+            },
+            "trace_message": {
+              "description": "Logged as a `rule` trace entry at `warn` when the rule fires; nothing is shown in the UI. Read it back with `get_trace { category: \"rule\" }`. Formerly `notify`.",
+              "type": [
+                "string",
+                "null"
+              ]
+            },
+            "when": {
+              "$ref": "#/definitions/RuleTrigger"
+            }
+          },
+          "required": [
+            "fired",
+            "id",
+            "source",
+            "when"
+          ],
+          "type": "object"
+        },
+        "RuleTrigger": {
+          "oneOf": [
+            {
+              "description": "A terminal pane's command exited.",
+              "properties": {
+                "command": {
+                  "description": "Substring of the pane's command.",
+                  "type": [
+                    "string",
+                    "null"
+                  ]
+                },
+                "event": {
+                  "enum": [
+                    "pane_exited"
+                  ],
+                  "type": "string"
+                },
+                "nonzero_only": {
+                  "default": false,
+                  "description": "Only fire for a non-zero exit status.",
+                  "type": "boolean"
+                }
+              },
+              "required": [
+                "event"
+              ],
+              "type": "object"
+            },
+            {
+              "description": "A terminal pane appeared.",
+              "properties": {
+                "command": {
+                  "type": [
+                    "string",
+                    "null"
+                  ]
+                },
+                "event": {
+                  "enum": [
+                    "pane_opened"
+                  ],
+                  "type": "string"
+                },
+                "title": {
+                  "description": "Substring of the pane title.",
+                  "type": [
+                    "string",
+                    "null"
+                  ]
+                }
+              },
+              "required": [
+                "event"
+              ],
+              "type": "object"
+            },
+            {
+              "description": "A tab with this exact name appeared (created or renamed).",
+              "properties": {
+                "event": {
+                  "enum": [
+                    "tab_appeared"
+                  ],
+                  "type": "string"
+                },
+                "name": {
+                  "type": "string"
+                }
+              },
+              "required": [
+                "event",
+                "name"
+              ],
+              "type": "object"
+            }
+          ]
+        }
+      },
+      "description": "Result of `list_rules`.",
+      "properties": {
+        "path": {
+          "description": "The editable rules file.",
+          "type": "string"
+        },
+        "rules": {
+          "items": {
+            "$ref": "#/definitions/ListedRule"
+          },
+          "type": "array"
+        }
+      },
+      "required": [
+        "path",
+        "rules"
+      ],
+      "title": "ListRulesResult",
+      "type": "object"
+    },
+    "list_tags": {
+      "$schema": "http://json-schema.org/draft-07/schema#",
+      "definitions": {
+        "TagSummary": {
+          "properties": {
+            "pane_ids": {
+              "items": {
+                "format": "uint32",
+                "minimum": 0.0,
+                "type": "integer"
+              },
+              "type": "array"
+            },
+            "tag": {
+              "type": "string"
+            }
+          },
+          "required": [
+            "pane_ids",
+            "tag"
+          ],
+          "type": "object"
+        }
+      },
+      "description": "Result of `list_tags`: every tag in use, sorted.",
+      "properties": {
+        "tags": {
+          "items": {
+            "$ref": "#/definitions/TagSummary"
+          },
+          "type": "array"
+        }
+      },
+      "required": [
+        "tags"
+      ],
+      "title": "ListTagsResult",
+      "type": "object"
+    },
+    "open_file": {
+      "$schema": "http://json-schema.org/draft-07/schema#",
+      "description": "Result of `open_file`: the editor pane.",
+      "properties": {
+        "pane_id": {
+          "format": "uint32",
+          "minimum": 0.0,
+          "type": "integer"
+        }
+      },
+      "required": [
+        "pane_id"
+      ],
+      "title": "OpenFileResult",
+      "type": "object"
+    },
+    "ping": {
+      "$schema": "http://json-schema.org/draft-07/schema#",
+      "properties": {
+        "ok": {
+          "type": "boolean"
+        }
+      },
+      "required": [
+        "ok"
+      ],
+      "title": "AckResult",
+      "type": "object"
+    },
+    "register_ui": {
+      "$schema": "http://json-schema.org/draft-07/schema#",
+      "properties": {
+        "ok": {
+          "type": "boolean"
+        }
+      },
+      "required": [
+        "ok"
+      ],
+      "title": "AckResult",
+      "type": "object"
+    },
+    "register_ui_pane": {
+      "$schema": "http://json-schema.org/draft-07/schema#",
+      "properties": {
+        "ok": {
+          "type": "boolean"
+        }
+      },
+      "required": [
+        "ok"
+      ],
+      "title": "AckResult",
+      "type": "object"
+    },
+    "remove_rule": {
+      "$schema": "http://json-schema.org/draft-07/schema#",
+      "properties": {
+        "ok": {
+          "type": "boolean"
+        }
+      },
+      "required": [
+        "ok"
+      ],
+      "title": "AckResult",
+      "type": "object"
+    },
+    "rename_pane": {
+      "$schema": "http://json-schema.org/draft-07/schema#",
+      "properties": {
+        "ok": {
+          "type": "boolean"
+        }
+      },
+      "required": [
+        "ok"
+      ],
+      "title": "AckResult",
+      "type": "object"
+    },
+    "rename_tab": {
+      "$schema": "http://json-schema.org/draft-07/schema#",
+      "properties": {
+        "ok": {
+          "type": "boolean"
+        }
+      },
+      "required": [
+        "ok"
+      ],
+      "title": "AckResult",
+      "type": "object"
+    },
+    "show_pane": {
+      "$schema": "http://json-schema.org/draft-07/schema#",
+      "properties": {
+        "ok": {
+          "type": "boolean"
+        }
+      },
+      "required": [
+        "ok"
+      ],
+      "title": "AckResult",
+      "type": "object"
+    },
+    "show_panes": {
+      "$schema": "http://json-schema.org/draft-07/schema#",
+      "description": "Result of `hide_panes`, `show_panes`, `float_panes` and `close_panes`.",
+      "properties": {
+        "dry_run": {
+          "type": "boolean"
+        },
+        "pane_ids": {
+          "description": "The panes acted on (or, with `dry_run`, that would be).",
+          "items": {
+            "format": "uint32",
+            "minimum": 0.0,
+            "type": "integer"
+          },
+          "type": "array"
+        }
+      },
+      "required": [
+        "dry_run",
+        "pane_ids"
+      ],
+      "title": "PanesResult",
+      "type": "object"
+    },
+    "start_recording": {
+      "$schema": "http://json-schema.org/draft-07/schema#",
+      "properties": {
+        "ok": {
+          "type": "boolean"
+        }
+      },
+      "required": [
+        "ok"
+      ],
+      "title": "AckResult",
+      "type": "object"
+    },
+    "stop_recording": {
+      "$schema": "http://json-schema.org/draft-07/schema#",
+      "properties": {
+        "dropped": {
+          "description": "Entries evicted because the recording hit its limit.",
+          "format": "uint64",
+          "minimum": 0.0,
+          "type": "integer"
+        },
+        "entries": {
+          "description": "Recorded entries (`seq`, `epoch_ms`, `event`); see `plugin/src/recording.rs`.",
+          "items": true,
+          "type": "array"
+        },
+        "recording": {
+          "type": "boolean"
+        }
+      },
+      "required": [
+        "dropped",
+        "entries",
+        "recording"
+      ],
+      "title": "RecordingResult",
+      "type": "object"
+    },
+    "tag_pane": {
+      "$schema": "http://json-schema.org/draft-07/schema#",
+      "properties": {
+        "ok": {
+          "type": "boolean"
+        }
+      },
+      "required": [
+        "ok"
+      ],
+      "title": "AckResult",
+      "type": "object"
+    },
+    "toggle_scratch": {
+      "$schema": "http://json-schema.org/draft-07/schema#",
+      "description": "Result of `toggle_scratch`.",
+      "properties": {
+        "action": {
+          "description": "`launch`, `show`, `hide`, `move` or `launch_skipped` (still starting).",
+          "type": "string"
+        },
+        "name": {
+          "type": "string"
+        },
+        "pane_id": {
+          "description": "The scratch pane, once one exists.",
+          "format": "uint32",
+          "minimum": 0.0,
+          "type": [
+            "integer",
+            "null"
+          ]
+        }
+      },
+      "required": [
+        "action",
+        "name"
+      ],
+      "title": "ScratchResult",
+      "type": "object"
+    },
+    "ui_heartbeat": {
+      "$schema": "http://json-schema.org/draft-07/schema#",
+      "properties": {
+        "ok": {
+          "type": "boolean"
+        }
+      },
+      "required": [
+        "ok"
+      ],
+      "title": "AckResult",
+      "type": "object"
+    },
+    "untag_pane": {
+      "$schema": "http://json-schema.org/draft-07/schema#",
+      "properties": {
+        "ok": {
+          "type": "boolean"
+        }
+      },
+      "required": [
+        "ok"
+      ],
+      "title": "AckResult",
+      "type": "object"
+    }
+  }
+} as const;
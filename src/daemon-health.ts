@@ -2,6 +2,7 @@ import { randomUUID } from "node:crypto";
 import { createConnection } from "node:net";
 import os from "node:os";
 import { DAEMON_SOCKET_PATH } from "./state.js";
+import { getButlerMetrics, type ButlerMetrics } from "./zellijPipe.js";
 import {
   encodeMessage,
   type ClientToDaemonMessage,
@@ -14,6 +15,12 @@ export type DaemonHealthProbeResult = {
   error?: string;
 };
 
+export type ButlerHealthProbeResult = {
+  ok: boolean;
+  metrics?: ButlerMetrics;
+  error?: string;
+};
+
 function send(socket: ReturnType<typeof createConnection>, message: ClientToDaemonMessage): void {
   socket.write(encodeMessage(message));
 }
@@ -119,3 +126,15 @@ export async function probeDaemonHealth(
     });
   });
 }
+
+/**
+ * Polls the butler plugin's counters. `get_metrics` needs no permissions or cache,
+ * so a failure here means the plugin itself is unreachable.
+ */
+export async function probeButlerHealth(): Promise<ButlerHealthProbeResult> {
+  try {
+    return { ok: true, metrics: await getButlerMetrics() };
+  } catch (error) {
+    return { ok: false, error: error instanceof Error ? error.message : String(error) };
+  }
+}
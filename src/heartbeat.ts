@@ -2,8 +2,10 @@ import { heartbeatQuery } from "./agent.js";
 import { logHeartbeatError, logHeartbeatInfo } from "./logging.js";
 import {
   getButlerState,
+  getButlerTabNameSuggestions,
   ZellijPipeError,
   type ButlerState,
+  type ButlerTabNameSuggestions,
 } from "./zellijPipe.js";
 import { setActiveZellijEnv, getActiveZellijEnv, zellijAction } from "./zellij.js";
 import type { ZellijEnvContext } from "./protocol.js";
@@ -47,7 +49,30 @@ export function removeHeartbeatSession(sessionName: string): void {
 
 // ── Heartbeat prompt ────────────────────────────────────────────────
 
-function buildHeartbeatPrompt(state: ButlerState, tabNames: string): string {
+/**
+ * The butler's rule-based tab names, or null when `auto_name_tabs` is off or the
+ * plugin predates the op.
+ */
+async function getRuleTabNames(): Promise<ButlerTabNameSuggestions | null> {
+  try {
+    return await getButlerTabNameSuggestions();
+  } catch {
+    return null;
+  }
+}
+
+function formatIdle(ms: number): string {
+  const minutes = Math.floor(ms / 60_000);
+  if (minutes < 1) return "<1m";
+  if (minutes < 60) return `${minutes}m`;
+  return `${Math.floor(minutes / 60)}h${minutes % 60 > 0 ? ` ${minutes % 60}m` : ""}`;
+}
+
+function buildHeartbeatPrompt(
+  state: ButlerState,
+  tabNames: string,
+  ruleNames: ButlerTabNameSuggestions | null
+): string {
   const now = new Date();
   const tz = Intl.DateTimeFormat().resolvedOptions().timeZone || "unknown";
 
@@ -60,9 +85,13 @@ function buildHeartbeatPrompt(state: ButlerState, tabNames: string): string {
         (p) => p.tab_index === tab.position && !p.is_plugin && !p.exited
       );
       const cmds = panes
-        .map((p) => p.terminal_command || p.title)
+        .map((p) => {
+          const cmd = p.foreground_command || p.terminal_command || p.title;
+          return cmd && p.cwd ? `${cmd} (in ${p.cwd})` : cmd;
+        })
         .filter(Boolean);
-      return `  Tab ${tab.position} "${tab.name}": ${cmds.length > 0 ? cmds.join(", ") : "(empty)"}`;
+      const idle = tab.activity ? ` (idle ${formatIdle(tab.activity.idle_ms)})` : "";
+      return `  Tab ${tab.position} "${tab.name}"${idle}: ${cmds.length > 0 ? cmds.join(", ") : "(empty)"}`;
     })
     .join("\n");
 
@@ -74,7 +103,13 @@ Tab names: ${tabNames}
 
 Per-tab commands:
 ${tabPaneSummaries}
-
+${
+  ruleNames && ruleNames.suggestions.length > 0
+    ? `\nRule-based name suggestions (prefer these unless clearly wrong):\n${ruleNames.suggestions
+        .map((s) => `  Tab ${s.position}: "${s.name}" (from ${s.reason})`)
+        .join("\n")}\n`
+    : ""
+}
 Full layout:
 ${JSON.stringify(state, null, 2)}
 
@@ -89,7 +124,7 @@ You are a workspace organization assistant. Analyze the workspace and respond wi
 2. "suggestion": a SHORT one-liner (max 80 chars) about other improvements, or null if nothing to suggest. Consider:
    - Overcrowded tabs (>4 panes → suggest splitting)
    - Similar panes across tabs (same command type → suggest grouping)
-   - Empty/idle tabs that could be closed
+   - Empty tabs, or tabs idle for hours, that could be closed
 
 Respond ONLY with valid JSON, no other text. Example:
 {"renames": [{"position": 1, "name": "editor"}, {"position": 3, "name": "logs"}], "suggestion": "Tab 2 has 6 panes — consider splitting dev and test panes"}
@@ -148,8 +183,15 @@ async function checkSession(sessionName: string, env: ZellijEnvContext): Promise
   try {
     const state = await getButlerState();
 
-    // Quick check: any unnamed tabs?
-    const unnamedTabs = state.tabs.filter((t) => DEFAULT_TAB_NAME.test(t.name));
+    // Quick check: any unnamed tabs? With `auto_name_tabs "apply"` the butler
+    // renames the ones its rules can name, so only the rest need the LLM.
+    const ruleNames = await getRuleTabNames();
+    const ruleNamed = new Set(
+      ruleNames?.mode === "apply" ? ruleNames.suggestions.map((s) => s.position) : []
+    );
+    const unnamedTabs = state.tabs.filter(
+      (t) => DEFAULT_TAB_NAME.test(t.name) && !ruleNamed.has(t.position)
+    );
     const overcrowded = state.tabs.some(
       (t) => t.selectable_tiled_panes_count + t.selectable_floating_panes_count > 4
     );
@@ -168,7 +210,7 @@ async function checkSession(sessionName: string, env: ZellijEnvContext): Promise
       )
       .join(", ");
 
-    const prompt = buildHeartbeatPrompt(state, tabNames);
+    const prompt = buildHeartbeatPrompt(state, tabNames, ruleNames);
     const rawResult = await heartbeatQuery(prompt);
     logHeartbeatInfo(`[${sessionName}] raw result: ${rawResult.slice(0, 200)}`);
 
@@ -196,6 +238,7 @@ async function checkSession(sessionName: string, env: ZellijEnvContext): Promise
       }
 
       for (const rename of result.renames) {
+        if (ruleNamed.has(rename.position)) continue;
         // Safety: only rename tabs that still have default names.
         const tab = state.tabs.find((t) => t.position === rename.position);
         if (!tab || !DEFAULT_TAB_NAME.test(tab.name)) {
@@ -6,8 +6,24 @@ import { zellijAction } from "./zellij.js";
 import {
   clearButlerTrace,
   getButlerState,
+  getButlerMetrics,
+  getButlerPaneInfo,
+  getButlerActivity,
+  getButlerFocusHistory,
+  getButlerFsChanges,
+  focusBack,
   getButlerTrace,
+  getButlerTraceEntries,
   hidePaneById,
+  floatPanes,
+  hidePanes,
+  showPanes,
+  closePanes,
+  toggleScratch,
+  openFileInEditor,
+  listButlerTags,
+  tagPane,
+  untagPane,
   renamePaneById,
   renameTabByPosition,
   showPaneById,
@@ -67,6 +83,71 @@ const getButlerStateTool = tool(
   }
 );
 
+const getPaneInfoTool = tool(
+  "get_pane_info",
+  "Return everything the Jelly J butler knows about one terminal pane: cwd, foreground command, geometry, exit status, and whether it is a Jelly pane.",
+  {
+    pane_id: z.number().int().min(0).describe("Terminal pane ID"),
+  },
+  async (args) => {
+    const info = await getButlerPaneInfo(args.pane_id);
+    return {
+      content: [{ type: "text", text: JSON.stringify(info, null, 2) }],
+    };
+  }
+);
+
+const getActivityTool = tool(
+  "get_activity",
+  "List tabs and panes by staleness: time since last focus or change, and total focus time, as observed by the Jelly J butler since it loaded. Use it before suggesting that idle tabs be closed.",
+  {},
+  async () => {
+    const activity = await getButlerActivity();
+    return {
+      content: [{ type: "text", text: JSON.stringify(activity, null, 2) }],
+    };
+  }
+);
+
+const getFsChangesTool = tool(
+  "get_fs_changes",
+  "List recent file changes in the session's working directory (created/updated/deleted paths, grouped into debounced bursts), e.g. to notice that tests or a config file were just edited. Requires fs_watch in the plugin config; pass the last seq seen as since to get only newer bursts.",
+  {
+    since: z.number().int().min(0).optional().describe("Only bursts with a higher seq"),
+  },
+  async (args) => {
+    const changes = await getButlerFsChanges(args.since);
+    if (!changes.watching) {
+      return {
+        content: [
+          {
+            type: "text",
+            text: 'Filesystem watching is off; set fs_watch "on" in the Jelly J plugin config.',
+          },
+        ],
+      };
+    }
+    return {
+      content: [{ type: "text", text: JSON.stringify(changes, null, 2) }],
+    };
+  }
+);
+
+const focusBackTool = tool(
+  "focus_back",
+  "Take the user back to a pane they were using before: focuses the pane `steps` back in the Jelly J butler's focus history (1 = the previous one). Also returns the recent history so you can describe where they were.",
+  {
+    steps: z.number().int().min(1).optional().describe("How far back to go (default 1)"),
+  },
+  async (args) => {
+    const focused = await focusBack(args.steps ?? 1);
+    const history = await getButlerFocusHistory();
+    return {
+      content: [{ type: "text", text: JSON.stringify({ focused, history }, null, 2) }],
+    };
+  }
+);
+
 const getButlerTraceTool = tool(
   "get_butler_trace",
   "Return recent Jelly J butler trace entries (state transitions and plugin actions).",
@@ -78,15 +159,38 @@ const getButlerTraceTool = tool(
       .max(200)
       .optional()
       .describe("Maximum number of trace entries to return"),
+    level: z
+      .enum(["debug", "info", "warn", "error"])
+      .optional()
+      .describe("Only return entries at or above this level"),
+    category: z
+      .enum(["lifecycle", "permission", "pipe", "toggle", "request", "rule", "fs"])
+      .optional()
+      .describe("Only return entries in this category"),
   },
   async (args) => {
-    const entries = await getButlerTrace(args.limit);
+    const entries =
+      args.level || args.category
+        ? await getButlerTraceEntries(args)
+        : await getButlerTrace(args.limit);
     return {
       content: [{ type: "text", text: JSON.stringify({ entries }, null, 2) }],
     };
   }
 );
 
+const getButlerMetricsTool = tool(
+  "get_butler_metrics",
+  "Return Jelly J butler counters: requests and errors per op, toggle outcomes and latency histograms, and event rates.",
+  {},
+  async () => {
+    const metrics = await getButlerMetrics();
+    return {
+      content: [{ type: "text", text: JSON.stringify(metrics, null, 2) }],
+    };
+  }
+);
+
 const clearButlerTraceTool = tool(
   "clear_butler_trace",
   "Clear the in-memory Jelly J butler trace buffer.",
@@ -318,11 +422,161 @@ const renamePane = tool(
   }
 );
 
+const paneTarget = z
+  .union([z.number().int().min(1), z.string().min(1)])
+  .describe('Terminal pane ID, or a selector such as "tag:role:logs"');
+
+const selectorDescription =
+  'Space-separated terms that must all match: tag:<tag>, tab:<0-based position>, title:<regex>, command:<regex>, exited:true|false, floating:true|false (e.g. "tab:1 command:^tail")';
+
+function bulkResultText(verb: string, result: { pane_ids: number[]; dry_run: boolean }): string {
+  if (result.pane_ids.length === 0) return "No panes matched";
+  const prefix = result.dry_run ? `Would ${verb}` : verb[0].toUpperCase() + verb.slice(1);
+  return `${prefix} ${result.pane_ids.length} pane(s): ${result.pane_ids.join(", ")}`;
+}
+
+const tagPaneTool = tool(
+  "tag_pane",
+  "Label panes with tags such as project:api or role:logs. Tags survive renames and plugin reloads, and tag selectors (\"tag:role:logs\") can replace a pane ID in other pane tools.",
+  {
+    pane_id: paneTarget,
+    tags: z.array(z.string().min(1)).min(1).describe("Tags to add"),
+  },
+  async (args) => {
+    await tagPane(args.pane_id, args.tags);
+    return {
+      content: [{ type: "text", text: `Tagged ${args.pane_id}: ${args.tags.join(", ")}` }],
+    };
+  }
+);
+
+const untagPaneTool = tool(
+  "untag_pane",
+  "Remove tags from panes; removes every tag when tags is omitted.",
+  {
+    pane_id: paneTarget,
+    tags: z.array(z.string().min(1)).optional().describe("Tags to remove"),
+  },
+  async (args) => {
+    await untagPane(args.pane_id, args.tags);
+    return {
+      content: [{ type: "text", text: `Untagged ${args.pane_id}` }],
+    };
+  }
+);
+
+const listTagsTool = tool(
+  "list_tags",
+  "List every pane tag in use with the pane IDs that carry it.",
+  {},
+  async () => {
+    const tags = await listButlerTags();
+    return {
+      content: [{ type: "text", text: JSON.stringify(tags, null, 2) }],
+    };
+  }
+);
+
+const hidePanesTool = tool(
+  "hide_panes",
+  "Hide every pane a selector matches that is not hidden already. Jelly J's own panes are never matched.",
+  {
+    selector: z.string().min(1).describe(selectorDescription),
+  },
+  async (args) => {
+    const result = await hidePanes(args.selector);
+    return { content: [{ type: "text", text: bulkResultText("hide", result) }] };
+  }
+);
+
+const showPanesTool = tool(
+  "show_panes",
+  "Show every hidden pane a selector matches, without moving focus unless asked.",
+  {
+    selector: z.string().min(1).describe(selectorDescription),
+    should_focus_pane: z.boolean().optional().describe("Focus each pane as it is shown"),
+  },
+  async (args) => {
+    const result = await showPanes(args.selector, true, args.should_focus_pane ?? false);
+    return { content: [{ type: "text", text: bulkResultText("show", result) }] };
+  }
+);
+
+const floatPanesTool = tool(
+  "float_panes",
+  "Float every visible tiled pane a selector matches. Jelly J's own panes are never matched.",
+  {
+    selector: z.string().min(1).describe(selectorDescription),
+  },
+  async (args) => {
+    const result = await floatPanes(args.selector);
+    return { content: [{ type: "text", text: bulkResultText("float", result) }] };
+  }
+);
+
+const closePanesTool = tool(
+  "close_panes",
+  "Close every pane a selector matches. Run with dry_run first to preview which panes would close, and confirm with the user before closing several.",
+  {
+    selector: z.string().min(1).describe(selectorDescription),
+    dry_run: z.boolean().optional().describe("Only report the panes that would close"),
+  },
+  async (args) => {
+    const result = await closePanes(args.selector, args.dry_run ?? false);
+    return { content: [{ type: "text", text: bulkResultText("close", result) }] };
+  }
+);
+
+const openFileTool = tool(
+  "open_file",
+  "Open a file in the user's $EDITOR at a line, e.g. to show them src/main.rs:42. Relative paths resolve against the user's focused pane's directory. Returns the editor pane ID so it can be focused or closed later.",
+  {
+    path: z.string().min(1).describe("File path, absolute or relative to the focused pane's cwd"),
+    line: z.number().int().min(1).optional().describe("1-based line to jump to"),
+    floating: z.boolean().optional().describe("Open as a floating pane instead of tiled"),
+    in_place_of_pane_id: paneTarget
+      .optional()
+      .describe(
+        'Temporarily replace this pane (an ID, or a selector such as "tag:editor" matching one pane); it returns when the editor exits'
+      ),
+  },
+  async (args) => {
+    const paneId = await openFileInEditor(args.path, {
+      line: args.line,
+      floating: args.floating,
+      inPlaceOfPaneId: args.in_place_of_pane_id,
+    });
+    const where = args.line === undefined ? args.path : `${args.path}:${args.line}`;
+    return {
+      content: [{ type: "text", text: `Opened ${where} in pane ${paneId}` }],
+    };
+  }
+);
+
+const toggleScratchTool = tool(
+  "toggle_scratch",
+  "Show, hide or bring to the current tab one of the user's configured scratch panes (scratch.<name>.command in the plugin config, e.g. a quick shell or htop), launching it if it isn't running. get_butler_state lists them under butler.scratch_panes.",
+  {
+    name: z.string().min(1).describe("Scratch pane name, e.g. top"),
+  },
+  async (args) => {
+    const result = await toggleScratch(args.name);
+    return {
+      content: [
+        {
+          type: "text",
+          text: `Scratch pane ${result.name}: ${result.action}${result.pane_id === null ? "" : ` (pane ${result.pane_id})`}`,
+        },
+      ],
+    };
+  }
+);
+
 const hidePaneByIdTool = tool(
   "hide_pane_by_id",
-  "Hide (suppress) a pane by ID through butler IPC without changing focus.",
+  "Hide (suppress) a pane by ID, or every pane a selector matches, through butler IPC without changing focus.",
   {
-    pane_id: z.number().int().min(1).describe("Terminal pane ID"),
+    pane_id: paneTarget,
   },
   async (args) => {
     await hidePaneById(args.pane_id);
@@ -334,9 +588,9 @@ const hidePaneByIdTool = tool(
 
 const showPaneByIdTool = tool(
   "show_pane_by_id",
-  "Show (unsuppress) a pane by ID through butler IPC.",
+  "Show (unsuppress) a pane by ID, or every pane a selector matches, through butler IPC.",
   {
-    pane_id: z.number().int().min(1).describe("Terminal pane ID"),
+    pane_id: paneTarget,
     should_float_if_hidden: z
       .boolean()
       .optional()
@@ -928,7 +1182,12 @@ export const zellijMcpServer = createSdkMcpServer({
     listTabs,
     listClients,
     getButlerStateTool,
+    getPaneInfoTool,
+    getActivityTool,
+    getFsChangesTool,
+    focusBackTool,
     getButlerTraceTool,
+    getButlerMetricsTool,
     clearButlerTraceTool,
     // Tab management
     goToTab,
@@ -941,6 +1200,15 @@ export const zellijMcpServer = createSdkMcpServer({
     renamePane,
     hidePaneByIdTool,
     showPaneByIdTool,
+    tagPaneTool,
+    untagPaneTool,
+    listTagsTool,
+    hidePanesTool,
+    floatPanesTool,
+    showPanesTool,
+    closePanesTool,
+    toggleScratchTool,
+    openFileTool,
     moveFocus,
     movePane,
     resizePane,
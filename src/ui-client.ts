@@ -21,9 +21,17 @@ import {
   type ZellijEnvContext,
 } from "./protocol.js";
 import { detectZellijBinary } from "./zellij.js";
+import {
+  registerButlerUi,
+  registerButlerUiPane,
+  sendButlerUiHeartbeat,
+  ZellijPipeError,
+} from "./zellijPipe.js";
 
 const EXIT_ALIASES = new Set(["exit", "bye", "quit", "q"]);
 const DAEMON_REGISTRATION_TIMEOUT_MS = 2_500;
+/** Well inside the butler's 30s `UI_HEARTBEAT_TIMEOUT_MS`. */
+const BUTLER_HEARTBEAT_INTERVAL_MS = 10_000;
 
 type UiSessionState = {
   currentModel: ModelAlias;
@@ -42,6 +50,48 @@ function normalizeString(value: unknown): string | undefined {
   return trimmed.length > 0 ? trimmed : undefined;
 }
 
+/**
+ * Panes launched by the butler carry a one-off token; echoing it back lets the
+ * butler tell this pane apart from anything that merely looks like Jelly J.
+ */
+async function registerWithButler(paneId: number): Promise<void> {
+  const token = normalizeString(process.env.JELLY_J_BUTLER_TOKEN);
+  if (!token) return;
+  try {
+    await registerButlerUiPane(paneId, token);
+  } catch {
+    // best effort: the butler falls back to its title/command heuristic
+  }
+}
+
+/**
+ * Registers this UI with the butler and heartbeats while the daemon connection is
+ * up, so a pane whose UI died is eventually replaced on Alt+j instead of shown.
+ * Returns a function that stops the heartbeat.
+ */
+function startButlerLiveness(clientId: string): () => void {
+  const paneId = Number(normalizeString(process.env.ZELLIJ_PANE_ID));
+  if (!Number.isInteger(paneId)) return () => {};
+
+  const register = async () => {
+    try {
+      await registerButlerUi(paneId, clientId, process.pid);
+    } catch {
+      // best effort: an old butler without liveness tracking
+    }
+  };
+  void registerWithButler(paneId).then(register);
+  const timer = setInterval(() => {
+    sendButlerUiHeartbeat(paneId).catch((error: unknown) => {
+      if (error instanceof ZellijPipeError && error.code === "ui_not_registered") {
+        void register();
+      }
+    });
+  }, BUTLER_HEARTBEAT_INTERVAL_MS);
+  timer.unref();
+  return () => clearInterval(timer);
+}
+
 function rolePrefix(role: HistoryEntry["role"]): string {
   if (role === "user") return "you";
   if (role === "assistant") return "jelly";
@@ -148,6 +198,7 @@ export async function runUiClient(): Promise<void> {
   });
 
   let started = false;
+  let stopButlerLiveness = () => {};
   let shuttingDown = false;
   let lastSigintAtMs = 0;
   let buffer = "";
@@ -178,6 +229,7 @@ export async function runUiClient(): Promise<void> {
         state.currentModel = message.model;
         if (!started) {
           started = true;
+          stopButlerLiveness = startButlerLiveness(clientId);
           display(renderWelcome(state.currentModel));
           rl.prompt();
         }
@@ -335,6 +387,8 @@ export async function runUiClient(): Promise<void> {
   });
 
   socket.on("close", () => {
+    // No daemon, no working prompt: let the butler see this UI as dead.
+    stopButlerLiveness();
     if (shuttingDown) return;
     if (registrationTimer) {
       clearTimeout(registrationTimer);
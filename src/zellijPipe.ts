@@ -1,11 +1,17 @@
 import { spawn } from "node:child_process";
 import os from "node:os";
 import path from "node:path";
+import type { butlerProtocolSchema } from "./butlerProtocol.generated.js";
 import { buildZellijEnv, resolveZellijBinary } from "./zellij.js";
 
 const REQUEST_TIMEOUT_MS = 8_000;
 const TOGGLE_TIMEOUT_MS = 3_000;
 
+/** Must match `PROTOCOL_VERSION` in plugin/src/protocol.rs. */
+export const BUTLER_PROTOCOL_VERSION = 1;
+
+let nextRequestId = 0;
+
 const DEFAULT_PLUGIN_URL = `file:${path.join(
   os.homedir(),
   ".config",
@@ -14,13 +20,15 @@ const DEFAULT_PLUGIN_URL = `file:${path.join(
   "jelly-j.wasm"
 )}`;
 
-type PipeResponse<T> =
+export type PipeResponse<T> =
   | {
       ok: true;
+      id?: string;
       result: T;
     }
   | {
       ok: false;
+      id?: string;
       code?: string;
       error: string;
     };
@@ -59,6 +67,17 @@ export interface ButlerTab {
   active: boolean;
   selectable_tiled_panes_count: number;
   selectable_floating_panes_count: number;
+  activity?: ButlerActivity;
+}
+
+/** What the butler observed since it loaded; see `plugin/src/activity.rs`. */
+export interface ButlerActivity {
+  first_seen_epoch_ms: number;
+  last_focused_epoch_ms?: number;
+  last_changed_epoch_ms: number;
+  focus_ms: number;
+  /** 0 while focused. */
+  idle_ms: number;
 }
 
 export interface ButlerPane {
@@ -71,6 +90,114 @@ export interface ButlerPane {
   is_floating: boolean;
   is_suppressed: boolean;
   exited: boolean;
+  cwd?: string;
+  foreground_command?: string;
+  tags: string[];
+  activity?: ButlerActivity;
+}
+
+/** A terminal pane id, or a selector such as `"tag:role:logs"` matching every tagged pane. */
+export type ButlerPaneTarget = number | string;
+
+export interface ButlerTagList {
+  tags: Array<{ tag: string; pane_ids: number[] }>;
+}
+
+/** Result of `hide_panes`, `show_panes`, `float_panes` and `close_panes`. */
+export interface ButlerBulkPaneResult {
+  pane_ids: number[];
+  dry_run: boolean;
+}
+
+/** One debounced burst of filesystem changes; paths are relative to the session cwd. */
+export interface ButlerFsChangeSummary {
+  seq: number;
+  first_epoch_ms: number;
+  last_epoch_ms: number;
+  created: string[];
+  updated: string[];
+  deleted: string[];
+  omitted: number;
+}
+
+export interface ButlerFsChanges {
+  watching: boolean;
+  include: string[];
+  exclude: string[];
+  summaries: ButlerFsChangeSummary[];
+}
+
+/** Result of `open_file`: the editor pane. */
+export interface ButlerOpenedFile {
+  pane_id: number;
+}
+
+/** Result of `toggle_scratch`. */
+export interface ButlerScratchToggle {
+  name: string;
+  pane_id: number | null;
+  action: "launch" | "show" | "hide" | "move" | "launch_skipped";
+}
+
+export interface ButlerPaneInfo extends ButlerPane {
+  geometry: { x: number; y: number; columns: number; rows: number };
+  is_fullscreen: boolean;
+  is_held: boolean;
+  exit_status?: number;
+  is_jelly: boolean;
+  registered_ui: boolean;
+}
+
+export interface ButlerTabNameSuggestion {
+  position: number;
+  current_name: string;
+  name: string;
+  reason: "repo" | "cwd" | "command";
+}
+
+export interface ButlerTabNameSuggestions {
+  mode: "suggest" | "apply";
+  suggestions: ButlerTabNameSuggestion[];
+}
+
+export type ButlerRuleTrigger =
+  | { event: "pane_exited"; command?: string; nonzero_only?: boolean }
+  | { event: "pane_opened"; command?: string; title?: string }
+  | { event: "tab_appeared"; name: string };
+
+/** Actions are request objects; `$pane_id` and `$tab_position` are filled in, also inside strings. */
+export interface ButlerRule {
+  id: string;
+  when: ButlerRuleTrigger;
+  actions?: Record<string, unknown>[];
+  /** Trace-only: a `warn` entry in the `rule` trace category, not shown in the UI. */
+  trace_message?: string;
+  /** Minimum time between two firings; defaults to 1000. */
+  cooldown_ms?: number;
+}
+
+export interface ButlerListedRule extends ButlerRule {
+  source: "config" | "file";
+  fired: number;
+  last_fired_epoch_ms?: number;
+}
+
+export interface ButlerRuleList {
+  path: string;
+  rules: ButlerListedRule[];
+}
+
+/** Tabs and terminal panes, stalest (largest `idle_ms`) first. */
+export interface ButlerActivityReport {
+  now_epoch_ms: number;
+  tabs: Array<{ position: number; name: string } & ButlerActivity>;
+  panes: Array<{ id: number; tab_index: number; title: string; is_jelly: boolean } & ButlerActivity>;
+}
+
+/** Most recent first, the current focus included; Jelly panes are not recorded. */
+export interface ButlerFocusHistory {
+  panes: Array<{ pane_id: number; tab_position: number; focused_epoch_ms: number }>;
+  tabs: Array<{ position: number; focused_epoch_ms: number }>;
 }
 
 export interface ButlerState {
@@ -78,20 +205,267 @@ export interface ButlerState {
   panes: ButlerPane[];
 }
 
+export type ButlerTraceLevel = "debug" | "info" | "warn" | "error";
+export type ButlerTraceCategory =
+  | "lifecycle"
+  | "permission"
+  | "pipe"
+  | "toggle"
+  | "request"
+  | "rule"
+  | "fs";
+
+export interface ButlerTraceEntry {
+  seq: number;
+  epoch_ms: number;
+  delta_ms: number;
+  level: ButlerTraceLevel;
+  category: ButlerTraceCategory;
+  message: string;
+  fields: Record<string, string>;
+}
+
+export interface ButlerTraceFilter {
+  limit?: number;
+  level?: ButlerTraceLevel;
+  category?: ButlerTraceCategory;
+  sinceSeq?: number;
+}
+
 type ButlerRequest =
+  | { op: "hello" }
+  | { op: "get_schema" }
   | { op: "ping" }
   | { op: "get_state" }
-  | { op: "get_trace"; limit?: number }
+  | { op: "get_metrics" }
+  | {
+      op: "get_trace";
+      limit?: number;
+      level?: ButlerTraceLevel;
+      category?: ButlerTraceCategory;
+      since_seq?: number;
+      format?: "text" | "json";
+    }
   | { op: "clear_trace" }
+  | { op: "export_trace"; path: string }
   | { op: "rename_tab"; position: number; name: string }
-  | { op: "rename_pane"; pane_id: number; name: string }
-  | { op: "hide_pane"; pane_id: number }
+  | { op: "rename_pane"; pane_id: ButlerPaneTarget; name: string }
+  | { op: "hide_pane"; pane_id: ButlerPaneTarget }
   | {
       op: "show_pane";
-      pane_id: number;
+      pane_id: ButlerPaneTarget;
       should_float_if_hidden?: boolean;
       should_focus_pane?: boolean;
-    };
+    }
+  | {
+      op: "batch";
+      requests: ButlerRequest[];
+      stop_on_error?: boolean;
+      atomic?: boolean;
+    }
+  | { op: "start_recording"; limit?: number }
+  | { op: "stop_recording" }
+  | { op: "get_recording"; since_seq?: number }
+  | { op: "register_ui_pane"; pane_id: number; token: string }
+  | { op: "register_ui"; pane_id: number; client_id: string; pid: number }
+  | { op: "ui_heartbeat"; pane_id: number }
+  | { op: "get_pane_info"; pane_id: ButlerPaneTarget }
+  | { op: "get_tab_name_suggestions" }
+  | { op: "list_rules" }
+  | { op: "add_rule"; rule: ButlerRule }
+  | { op: "remove_rule"; id: string }
+  | { op: "get_activity" }
+  | { op: "focus_back"; steps?: number }
+  | { op: "get_focus_history" }
+  | { op: "tag_pane"; pane_id: ButlerPaneTarget; tags: string[] }
+  | { op: "untag_pane"; pane_id: ButlerPaneTarget; tags?: string[] }
+  | { op: "list_tags" }
+  | { op: "hide_panes"; selector: string }
+  | {
+      op: "show_panes";
+      selector: string;
+      should_float_if_hidden?: boolean;
+      should_focus_pane?: boolean;
+    }
+  | { op: "float_panes"; selector: string }
+  | { op: "close_panes"; selector: string; dry_run?: boolean }
+  | { op: "toggle_scratch"; name: string; client_id?: number }
+  | {
+      op: "open_file";
+      path: string;
+      line?: number;
+      floating?: boolean;
+      in_place_of_pane_id?: ButlerPaneTarget;
+    }
+  | { op: "get_fs_changes"; since?: number };
+
+export type ButlerBatchStep = Exclude<ButlerRequest, { op: "batch" }>;
+
+export type ButlerStepResponse = PipeResponse<unknown>;
+
+export interface ButlerHello {
+  protocol_version: number;
+  crate_version: string;
+  supported_ops: string[];
+  permission_status: "granted" | "denied" | "inferred" | "pending";
+  granted_permissions: string[];
+}
+
+export interface ButlerBatchResult {
+  steps: ButlerStepResponse[];
+  completed: number;
+  stopped_early: boolean;
+}
+
+export interface ButlerLatencyHistogram {
+  counts: number[];
+  count: number;
+  sum_ms: number;
+  max_ms: number;
+}
+
+export interface ButlerMetrics {
+  uptime_ms: number;
+  requests_by_op: Record<string, number>;
+  errors_by_op: Record<string, number>;
+  errors_by_code: Record<string, number>;
+  toggles: {
+    received: number;
+    dedup_window_ignored: number;
+    duplicate_ignored: number;
+    executed: number;
+    launch_failures: number;
+    grace_period_timeouts: number;
+    zombies_replaced: number;
+  };
+  pane_exits: {
+    detected: number;
+    reruns: number;
+    closed: number;
+    backoffs: number;
+  };
+  latency_bucket_bounds_ms: number[];
+  toggle_latency_ms: Record<string, ButlerLatencyHistogram>;
+  events: Record<string, { count: number; per_minute: number }>;
+}
+
+export interface ButlerTraceExport {
+  path: string;
+  entries: number;
+  bytes: number;
+  persisted: boolean;
+}
+
+export interface ButlerRecording {
+  recording: boolean;
+  dropped: number;
+  entries: { seq: number; epoch_ms: number; event: { kind: string } }[];
+}
+
+// --- Protocol drift checks ---
+// The Rust protocol (plugin/src/protocol.rs) is the source of truth.
+// `UPDATE_SCHEMA=1 cargo test --test protocol_schema` regenerates butlerProtocol.generated.ts
+// from it; these types fail `npm run typecheck` if the hand-written request/response types
+// above stop matching the generated schema.
+
+type ButlerProtocolSchema = typeof butlerProtocolSchema;
+type SchemaRequestVariant = ButlerProtocolSchema["request"]["oneOf"][number];
+type SchemaOp = SchemaRequestVariant["properties"]["op"]["enum"][0];
+type SchemaRequestKeys<Op> = keyof Extract<
+  SchemaRequestVariant,
+  { properties: { op: { enum: readonly [Op] } } }
+>["properties"];
+type SchemaStateDefinitions = ButlerProtocolSchema["results"]["get_state"]["definitions"];
+
+type KeysEqual<A, B> = [A] extends [B] ? ([B] extends [A] ? true : false) : false;
+type AssertNoDrift<T extends never> = T;
+
+/** Ops whose TS shape is missing from, or disagrees with, the Rust schema. */
+type RequestDrift = {
+  [R in ButlerRequest as R["op"]]: R["op"] extends SchemaOp
+    ? KeysEqual<keyof R, SchemaRequestKeys<R["op"]>> extends true
+      ? never
+      : R["op"]
+    : R["op"];
+}[ButlerRequest["op"]];
+
+/** Response fields the TS side reads that the Rust side does not send. */
+type ResponseDrift =
+  | Exclude<keyof ButlerState, keyof ButlerProtocolSchema["results"]["get_state"]["properties"]>
+  | Exclude<keyof ButlerTab, keyof SchemaStateDefinitions["ButlerTabState"]["properties"]>
+  | Exclude<keyof ButlerPane, keyof SchemaStateDefinitions["ButlerPaneState"]["properties"]>
+  | Exclude<keyof ButlerPaneInfo, keyof ButlerProtocolSchema["results"]["get_pane_info"]["properties"]>
+  | Exclude<
+      keyof ButlerTabNameSuggestion,
+      keyof ButlerProtocolSchema["results"]["get_tab_name_suggestions"]["definitions"]["TabNameSuggestion"]["properties"]
+    >
+  | Exclude<keyof ButlerRuleList, keyof ButlerProtocolSchema["results"]["list_rules"]["properties"]>
+  | Exclude<
+      keyof ButlerListedRule,
+      keyof ButlerProtocolSchema["results"]["list_rules"]["definitions"]["ListedRule"]["properties"]
+    >
+  | Exclude<
+      keyof ButlerActivity,
+      keyof SchemaStateDefinitions["ButlerActivity"]["properties"]
+    >
+  | Exclude<
+      keyof ButlerActivityReport,
+      keyof ButlerProtocolSchema["results"]["get_activity"]["properties"]
+    >
+  | Exclude<
+      keyof ButlerActivityReport["panes"][number],
+      keyof ButlerProtocolSchema["results"]["get_activity"]["definitions"]["PaneActivity"]["properties"]
+    >
+  | Exclude<
+      keyof ButlerFocusHistory,
+      keyof ButlerProtocolSchema["results"]["get_focus_history"]["properties"]
+    >
+  | Exclude<
+      keyof ButlerFocusHistory["panes"][number],
+      keyof ButlerProtocolSchema["results"]["get_focus_history"]["definitions"]["PaneFocusEntry"]["properties"]
+    >
+  | Exclude<keyof ButlerTagList, keyof ButlerProtocolSchema["results"]["list_tags"]["properties"]>
+  | Exclude<keyof ButlerFsChanges, keyof ButlerProtocolSchema["results"]["get_fs_changes"]["properties"]>
+  | Exclude<
+      keyof ButlerFsChangeSummary,
+      keyof ButlerProtocolSchema["results"]["get_fs_changes"]["definitions"]["FsChangeSummary"]["properties"]
+    >
+  | Exclude<keyof ButlerOpenedFile, keyof ButlerProtocolSchema["results"]["open_file"]["properties"]>
+  | Exclude<
+      keyof ButlerScratchToggle,
+      keyof ButlerProtocolSchema["results"]["toggle_scratch"]["properties"]
+    >
+  | Exclude<
+      keyof ButlerBulkPaneResult,
+      keyof ButlerProtocolSchema["results"]["close_panes"]["properties"]
+    >
+  | Exclude<keyof ButlerHello, keyof ButlerProtocolSchema["results"]["hello"]["properties"]>
+  | Exclude<
+      keyof ButlerTraceEntry,
+      keyof ButlerProtocolSchema["results"]["get_trace"]["definitions"]["TraceEntry"]["properties"]
+    >
+  | Exclude<
+      keyof ButlerBatchResult,
+      keyof ButlerProtocolSchema["results"]["batch"]["properties"]
+    >
+  | Exclude<keyof ButlerMetrics, keyof ButlerProtocolSchema["results"]["get_metrics"]["properties"]>
+  | Exclude<
+      keyof ButlerMetrics["toggles"],
+      keyof ButlerProtocolSchema["results"]["get_metrics"]["definitions"]["ToggleMetrics"]["properties"]
+    >
+  | Exclude<
+      keyof ButlerTraceExport,
+      keyof ButlerProtocolSchema["results"]["export_trace"]["properties"]
+    >
+  | Exclude<
+      keyof ButlerRecording,
+      keyof ButlerProtocolSchema["results"]["get_recording"]["properties"]
+    >;
+
+export type ButlerProtocolDriftCheck = [
+  AssertNoDrift<RequestDrift>,
+  AssertNoDrift<ResponseDrift>,
+];
 
 function pluginUrl(): string {
   return process.env.JELLY_J_PLUGIN_URL?.trim() || DEFAULT_PLUGIN_URL;
@@ -100,6 +474,7 @@ function pluginUrl(): string {
 async function pipeRequest<T>(payload: ButlerRequest): Promise<T> {
   const binary = resolveZellijBinary();
   const env = buildZellijEnv();
+  const requestId = `${process.pid}-${++nextRequestId}`;
   const args = [
     "pipe",
     "--plugin",
@@ -107,7 +482,7 @@ async function pipeRequest<T>(payload: ButlerRequest): Promise<T> {
     "--name",
     "request",
     "--",
-    JSON.stringify(payload),
+    JSON.stringify({ ...payload, id: requestId }),
   ];
 
   let stdout: string;
@@ -168,6 +543,13 @@ async function pipeRequest<T>(payload: ButlerRequest): Promise<T> {
     );
   }
 
+  if (parsed.id !== undefined && parsed.id !== requestId) {
+    throw new ZellijPipeError(
+      `Butler pipe response id mismatch (expected ${requestId}, got ${parsed.id})`,
+      "id_mismatch"
+    );
+  }
+
   if (!parsed.ok) {
     throw new ZellijPipeError(parsed.error, parsed.code);
   }
@@ -175,6 +557,21 @@ async function pipeRequest<T>(payload: ButlerRequest): Promise<T> {
   return parsed.result;
 }
 
+export async function helloButler(): Promise<ButlerHello> {
+  const hello = await pipeRequest<ButlerHello>({ op: "hello" });
+  if (hello.protocol_version !== BUTLER_PROTOCOL_VERSION) {
+    throw new ZellijPipeError(
+      `Butler plugin speaks protocol v${hello.protocol_version}, expected v${BUTLER_PROTOCOL_VERSION}; reinstall jelly-j.wasm`,
+      "protocol_mismatch"
+    );
+  }
+  return hello;
+}
+
+export async function getButlerSchema(): Promise<unknown> {
+  return await pipeRequest<unknown>({ op: "get_schema" });
+}
+
 export async function pingButler(): Promise<void> {
   await pipeRequest<{ ok: true }>({ op: "ping" });
 }
@@ -183,6 +580,10 @@ export async function getButlerState(): Promise<ButlerState> {
   return await pipeRequest<ButlerState>({ op: "get_state" });
 }
 
+export async function getButlerMetrics(): Promise<ButlerMetrics> {
+  return await pipeRequest<ButlerMetrics>({ op: "get_metrics" });
+}
+
 export async function getButlerTrace(limit?: number): Promise<string[]> {
   const result = await pipeRequest<{ entries: string[] }>({
     op: "get_trace",
@@ -191,24 +592,95 @@ export async function getButlerTrace(limit?: number): Promise<string[]> {
   return result.entries;
 }
 
+export async function getButlerTraceEntries(
+  filter: ButlerTraceFilter = {}
+): Promise<ButlerTraceEntry[]> {
+  const result = await pipeRequest<{ entries: ButlerTraceEntry[] }>({
+    op: "get_trace",
+    limit: filter.limit,
+    level: filter.level,
+    category: filter.category,
+    since_seq: filter.sinceSeq,
+    format: "json",
+  });
+  return result.entries;
+}
+
 export async function clearButlerTrace(): Promise<void> {
   await pipeRequest<{ ok: true }>({ op: "clear_trace" });
 }
 
+/** `path` is as seen by the plugin and must be a file under `/data` or `/tmp`. */
+export async function exportButlerTrace(path: string): Promise<ButlerTraceExport> {
+  return await pipeRequest<ButlerTraceExport>({ op: "export_trace", path });
+}
+
 export async function renameTabByPosition(position: number, name: string): Promise<void> {
   await pipeRequest<{ ok: true }>({ op: "rename_tab", position, name });
 }
 
-export async function renamePaneById(paneId: number, name: string): Promise<void> {
+export async function renamePaneById(paneId: ButlerPaneTarget, name: string): Promise<void> {
   await pipeRequest<{ ok: true }>({ op: "rename_pane", pane_id: paneId, name });
 }
 
-export async function hidePaneById(paneId: number): Promise<void> {
+export async function getButlerPaneInfo(paneId: number): Promise<ButlerPaneInfo> {
+  return pipeRequest<ButlerPaneInfo>({ op: "get_pane_info", pane_id: paneId });
+}
+
+export async function getButlerTabNameSuggestions(): Promise<ButlerTabNameSuggestions> {
+  return pipeRequest<ButlerTabNameSuggestions>({ op: "get_tab_name_suggestions" });
+}
+
+export async function getButlerActivity(): Promise<ButlerActivityReport> {
+  return pipeRequest<ButlerActivityReport>({ op: "get_activity" });
+}
+
+/** Change summaries newer than `since` (a summary `seq`); empty unless `fs_watch` is on. */
+export async function getButlerFsChanges(since?: number): Promise<ButlerFsChanges> {
+  return pipeRequest<ButlerFsChanges>({ op: "get_fs_changes", since });
+}
+
+export async function getButlerFocusHistory(): Promise<ButlerFocusHistory> {
+  return pipeRequest<ButlerFocusHistory>({ op: "get_focus_history" });
+}
+
+/** Focuses the pane `steps` back in the focus history, not counting the current one. */
+export async function focusBack(steps = 1): Promise<{ pane_id: number; tab_position: number }> {
+  return pipeRequest<{ pane_id: number; tab_position: number }>({ op: "focus_back", steps });
+}
+
+export async function listButlerRules(): Promise<ButlerRuleList> {
+  return pipeRequest<ButlerRuleList>({ op: "list_rules" });
+}
+
+/** Saved to the plugin's rules file; rules from the plugin config are read-only. */
+export async function addButlerRule(rule: ButlerRule): Promise<void> {
+  await pipeRequest<{ ok: true }>({ op: "add_rule", rule });
+}
+
+export async function removeButlerRule(id: string): Promise<void> {
+  await pipeRequest<{ ok: true }>({ op: "remove_rule", id });
+}
+
+export async function listButlerTags(): Promise<ButlerTagList> {
+  return pipeRequest<ButlerTagList>({ op: "list_tags" });
+}
+
+export async function tagPane(paneId: ButlerPaneTarget, tags: string[]): Promise<void> {
+  await pipeRequest<{ ok: true }>({ op: "tag_pane", pane_id: paneId, tags });
+}
+
+/** Removes `tags`, or every tag when omitted. */
+export async function untagPane(paneId: ButlerPaneTarget, tags?: string[]): Promise<void> {
+  await pipeRequest<{ ok: true }>({ op: "untag_pane", pane_id: paneId, tags });
+}
+
+export async function hidePaneById(paneId: ButlerPaneTarget): Promise<void> {
   await pipeRequest<{ ok: true }>({ op: "hide_pane", pane_id: paneId });
 }
 
 export async function showPaneById(
-  paneId: number,
+  paneId: ButlerPaneTarget,
   shouldFloatIfHidden = true,
   shouldFocusPane = true
 ): Promise<void> {
@@ -220,6 +692,110 @@ export async function showPaneById(
   });
 }
 
+/** Hides every pane `selector` matches that is not hidden yet; Jelly panes are skipped. */
+export async function hidePanes(selector: string): Promise<ButlerBulkPaneResult> {
+  return pipeRequest<ButlerBulkPaneResult>({ op: "hide_panes", selector });
+}
+
+/** Floats every visible tiled pane `selector` matches; Jelly panes are skipped. */
+export async function floatPanes(selector: string): Promise<ButlerBulkPaneResult> {
+  return pipeRequest<ButlerBulkPaneResult>({ op: "float_panes", selector });
+}
+
+/** Shows every hidden pane `selector` matches, without focusing them by default. */
+export async function showPanes(
+  selector: string,
+  shouldFloatIfHidden = true,
+  shouldFocusPane = false
+): Promise<ButlerBulkPaneResult> {
+  return pipeRequest<ButlerBulkPaneResult>({
+    op: "show_panes",
+    selector,
+    should_float_if_hidden: shouldFloatIfHidden,
+    should_focus_pane: shouldFocusPane,
+  });
+}
+
+/** Closes every pane `selector` matches; `dryRun` only reports which ones would close. */
+export async function closePanes(
+  selector: string,
+  dryRun = false
+): Promise<ButlerBulkPaneResult> {
+  return pipeRequest<ButlerBulkPaneResult>({ op: "close_panes", selector, dry_run: dryRun });
+}
+
+/**
+ * Opens `path` in the user's `$EDITOR` at `line`, tiled by default, and returns the
+ * editor pane id. An `inPlaceOfPaneId` pane (an id, or a selector matching one pane)
+ * comes back when the editor exits.
+ */
+export async function openFileInEditor(
+  path: string,
+  options: { line?: number; floating?: boolean; inPlaceOfPaneId?: ButlerPaneTarget } = {}
+): Promise<number> {
+  const result = await pipeRequest<ButlerOpenedFile>({
+    op: "open_file",
+    path,
+    line: options.line,
+    floating: options.floating,
+    in_place_of_pane_id: options.inPlaceOfPaneId,
+  });
+  return result.pane_id;
+}
+
+/** Toggles the `scratch.<name>.command` pane like Alt+j toggles Jelly J. */
+export async function toggleScratch(name: string): Promise<ButlerScratchToggle> {
+  return pipeRequest<ButlerScratchToggle>({ op: "toggle_scratch", name });
+}
+
+export async function runButlerBatch(
+  requests: ButlerBatchStep[],
+  options: { stopOnError?: boolean; atomic?: boolean } = {}
+): Promise<ButlerBatchResult> {
+  return await pipeRequest<ButlerBatchResult>({
+    op: "batch",
+    requests,
+    stop_on_error: options.stopOnError,
+    atomic: options.atomic,
+  });
+}
+
+export async function startButlerRecording(limit?: number): Promise<void> {
+  await pipeRequest<{ ok: true }>({ op: "start_recording", limit });
+}
+
+export async function stopButlerRecording(): Promise<ButlerRecording> {
+  return await pipeRequest<ButlerRecording>({ op: "stop_recording" });
+}
+
+export async function getButlerRecording(sinceSeq?: number): Promise<ButlerRecording> {
+  return await pipeRequest<ButlerRecording>({ op: "get_recording", since_seq: sinceSeq });
+}
+
+/** Proves to the butler that `paneId` runs Jelly J, using the token it launched us with. */
+export async function registerButlerUiPane(paneId: number, token: string): Promise<void> {
+  await pipeRequest<{ ok: true }>({ op: "register_ui_pane", pane_id: paneId, token });
+}
+
+/** Starts butler liveness tracking for this UI's pane; see `sendButlerUiHeartbeat`. */
+export async function registerButlerUi(
+  paneId: number,
+  clientId: string,
+  pid: number
+): Promise<void> {
+  await pipeRequest<{ ok: true }>({
+    op: "register_ui",
+    pane_id: paneId,
+    client_id: clientId,
+    pid,
+  });
+}
+
+/** Fails with code `ui_not_registered` after a plugin reload; register again then. */
+export async function sendButlerUiHeartbeat(paneId: number): Promise<void> {
+  await pipeRequest<{ ok: true }>({ op: "ui_heartbeat", pane_id: paneId });
+}
+
 export async function toggleButler(): Promise<void> {
   try {
     await new Promise<void>((resolve, reject) => {
@@ -75,8 +75,9 @@ const REQUIRED_PERMISSIONS = [
   "ReadApplicationState",
   "ChangeApplicationState",
   "OpenTerminalsOrPlugins",
-  "WriteToStdin",
+  "RunCommands",
   "ReadCliPipes",
+  "OpenFiles",
 ];
 const DAEMON_SOCKET_PATH = path.join(os.homedir(), ".jelly-j", "daemon.sock");
 const CHAT_PROBE_ENABLED = process.env.JJ_CLI_HARNESS_CHAT_PROBE !== "0";